@@ -0,0 +1,175 @@
+use crate::chess::{Board, Move};
+use crate::engine::{format_score, Engine, Limits, MATE};
+use crate::pgn::{Annotation, Game};
+use std::io::{stdout, Write};
+
+pub const DEFAULT_REVIEW_DEPTH: i32 = 8;
+
+/// Centipawn scores are capped here, and mates count as this much
+const MAX_CP: i32 = 1000;
+
+/// Losses in winning chances, on a scale from -1 to 1, that earn a NAG
+const INACCURACY: f64 = 0.1;
+const MISTAKE: f64 = 0.2;
+const BLUNDER: f64 = 0.3;
+
+const NAG_MISTAKE: u8 = 2;
+const NAG_BLUNDER: u8 = 4;
+const NAG_INACCURACY: u8 = 6;
+
+#[derive(Debug, Clone, Default)]
+pub struct PlayerSummary {
+    pub moves: usize,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+    accuracy_sum: f64,
+}
+
+impl PlayerSummary {
+    /// Average accuracy of all moves from 0 to 100
+    pub fn accuracy(&self) -> f64 {
+        if self.moves == 0 {
+            100.0
+        } else {
+            self.accuracy_sum / self.moves as f64
+        }
+    }
+}
+
+pub struct Review {
+    pub game: Game,
+    /// White and black
+    pub players: [PlayerSummary; 2],
+}
+
+/// Score for the side to move with the best line, using the result when the
+/// game has ended
+fn evaluate(engine: &mut Engine, board: &Board, limits: &Limits) -> (i32, Vec<Move>) {
+    if let Some(outcome) = board.get_outcome() {
+        let score = match outcome.winner() {
+            0 => 0,
+            w if w == board.turn => MATE,
+            _ => -MATE,
+        };
+
+        return (score, Vec::new());
+    }
+
+    let result = engine.search(board, limits, &mut |_| ());
+    (result.score, result.pv)
+}
+
+/// Expected result from -1 to 1 for a score, following the usual logistic
+/// model of centipawns to winning chances
+fn winning_chances(score: i32) -> f64 {
+    let cp = score.clamp(-MAX_CP, MAX_CP);
+
+    2.0 / (1.0 + (-0.00368208 * cp as f64).exp()) - 1.0
+}
+
+/// Accuracy of a single move from the drop in win percentage it caused
+fn move_accuracy(before: f64, after: f64) -> f64 {
+    let drop = ((before - after) * 50.0).max(0.0);
+    (103.1668 * (-0.04354 * drop).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+/// Replays a game through the engine, annotating every move that lost
+/// winning chances with a NAG, the evaluation and the best line instead
+pub fn review_game(engine: &mut Engine, game: &Game, depth: i32) -> Option<Review> {
+    let mut board = game.start_board()?;
+    let limits = Limits {
+        depth: Some(depth),
+        ..Limits::default()
+    };
+
+    let mut review = Review {
+        game: game.clone(),
+        players: [PlayerSummary::default(), PlayerSummary::default()],
+    };
+
+    review.game.annotations = Vec::new();
+    engine.clear();
+
+    let (mut score, mut pv) = evaluate(engine, &board, &limits);
+
+    for (idx, mv) in game.moves.iter().enumerate() {
+        print!("\rAnalyzing move {}/{}", idx + 1, game.moves.len());
+        stdout().flush().unwrap();
+
+        let before = board.clone();
+        let mover = board.turn;
+
+        board.make_move(mv);
+
+        let (next_score, next_pv) = evaluate(engine, &board, &limits);
+
+        let chances_before = winning_chances(score);
+        let chances_after = winning_chances(-next_score);
+        let loss = chances_before - chances_after;
+
+        let summary = &mut review.players[mover as usize - 1];
+        summary.moves += 1;
+        summary.accuracy_sum += move_accuracy(chances_before, chances_after);
+
+        // A best move can look worse at the next ply only because of depth
+        let best = pv.first().filter(|best| *best != mv && loss >= INACCURACY);
+
+        let mut annotation = Annotation::default();
+
+        if let Some(best) = best {
+            let (nag, label) = if loss >= BLUNDER {
+                summary.blunders += 1;
+                (NAG_BLUNDER, "Blunder")
+            } else if loss >= MISTAKE {
+                summary.mistakes += 1;
+                (NAG_MISTAKE, "Mistake")
+            } else {
+                summary.inaccuracies += 1;
+                (NAG_INACCURACY, "Inaccuracy")
+            };
+
+            let white =
+                |score: i32, turn: i32| format_score(if turn == 1 { score } else { -score });
+
+            annotation.nag = Some(nag);
+            annotation.comment = Some(format!(
+                "{} ({} -> {}). {} was best.",
+                label,
+                white(score, mover),
+                white(next_score, board.turn),
+                before.to_san(best)
+            ));
+            annotation.variation = pv.clone();
+        }
+
+        review.game.annotations.push(annotation);
+        score = next_score;
+        pv = next_pv;
+    }
+
+    println!();
+
+    review.game.set_tag("Annotator", "rust-chess");
+    Some(review)
+}
+
+pub fn print_summary(review: &Review) {
+    for (idx, summary) in review.players.iter().enumerate() {
+        let color = if idx == 0 { "White" } else { "Black" };
+
+        let name = match review.game.tag(color) {
+            Some(name) if name != "?" => name,
+            _ => color,
+        };
+
+        println!(
+            "{}: accuracy {:.1}%, {} inaccuracies, {} mistakes, {} blunders",
+            name,
+            summary.accuracy(),
+            summary.inaccuracies,
+            summary.mistakes,
+            summary.blunders
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use crate::chess::{Board, Piece};
+use crate::engine::Clock;
+use crate::net::Interface;
+use crate::pgn::{Annotation, Game};
+use crate::syzygy::{Tablebase, Wdl};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Time allowed over the clock before a move counts as a time forfeit
+const TIME_MARGIN: Duration = Duration::from_millis(50);
+
+/// Rules for ending games early once the result is clear
+#[derive(Clone)]
+pub struct Adjudication {
+    /// Score both engines must agree on for a resignation, 0 to disable
+    pub resign_score: i32,
+    pub resign_moves: usize,
+    /// First move number where draws may be adjudicated, 0 to disable
+    pub draw_move: i32,
+    pub draw_score: i32,
+    pub draw_plies: usize,
+    /// Games are drawn after this many moves, 0 for no limit
+    pub max_moves: i32,
+    pub tablebase: Option<Arc<Tablebase>>,
+}
+
+impl Default for Adjudication {
+    fn default() -> Adjudication {
+        Adjudication {
+            resign_score: 1000,
+            resign_moves: 3,
+            draw_move: 40,
+            draw_score: 10,
+            draw_plies: 8,
+            max_moves: 200,
+            tablebase: None,
+        }
+    }
+}
+
+/// How a game ended, with the winner or 0 for a draw
+pub struct GameResult {
+    pub board: Board,
+    pub winner: i32,
+    pub reason: String,
+    pub termination: &'static str,
+}
+
+impl GameResult {
+    fn new(board: Board, winner: i32, reason: &str, termination: &'static str) -> GameResult {
+        GameResult {
+            board,
+            winner,
+            reason: reason.to_string(),
+            termination,
+        }
+    }
+
+    /// The game with its result, ending in a comment saying why
+    pub fn to_game(&self) -> Game {
+        let mut game = Game::from_board(&self.board);
+
+        game.result = match self.winner {
+            1 => "1-0",
+            2 => "0-1",
+            _ => "1/2-1/2",
+        }
+        .to_string();
+
+        game.set_tag("Result", &game.result.clone());
+        game.set_tag("Termination", self.termination);
+
+        if !game.moves.is_empty() {
+            game.annotations = vec![Annotation::default(); game.moves.len()];
+            game.annotations.last_mut().unwrap().comment = Some(self.reason.clone());
+        }
+
+        game
+    }
+}
+
+/// Consecutive moves each engine has claimed a clear result
+#[derive(Default)]
+struct Claims {
+    losing: [usize; 2],
+    winning: [usize; 2],
+    drawn: usize,
+}
+
+/// Plays a game between two interfaces from a start position until the
+/// rules or the adjudication settings decide it
+pub fn play_game(
+    white: &mut dyn Interface,
+    black: &mut dyn Interface,
+    start: &Board,
+    clock: Option<Clock>,
+    adjudication: &Adjudication,
+) -> GameResult {
+    let mut board = start.clone_with_history();
+    let mut clock = clock;
+    let mut claims = Claims::default();
+
+    white.new_game(start);
+    black.new_game(start);
+
+    loop {
+        if let Some(outcome) = board.get_outcome() {
+            let reason = outcome.to_string();
+            return GameResult::new(board, outcome.winner(), &reason, "normal");
+        }
+
+        if board.halfmove_clock >= 100 {
+            return GameResult::new(board, 0, "Fifty-move rule", "normal");
+        }
+
+        if adjudication.max_moves > 0 && board.fullmove_number > adjudication.max_moves {
+            return GameResult::new(board, 0, "Move limit reached", "adjudication");
+        }
+
+        if let Some((winner, reason)) = adjudicate_tablebase(&board, adjudication) {
+            return GameResult::new(board, winner, reason, "adjudication");
+        }
+
+        let turn = board.turn;
+        let opponent = if turn == 1 { 2 } else { 1 };
+
+        let (player, other): (&mut dyn Interface, &mut dyn Interface) = if turn == 1 {
+            (&mut *white, &mut *black)
+        } else {
+            (&mut *black, &mut *white)
+        };
+
+        if let Some(clock) = clock {
+            player.set_clock(clock);
+        }
+
+        let start = Instant::now();
+
+        if !player.wait(&mut board) {
+            let reason = if turn == 1 {
+                "White forfeits"
+            } else {
+                "Black forfeits"
+            };
+
+            return GameResult::new(board, opponent, reason, "rules infraction");
+        }
+
+        if let Some(clock) = clock.as_mut() {
+            let elapsed = start.elapsed().saturating_sub(TIME_MARGIN);
+
+            if !clock.spend(turn, elapsed) {
+                return GameResult::new(board, opponent, "Loss on time", "time forfeit");
+            }
+        }
+
+        // A remote player picks the promotion piece separately
+        if board.turn == turn {
+            continue;
+        }
+
+        if let Some(mv) = board.prev_move {
+            other.send_move(&mv.with_promotion(Piece::None));
+
+            if mv.promotion != Piece::None {
+                other.send_promote(mv.promotion);
+            }
+        }
+
+        if let Some((winner, reason)) =
+            adjudicate_score(&board, player.last_score(), &mut claims, adjudication)
+        {
+            return GameResult::new(board, winner, reason, "adjudication");
+        }
+    }
+}
+
+/// Winner by the tables once few enough pieces are left
+fn adjudicate_tablebase(board: &Board, adjudication: &Adjudication) -> Option<(i32, &'static str)> {
+    let wdl = adjudication.tablebase.as_ref()?.probe_wdl(board)?;
+    let opponent = if board.turn == 1 { 2 } else { 1 };
+
+    let winner = match wdl {
+        Wdl::Win => board.turn,
+        Wdl::Loss => opponent,
+        _ => 0,
+    };
+
+    Some((winner, "Tablebase adjudication"))
+}
+
+/// Resigns for an engine when both agree it is lost, and draws when both
+/// have seen a level position for long enough
+fn adjudicate_score(
+    board: &Board,
+    score: Option<i32>,
+    claims: &mut Claims,
+    adjudication: &Adjudication,
+) -> Option<(i32, &'static str)> {
+    // The player who just moved
+    let mover = if board.turn == 1 { 2 } else { 1 };
+    let idx = mover as usize - 1;
+
+    let score = match score {
+        Some(score) => score,
+        None => {
+            *claims = Claims::default();
+            return None;
+        }
+    };
+
+    let resign = adjudication.resign_score;
+
+    if resign > 0 {
+        claims.losing[idx] = if score <= -resign {
+            claims.losing[idx] + 1
+        } else {
+            0
+        };
+        claims.winning[idx] = if score >= resign {
+            claims.winning[idx] + 1
+        } else {
+            0
+        };
+
+        let count = adjudication.resign_moves;
+
+        for (loser, winner) in [(0, 1), (1, 0)].iter() {
+            if claims.losing[*loser] >= count && claims.winning[*winner] >= count {
+                let reason = if *loser == 0 {
+                    "White resigns"
+                } else {
+                    "Black resigns"
+                };
+
+                return Some((*winner as i32 + 1, reason));
+            }
+        }
+    }
+
+    if adjudication.draw_move > 0 && board.fullmove_number >= adjudication.draw_move {
+        if score.abs() <= adjudication.draw_score {
+            claims.drawn += 1;
+        } else {
+            claims.drawn = 0;
+        }
+
+        if claims.drawn >= adjudication.draw_plies {
+            return Some((0, "Draw by adjudication"));
+        }
+    }
+
+    None
+}
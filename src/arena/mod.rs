@@ -0,0 +1,337 @@
+mod game;
+mod stats;
+
+pub use self::game::{play_game, Adjudication, GameResult};
+pub use self::stats::{Score, Sprt};
+
+use crate::chess::{Board, Variant};
+use crate::engine::{Clock, Engine, Limits, Skill, DEFAULT_HASH_MB, MAX_LEVEL};
+use crate::net::{ComputerInterface, Interface, UciInterface};
+use crate::pgn;
+use crate::syzygy::Tablebase;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+/// The built-in engine at some level, or an external UCI engine command
+#[derive(Debug, Clone)]
+pub enum EngineSpec {
+    Internal(i32),
+    External(String),
+}
+
+impl EngineSpec {
+    /// `internal`, `internal:<level>` or a command line
+    pub fn from(spec: &str) -> Option<EngineSpec> {
+        if spec == "internal" {
+            return Some(EngineSpec::Internal(MAX_LEVEL));
+        }
+
+        if let Some(level) = spec.strip_prefix("internal:") {
+            return match level.parse::<i32>() {
+                Ok(level) if (0..=MAX_LEVEL).contains(&level) => Some(EngineSpec::Internal(level)),
+                _ => None,
+            };
+        }
+
+        if spec.trim().is_empty() {
+            None
+        } else {
+            Some(EngineSpec::External(spec.to_string()))
+        }
+    }
+
+    /// Starts the engine with its name for the PGN tags
+    pub fn create(
+        &self,
+        limits: Limits,
+        threads: usize,
+        tablebase: &Option<Arc<Tablebase>>,
+    ) -> io::Result<(Box<dyn Interface>, String)> {
+        match self {
+            EngineSpec::Internal(level) => {
+                let mut engine = Engine::new(DEFAULT_HASH_MB);
+                engine.set_threads(threads);
+                engine.set_tablebase(tablebase.clone());
+                engine.set_skill(Some(Skill::new(*level)));
+
+                let name = if *level == MAX_LEVEL {
+                    String::from("rust-chess")
+                } else {
+                    format!("rust-chess level {}", level)
+                };
+
+                Ok((Box::new(ComputerInterface::new(engine, limits, None)), name))
+            }
+            EngineSpec::External(command) => {
+                let uci = UciInterface::spawn(command, limits)?;
+                let name = uci.name.clone();
+                Ok((Box::new(uci), name))
+            }
+        }
+    }
+}
+
+/// Start positions from a PGN file, using the first `plies` moves of each
+/// game, or from a file with one FEN or EPD position per line
+pub fn load_openings(path: &str, plies: usize, variant: Variant) -> io::Result<Vec<Board>> {
+    let mut openings = Vec::new();
+
+    if path.to_lowercase().ends_with(".pgn") {
+        for game in pgn::read_file(path)? {
+            if let Some(mut board) = game.start_board() {
+                for mv in game.moves.iter().take(plies) {
+                    board.make_move(mv);
+                }
+
+                openings.push(board);
+            }
+        }
+    } else {
+        for line in std::fs::read_to_string(path)?.lines() {
+            if let Some(board) = Board::from_fen(line, variant) {
+                openings.push(board);
+            }
+        }
+    }
+
+    Ok(openings)
+}
+
+/// Time control as `<seconds>[+<increment>]`, such as "10+0.1"
+pub fn parse_time_control(tc: &str) -> Option<Clock> {
+    let mut parts = tc.splitn(2, '+');
+    let time = parts.next()?.parse::<f64>().ok()?;
+    let increment = parts.next().unwrap_or("0").parse::<f64>().ok()?;
+
+    if time <= 0.0 || increment < 0.0 {
+        return None;
+    }
+
+    Some(Clock::new(
+        Duration::from_secs_f64(time),
+        Duration::from_secs_f64(increment),
+    ))
+}
+
+pub fn format_time_control(clock: &Clock) -> String {
+    let time = clock.white.as_secs_f64();
+    let increment = clock.increment.as_secs_f64();
+
+    if increment > 0.0 {
+        format!("{}+{}", time, increment)
+    } else {
+        format!("{}", time)
+    }
+}
+
+pub struct MatchSettings {
+    pub engines: [EngineSpec; 2],
+    pub games: usize,
+    pub concurrency: usize,
+    pub openings: Vec<Board>,
+    pub variant: Variant,
+    pub clock: Clock,
+    pub adjudication: Adjudication,
+    pub pgn_out: Option<String>,
+    pub sprt: Sprt,
+    pub threads: usize,
+}
+
+struct Finished {
+    round: usize,
+    names: [String; 2],
+    result: GameResult,
+}
+
+/// Plays games in pairs from each opening with colours reversed, printing
+/// the score, Elo difference and SPRT state after every game. Stops early
+/// once the SPRT accepts either hypothesis.
+pub fn run_match(settings: &MatchSettings) {
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+
+    let mut pgn_file = match &settings.pgn_out {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                println!("Could not open '{}': {}", path, err);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..settings.concurrency.max(1) {
+            let sender = sender.clone();
+            let next = &next;
+            let stop = &stop;
+
+            scope.spawn(move || run_worker(settings, next, stop, sender));
+        }
+
+        drop(sender);
+
+        let mut score = Score::default();
+        let (lower, upper) = settings.sprt.bounds();
+
+        for finished in receiver {
+            let engine_white = finished.round.is_multiple_of(2);
+
+            let points = match finished.result.winner {
+                0 => 0.5,
+                1 if engine_white => 1.0,
+                2 if !engine_white => 1.0,
+                _ => 0.0,
+            };
+
+            score.add(points);
+
+            let game = finished_game(&finished, settings);
+
+            println!(
+                "Game {} of {}: {} vs {} {} ({})",
+                finished.round + 1,
+                settings.games,
+                finished.names[0],
+                finished.names[1],
+                game.result,
+                finished.result.reason
+            );
+
+            if let Some(file) = pgn_file.as_mut() {
+                if let Err(err) = writeln!(file, "{}", game.to_pgn()) {
+                    println!("Could not write PGN: {}", err);
+                }
+            }
+
+            let engine_names = if engine_white {
+                (&finished.names[0], &finished.names[1])
+            } else {
+                (&finished.names[1], &finished.names[0])
+            };
+
+            println!(
+                "Score of {} vs {}: {} - {} - {} [{:.3}] {}",
+                engine_names.0,
+                engine_names.1,
+                score.wins,
+                score.losses,
+                score.draws,
+                score.ratio(),
+                score.games()
+            );
+
+            match score.elo() {
+                Some((elo, margin)) => println!("Elo difference: {:.1} +/- {:.1}", elo, margin),
+                None => println!("Elo difference: unknown"),
+            }
+
+            println!(
+                "SPRT: llr {:.2} ({:.2}, {:.2}) [{}, {}]",
+                settings.sprt.llr(&score),
+                lower,
+                upper,
+                settings.sprt.elo0,
+                settings.sprt.elo1
+            );
+
+            if let Some(accepted) = settings.sprt.conclusion(&score) {
+                let elo = if accepted {
+                    settings.sprt.elo1
+                } else {
+                    settings.sprt.elo0
+                };
+
+                println!("SPRT: H{} accepted (elo {})", accepted as i32, elo);
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+    });
+}
+
+fn finished_game(finished: &Finished, settings: &MatchSettings) -> pgn::Game {
+    let mut game = finished.result.to_game();
+
+    game.set_tag("Event", "Engine match");
+    game.set_tag("Round", &(finished.round + 1).to_string());
+    game.set_tag("White", &finished.names[0]);
+    game.set_tag("Black", &finished.names[1]);
+    game.set_tag("TimeControl", &format_time_control(&settings.clock));
+    game
+}
+
+/// Plays games until they run out or the match is stopped, with its own
+/// pair of engines
+fn run_worker(
+    settings: &MatchSettings,
+    next: &AtomicUsize,
+    stop: &AtomicBool,
+    sender: mpsc::Sender<Finished>,
+) {
+    let limits = Limits::default();
+    let mut engines = Vec::new();
+
+    for spec in settings.engines.iter() {
+        match spec.create(limits, settings.threads, &settings.adjudication.tablebase) {
+            Ok(engine) => engines.push(engine),
+            Err(err) => {
+                println!("Could not start engine {:?}: {}", spec, err);
+                stop.store(true, Ordering::Relaxed);
+                return;
+            }
+        }
+    }
+
+    let (mut second, second_name) = engines.pop().unwrap();
+    let (mut first, first_name) = engines.pop().unwrap();
+
+    while !stop.load(Ordering::Relaxed) {
+        let round = next.fetch_add(1, Ordering::Relaxed);
+
+        if round >= settings.games {
+            break;
+        }
+
+        let start = match settings.openings.len() {
+            0 => Board::new_variant(settings.variant),
+            len => settings.openings[(round / 2) % len].clone_with_history(),
+        };
+
+        // The first engine is white in even rounds
+        let (result, names) = if round.is_multiple_of(2) {
+            let result = play_game(
+                first.as_mut(),
+                second.as_mut(),
+                &start,
+                Some(settings.clock),
+                &settings.adjudication,
+            );
+            (result, [first_name.clone(), second_name.clone()])
+        } else {
+            let result = play_game(
+                second.as_mut(),
+                first.as_mut(),
+                &start,
+                Some(settings.clock),
+                &settings.adjudication,
+            );
+            (result, [second_name.clone(), first_name.clone()])
+        };
+
+        let finished = Finished {
+            round,
+            names,
+            result,
+        };
+
+        if sender.send(finished).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,124 @@
+/// Wins, losses and draws from the first engine's point of view
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Score {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Score {
+    pub fn add(&mut self, points: f64) {
+        if points > 0.5 {
+            self.wins += 1;
+        } else if points < 0.5 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Average points per game
+    pub fn ratio(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// Variance of the points of a single game
+    fn variance(&self) -> f64 {
+        let n = self.games() as f64;
+        let mean = self.ratio();
+        let square = (self.wins as f64 + self.draws as f64 / 4.0) / n;
+        square - mean * mean
+    }
+
+    /// Elo difference with its 95% confidence margin, once both sides have
+    /// scored
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let ratio = self.ratio();
+
+        if self.games() == 0 || ratio <= 0.0 || ratio >= 1.0 {
+            return None;
+        }
+
+        let deviation = (self.variance() / self.games() as f64).sqrt();
+        let low = (ratio - 1.96 * deviation).max(0.001);
+        let high = (ratio + 1.96 * deviation).min(0.999);
+
+        Some((
+            ratio_to_elo(ratio),
+            (ratio_to_elo(high) - ratio_to_elo(low)) / 2.0,
+        ))
+    }
+}
+
+fn ratio_to_elo(ratio: f64) -> f64 {
+    -400.0 * (1.0 / ratio - 1.0).log10()
+}
+
+fn elo_to_ratio(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Sequential probability ratio test of the hypotheses that the first
+/// engine is `elo0` or `elo1` stronger
+#[derive(Debug, Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Sprt {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// Log-likelihood ratio using the normal approximation of the score
+    pub fn llr(&self, score: &Score) -> f64 {
+        let variance = score.variance();
+
+        if score.games() == 0 || variance <= 0.0 {
+            return 0.0;
+        }
+
+        let s0 = elo_to_ratio(self.elo0);
+        let s1 = elo_to_ratio(self.elo1);
+
+        score.games() as f64 * (s1 - s0) * (2.0 * score.ratio() - s0 - s1) / (2.0 * variance)
+    }
+
+    /// Lower and upper LLR bounds for accepting `elo0` and `elo1`
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// True when `elo1` is accepted and false when `elo0` is
+    pub fn conclusion(&self, score: &Score) -> Option<bool> {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+
+        if llr >= upper {
+            Some(true)
+        } else if llr <= lower {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,257 @@
+mod keys;
+mod writer;
+
+pub use self::writer::build_book;
+
+use self::keys::RANDOM64;
+use crate::chess::{Board, Move, Piece, Position, Variant};
+use rand::Rng;
+use std::fs;
+use std::io;
+
+const ENTRY_SIZE: usize = 16;
+
+pub const DEFAULT_BOOK_DEPTH: i32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    /// Picks moves at random in proportion to their weights
+    Weighted,
+    /// Always plays the move with the highest weight
+    Best,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl Entry {
+    fn from(bytes: &[u8]) -> Entry {
+        let mut key = [0; 8];
+        key.copy_from_slice(&bytes[0..8]);
+
+        Entry {
+            key: u64::from_be_bytes(key),
+            mv: u16::from_be_bytes([bytes[8], bytes[9]]),
+            weight: u16::from_be_bytes([bytes[10], bytes[11]]),
+            learn: u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+/// An opening book in the Polyglot format, entries sorted by key
+pub struct Book {
+    entries: Vec<Entry>,
+    pub selection: Selection,
+    /// Last full move number the book is consulted for
+    pub depth: i32,
+}
+
+impl Book {
+    pub fn new(entries: Vec<Entry>) -> Book {
+        Book {
+            entries,
+            selection: Selection::Weighted,
+            depth: DEFAULT_BOOK_DEPTH,
+        }
+    }
+
+    pub fn open(path: &str) -> io::Result<Book> {
+        let bytes = fs::read(path)?;
+
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file size is not a multiple of 16 bytes",
+            ));
+        }
+
+        let entries = bytes.chunks(ENTRY_SIZE).map(Entry::from).collect();
+        Ok(Book::new(entries))
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * ENTRY_SIZE);
+
+        for entry in self.entries.iter() {
+            bytes.extend_from_slice(&entry.to_bytes());
+        }
+
+        fs::write(path, bytes)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Legal book moves for the position together with their weights
+    pub fn probe(&self, board: &Board) -> Vec<(Move, u16)> {
+        if board.variant != Variant::Standard {
+            return Vec::new();
+        }
+
+        let key = polyglot_key(board);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let legal = board.get_legal_moves();
+
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                let mv = decode_move(board, entry.mv)?;
+                legal.iter().find(|m| **m == mv)?;
+                Some((mv, entry.weight))
+            })
+            .collect()
+    }
+
+    /// Picks a book move according to the selection mode and depth limit
+    pub fn choose(&self, board: &Board) -> Option<Move> {
+        if board.fullmove_number > self.depth {
+            return None;
+        }
+
+        let moves: Vec<(Move, u16)> = self
+            .probe(board)
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0)
+            .collect();
+
+        match self.selection {
+            Selection::Best => moves
+                .iter()
+                .max_by_key(|(_, weight)| *weight)
+                .map(|(mv, _)| *mv),
+            Selection::Weighted => {
+                let total: u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+
+                if total == 0 {
+                    return None;
+                }
+
+                let mut pick = rand::thread_rng().gen_range(0, total);
+
+                for (mv, weight) in moves.iter() {
+                    if pick < *weight as u32 {
+                        return Some(*mv);
+                    }
+
+                    pick -= *weight as u32;
+                }
+
+                None
+            }
+        }
+    }
+}
+
+/// Position key using the standard Polyglot random numbers
+pub fn polyglot_key(board: &Board) -> u64 {
+    let mut key = 0;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let square = board.get(x, y);
+
+            let kind = match square.piece {
+                Piece::Pawn => 0,
+                Piece::Knight => 2,
+                Piece::Bishop => 4,
+                Piece::Rook => 6,
+                Piece::Queen => 8,
+                Piece::King => 10,
+                Piece::None => continue,
+            };
+
+            let kind = kind + if square.player == 1 { 1 } else { 0 };
+            key ^= RANDOM64[64 * kind + 8 * y as usize + x as usize];
+        }
+    }
+
+    for (idx, right) in board.castling_rights().iter().enumerate() {
+        if *right {
+            key ^= RANDOM64[768 + idx];
+        }
+    }
+
+    // The en passant file only counts if a pawn can actually capture there
+    if let Some(target) = board.en_passant {
+        let y = if board.turn == 1 { 4 } else { 3 };
+
+        let capturer = [target.x - 1, target.x + 1].iter().any(|&x| {
+            (0..8).contains(&x) && {
+                let square = board.get(x, y);
+                square.piece == Piece::Pawn && square.player == board.turn
+            }
+        });
+
+        if capturer {
+            key ^= RANDOM64[772 + target.x as usize];
+        }
+    }
+
+    if board.turn == 1 {
+        key ^= RANDOM64[780];
+    }
+
+    key
+}
+
+/// Encodes a move, writing castling as the king capturing its own rook
+pub fn encode_move(board: &Board, mv: &Move) -> u16 {
+    let mut to = mv.to;
+
+    if board.getp(&mv.from).piece == Piece::King && (mv.to.x - mv.from.x).abs() == 2 {
+        to.x = if mv.to.x > mv.from.x { 7 } else { 0 };
+    }
+
+    let promotion = match mv.promotion {
+        Piece::Knight => 1,
+        Piece::Bishop => 2,
+        Piece::Rook => 3,
+        Piece::Queen => 4,
+        _ => 0,
+    };
+
+    (to.x | to.y << 3 | mv.from.x << 6 | mv.from.y << 9 | promotion << 12) as u16
+}
+
+pub fn decode_move(board: &Board, data: u16) -> Option<Move> {
+    let data = data as i32;
+    let from = Position::new((data >> 6) & 7, (data >> 9) & 7);
+    let mut to = Position::new(data & 7, (data >> 3) & 7);
+
+    let promotion = match (data >> 12) & 7 {
+        1 => Piece::Knight,
+        2 => Piece::Bishop,
+        3 => Piece::Rook,
+        4 => Piece::Queen,
+        _ => Piece::None,
+    };
+
+    let king = board.getp(&from);
+    let rook = board.getp(&to);
+
+    if king.piece == Piece::King
+        && rook.piece == Piece::Rook
+        && king.player == rook.player
+        && from.y == to.y
+    {
+        to.x = if to.x > from.x { 6 } else { 2 };
+    }
+
+    Some(Move::new(from, to)?.with_promotion(promotion))
+}
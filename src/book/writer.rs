@@ -0,0 +1,63 @@
+use super::{encode_move, polyglot_key, Book, Entry};
+use crate::chess::Variant;
+use crate::pgn::Game;
+use std::collections::HashMap;
+
+/// Builds a book from finished standard games, scoring each move with two
+/// points for a win and one for a draw from the mover's point of view
+pub fn build_book(games: &[Game], max_ply: usize) -> Book {
+    let mut scores: HashMap<(u64, u16), u64> = HashMap::new();
+
+    for game in games.iter() {
+        let winner = match game.winner() {
+            Some(winner) => winner,
+            None => continue,
+        };
+
+        if game.variant() != Variant::Standard {
+            continue;
+        }
+
+        let mut board = match game.start_board() {
+            Some(board) => board,
+            None => continue,
+        };
+
+        for mv in game.moves.iter().take(max_ply) {
+            let points = match winner {
+                0 => 1,
+                w if w == board.turn => 2,
+                _ => 0,
+            };
+
+            let key = (polyglot_key(&board), encode_move(&board, mv));
+            *scores.entry(key).or_insert(0) += points;
+            board.make_move(mv);
+        }
+    }
+
+    let max = scores.values().copied().max().unwrap_or(0);
+
+    let mut entries: Vec<Entry> = scores
+        .into_iter()
+        .filter(|(_, score)| *score > 0)
+        .map(|((key, mv), score)| {
+            let weight = if max > u16::MAX as u64 {
+                (score * u16::MAX as u64 / max).max(1)
+            } else {
+                score
+            };
+
+            Entry {
+                key,
+                mv,
+                weight: weight as u16,
+                learn: 0,
+            }
+        })
+        .collect();
+
+    entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+
+    Book::new(entries)
+}
@@ -1,7 +1,8 @@
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
-use crate::chess::{Move, Piece, Position, Square};
+use crate::chess::zobrist;
+use crate::chess::{Move, Outcome, Piece, Pocket, Position, Square, Variant};
 
 #[derive(Debug)]
 pub struct Board {
@@ -10,6 +11,30 @@ pub struct Board {
     pub cur_moves: Vec<Move>,
     pub prev_move: Option<Move>,
     pub grid: [[Square; 8]; 8],
+    pub variant: Variant,
+    pub pockets: [Pocket; 2],
+    pub en_passant: Option<Position>,
+    /// Whether the side to move can legally take on `en_passant`, worked
+    /// out when the pawn moves so hashing never has to generate moves
+    en_passant_capturable: bool,
+    pub halfmove_clock: i32,
+    pub fullmove_number: i32,
+    pub hash: u64,
+    history: Vec<State>,
+}
+
+/// Everything needed to take back a move
+#[derive(Debug, Clone, Copy)]
+struct State {
+    turn: i32,
+    prev_move: Option<Move>,
+    grid: [[Square; 8]; 8],
+    pockets: [Pocket; 2],
+    en_passant: Option<Position>,
+    en_passant_capturable: bool,
+    halfmove_clock: i32,
+    fullmove_number: i32,
+    hash: u64,
 }
 
 impl Clone for Board {
@@ -20,46 +45,98 @@ impl Clone for Board {
             cur_moves: Vec::new(),
             prev_move: None,
             grid: self.grid.clone(),
+            variant: self.variant,
+            pockets: self.pockets,
+            en_passant: self.en_passant,
+            en_passant_capturable: self.en_passant_capturable,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+            history: Vec::new(),
         }
     }
 }
 
 impl Board {
+    #[allow(dead_code)]
     pub fn new() -> Board {
-        Board {
+        Board::new_variant(Variant::Standard)
+    }
+
+    pub fn new_variant(variant: Variant) -> Board {
+        let grid = match variant {
+            Variant::RacingKings => Board::racing_kings_grid(),
+            _ => Board::standard_grid(),
+        };
+
+        let mut board = Board {
             turn: 1,
             cur_pos: None,
             cur_moves: Vec::new(),
             prev_move: None,
-            grid: [
-                [
-                    Square::from(Piece::Rook, 1),
-                    Square::from(Piece::Knight, 1),
-                    Square::from(Piece::Bishop, 1),
-                    Square::from(Piece::Queen, 1),
-                    Square::from(Piece::King, 1),
-                    Square::from(Piece::Bishop, 1),
-                    Square::from(Piece::Knight, 1),
-                    Square::from(Piece::Rook, 1),
-                ],
-                [Square::from(Piece::Pawn, 1); 8],
-                [Square::from(Piece::None, 0); 8],
-                [Square::from(Piece::None, 0); 8],
-                [Square::from(Piece::None, 0); 8],
-                [Square::from(Piece::None, 0); 8],
-                [Square::from(Piece::Pawn, 2); 8],
-                [
-                    Square::from(Piece::Rook, 2),
-                    Square::from(Piece::Knight, 2),
-                    Square::from(Piece::Bishop, 2),
-                    Square::from(Piece::Queen, 2),
-                    Square::from(Piece::King, 2),
-                    Square::from(Piece::Bishop, 2),
-                    Square::from(Piece::Knight, 2),
-                    Square::from(Piece::Rook, 2),
-                ],
+            grid,
+            variant,
+            pockets: [Pocket::new(); 2],
+            en_passant: None,
+            en_passant_capturable: false,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            hash: 0,
+            history: Vec::new(),
+        };
+
+        board.rehash();
+        board
+    }
+
+    fn standard_grid() -> [[Square; 8]; 8] {
+        [
+            [
+                Square::from(Piece::Rook, 1),
+                Square::from(Piece::Knight, 1),
+                Square::from(Piece::Bishop, 1),
+                Square::from(Piece::Queen, 1),
+                Square::from(Piece::King, 1),
+                Square::from(Piece::Bishop, 1),
+                Square::from(Piece::Knight, 1),
+                Square::from(Piece::Rook, 1),
             ],
+            [Square::from(Piece::Pawn, 1); 8],
+            [Square::from(Piece::None, 0); 8],
+            [Square::from(Piece::None, 0); 8],
+            [Square::from(Piece::None, 0); 8],
+            [Square::from(Piece::None, 0); 8],
+            [Square::from(Piece::Pawn, 2); 8],
+            [
+                Square::from(Piece::Rook, 2),
+                Square::from(Piece::Knight, 2),
+                Square::from(Piece::Bishop, 2),
+                Square::from(Piece::Queen, 2),
+                Square::from(Piece::King, 2),
+                Square::from(Piece::Bishop, 2),
+                Square::from(Piece::Knight, 2),
+                Square::from(Piece::Rook, 2),
+            ],
+        ]
+    }
+
+    fn racing_kings_grid() -> [[Square; 8]; 8] {
+        let mut grid = [[Square::from(Piece::None, 0); 8]; 8];
+        let pieces = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+        for (x, piece) in pieces.iter().enumerate() {
+            grid[0][x] = Square::from(*piece, 2);
+            grid[0][7 - x] = Square::from(*piece, 1);
         }
+
+        for (x, piece) in pieces.iter().skip(1).enumerate() {
+            grid[1][x + 1] = Square::from(*piece, 2);
+            grid[1][6 - x] = Square::from(*piece, 1);
+        }
+
+        grid[1][0] = Square::from(Piece::King, 2);
+        grid[1][7] = Square::from(Piece::King, 1);
+        grid
     }
 
     #[allow(dead_code)]
@@ -73,6 +150,8 @@ impl Board {
                 self.grid[y][x] = squares[y * 8 + x];
             }
         }
+
+        self.rehash();
     }
 
     #[allow(dead_code)]
@@ -83,6 +162,13 @@ impl Board {
         self.grid[6][1] = Square::from(Piece::Pawn, 1);
         self.grid[0][0] = Square::from(Piece::Rook, 1);
         self.grid[0][7] = Square::from(Piece::Rook, 1);
+        self.rehash();
+    }
+
+    /// Computes the hash from scratch, for positions set up directly
+    pub(super) fn rehash(&mut self) {
+        self.en_passant_capturable = self.find_en_passant_capture();
+        self.hash = zobrist::hash(self);
     }
 
     pub fn get(&self, x: i32, y: i32) -> &Square {
@@ -94,6 +180,8 @@ impl Board {
     }
 
     pub fn set(&mut self, x: i32, y: i32, square: &Square) {
+        let old = self.get(x, y);
+        self.hash ^= zobrist::square_key(old, x, y) ^ zobrist::square_key(square, x, y);
         self.grid[y as usize][x as usize] = *square;
     }
 
@@ -101,6 +189,56 @@ impl Board {
         self.set(pos.x as i32, pos.y as i32, square);
     }
 
+    pub fn get_pocket(&self, player: i32) -> &Pocket {
+        &self.pockets[(player - 1) as usize]
+    }
+
+    fn pocket_add(&mut self, player: i32, piece: Piece) {
+        let pocket = &mut self.pockets[(player - 1) as usize];
+        let count = pocket.get(piece);
+        pocket.add(piece);
+
+        self.hash ^= zobrist::pocket_key(player, piece, count)
+            ^ zobrist::pocket_key(player, piece, pocket.get(piece));
+    }
+
+    fn pocket_remove(&mut self, player: i32, piece: Piece) {
+        let pocket = &mut self.pockets[(player - 1) as usize];
+        let count = pocket.get(piece);
+        pocket.remove(piece);
+
+        self.hash ^= zobrist::pocket_key(player, piece, count)
+            ^ zobrist::pocket_key(player, piece, pocket.get(piece));
+    }
+
+    /// White king side, white queen side, black king side, black queen side
+    pub fn castling_rights(&self) -> [bool; 4] {
+        let mut rights = [false; 4];
+
+        if !self.variant.has_castling() {
+            return rights;
+        }
+
+        for player in 1..=2 {
+            let y = if player == 1 { 0 } else { 7 };
+            let king = self.get(4, y);
+
+            if king.piece != Piece::King || king.player != player || king.moved {
+                continue;
+            }
+
+            for (idx, x) in [7, 0].iter().enumerate() {
+                let rook = self.get(*x, y);
+
+                if rook.piece == Piece::Rook && rook.player == player && !rook.moved {
+                    rights[((player - 1) * 2) as usize + idx] = true;
+                }
+            }
+        }
+
+        rights
+    }
+
     pub fn can_move_to(&self, square: &Square, pos: &Position, attack: bool) -> bool {
         if pos.x < 0 || pos.x >= 8 || pos.y < 0 || pos.y >= 8 {
             false
@@ -192,6 +330,8 @@ impl Board {
 
                         if !other1.is_none() && other1.player != square.player {
                             try_add(pa, true);
+                        } else if self.en_passant == Some(pa) && square.player == self.turn {
+                            try_add(pa, false);
                         }
                     }
                 }
@@ -236,7 +376,7 @@ impl Board {
                     try_add(p.corner(idx, 1), true);
                 }
 
-                if !all && !self.is_check() {
+                if !all && self.variant.has_castling() && !self.is_check() {
                     let mv1 = self.get_castling_move(p, 0);
                     let mv2 = self.get_castling_move(p, 1);
 
@@ -259,31 +399,93 @@ impl Board {
         }
 
         if !all {
-            let mut cloned = self.clone();
-            let grid = cloned.grid;
-
-            moves.retain(|mv| {
-                cloned.grid = grid.clone();
-                cloned.perform_move(&mv);
-                !cloned.is_check()
-            })
+            self.filter_legal(&mut moves);
         }
 
         moves
     }
 
-    pub fn square_vulnerable(&self, pos: &Position) -> bool {
+    fn filter_legal(&self, moves: &mut Vec<Move>) {
+        match self.variant {
+            Variant::Antichess => {
+                // Captures are compulsory and the king may be left in check
+                if self.has_capture() {
+                    moves.retain(|mv| self.is_capture(mv));
+                }
+            }
+            Variant::RacingKings => {
+                // Neither side may ever be in check
+                let mut cloned = self.clone();
+
+                moves.retain(|mv| {
+                    cloned.perform_move(mv);
+                    let mut legal = !cloned.is_check();
+
+                    if legal {
+                        cloned.next_turn();
+                        legal = !cloned.is_check();
+                    }
+
+                    cloned.undo_move();
+                    legal
+                })
+            }
+            _ => {
+                let mut cloned = self.clone();
+
+                moves.retain(|mv| {
+                    cloned.perform_move(mv);
+                    let legal = !cloned.is_check();
+                    cloned.undo_move();
+                    legal
+                })
+            }
+        }
+    }
+
+    pub fn is_capture(&self, mv: &Move) -> bool {
+        !mv.is_drop() && (!self.getp(&mv.to).is_none() || self.is_en_passant(mv))
+    }
+
+    pub fn is_en_passant(&self, mv: &Move) -> bool {
+        self.en_passant == Some(mv.to)
+            && mv.from.x != mv.to.x
+            && self.getp(&mv.from).piece == Piece::Pawn
+    }
+
+    /// Whether a pawn of the side to move can legally take en passant
+    pub fn can_capture_en_passant(&self) -> bool {
+        self.en_passant_capturable
+    }
+
+    fn find_en_passant_capture(&self) -> bool {
+        let pos = match self.en_passant {
+            Some(pos) => pos,
+            None => return false,
+        };
+
+        let y = if self.turn == 1 { pos.y - 1 } else { pos.y + 1 };
+
+        [pos.x - 1, pos.x + 1].iter().any(|x| {
+            let from = Position::new(*x, y);
+
+            from.is_valid()
+                && self.getp(&from).piece == Piece::Pawn
+                && self.getp(&from).player == self.turn
+                && self.get_moves(&from, false).iter().any(|mv| mv.to == pos)
+        })
+    }
+
+    pub fn has_capture(&self) -> bool {
         for y in 0..8 {
             for x in 0..8 {
-                let from_sq = self.get(x, y);
-
-                if from_sq.player == 0 || from_sq.player == self.turn {
+                if self.get(x, y).player != self.turn {
                     continue;
                 }
 
                 let moves = self.get_moves(&Position::new(x, y), true);
 
-                if moves.iter().any(|mv| &mv.to == pos) {
+                if moves.iter().any(|mv| self.is_capture(mv)) {
                     return true;
                 }
             }
@@ -292,18 +494,200 @@ impl Board {
         false
     }
 
-    pub fn is_check(&self) -> bool {
+    pub fn get_drops(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = vec![];
+
+        if self.variant != Variant::Crazyhouse {
+            return moves;
+        }
+
+        for piece in self.get_pocket(self.turn).pieces() {
+            for y in 0..8 {
+                for x in 0..8 {
+                    let pos = Position::new(x, y);
+
+                    if !self.getp(&pos).is_none() {
+                        continue;
+                    }
+
+                    // Pawns cannot be dropped on the first or last rank
+                    if piece == Piece::Pawn && (y == 0 || y == 7) {
+                        continue;
+                    }
+
+                    if let Some(mv) = Move::new_drop(piece, pos) {
+                        moves.push(mv);
+                    }
+                }
+            }
+        }
+
+        let mut cloned = self.clone();
+
+        moves.retain(|mv| {
+            cloned.perform_move(mv);
+            let legal = !cloned.is_check();
+            cloned.undo_move();
+            legal
+        });
+
+        moves
+    }
+
+    pub fn square_vulnerable(&self, pos: &Position) -> bool {
+        let enemy = if self.turn == 1 { 2 } else { 1 };
+        self.is_attacked(pos, enemy)
+    }
+
+    /// Whether any piece of `player` attacks the square
+    pub fn is_attacked(&self, pos: &Position, player: i32) -> bool {
+        let has_piece = |p: &Position, pieces: &[Piece]| -> bool {
+            if !p.is_valid() {
+                return false;
+            }
+
+            let square = self.getp(p);
+            square.player == player && pieces.contains(&square.piece)
+        };
+
+        for idx in 0..4 {
+            let knight1 = pos.side(idx, 2).side((idx + 1) % 4, 1);
+            let knight2 = pos.side(idx, 2).side((idx + 3) % 4, 1);
+
+            if has_piece(&knight1, &[Piece::Knight]) || has_piece(&knight2, &[Piece::Knight]) {
+                return true;
+            }
+
+            if has_piece(&pos.side(idx, 1), &[Piece::King])
+                || has_piece(&pos.corner(idx, 1), &[Piece::King])
+            {
+                return true;
+            }
+        }
+
+        let sign = if player == 1 { 1 } else { -1 };
+
+        for dx in [-1, 1] {
+            if has_piece(&Position::new(pos.x + dx, pos.y - sign), &[Piece::Pawn]) {
+                return true;
+            }
+        }
+
+        let sliders = [[Piece::Rook, Piece::Queen], [Piece::Bishop, Piece::Queen]];
+
+        for (diagonal, pieces) in sliders.iter().enumerate() {
+            for idx in 0..4 {
+                for dist in 1..8 {
+                    let p = if diagonal == 1 {
+                        pos.corner(idx, dist)
+                    } else {
+                        pos.side(idx, dist)
+                    };
+
+                    if !p.is_valid() {
+                        break;
+                    }
+
+                    if !self.getp(&p).is_none() {
+                        if has_piece(&p, pieces) {
+                            return true;
+                        }
+
+                        break;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    pub fn find_king(&self, player: i32) -> Option<Position> {
         for y in 0..8 {
             for x in 0..8 {
                 let square = self.get(x, y);
 
-                if square.piece == Piece::King && square.player == self.turn {
-                    return self.square_vulnerable(&Position::new(x, y));
+                if square.piece == Piece::King && square.player == player {
+                    return Some(Position::new(x, y));
                 }
             }
         }
 
-        false
+        None
+    }
+
+    pub fn is_check(&self) -> bool {
+        // The king is an ordinary piece in antichess
+        if self.variant == Variant::Antichess {
+            return false;
+        }
+
+        match self.find_king(self.turn) {
+            Some(pos) => self.square_vulnerable(&pos),
+            None => false,
+        }
+    }
+
+    pub fn get_outcome(&self) -> Option<Outcome> {
+        if self.repetitions() >= 2 {
+            return Some(Outcome::Repetition);
+        }
+
+        match self.variant {
+            Variant::Antichess => {
+                // Losing all pieces or being stalemated wins
+                if !self.can_move_any() {
+                    Some(Outcome::VariantWin(self.turn))
+                } else {
+                    None
+                }
+            }
+            Variant::RacingKings => self.get_racing_kings_outcome(),
+            _ => {
+                if self.is_checkmate() {
+                    Some(Outcome::Checkmate(if self.turn == 1 { 2 } else { 1 }))
+                } else if self.is_stalemate() {
+                    Some(Outcome::Stalemate)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn get_racing_kings_outcome(&self) -> Option<Outcome> {
+        let at_goal = |player: i32| match self.find_king(player) {
+            Some(pos) => pos.y == 7,
+            None => false,
+        };
+
+        let white_goal = at_goal(1);
+        let black_goal = at_goal(2);
+
+        if white_goal && black_goal {
+            return Some(Outcome::VariantDraw);
+        } else if black_goal {
+            return Some(Outcome::VariantWin(2));
+        } else if white_goal {
+            // Black gets one more move to equalise
+            if self.turn == 2 {
+                if let Some(pos) = self.find_king(2) {
+                    let moves = self.get_moves(&pos, false);
+
+                    if moves.iter().any(|mv| mv.to.y == 7) {
+                        return None;
+                    }
+                }
+            }
+
+            return Some(Outcome::VariantWin(1));
+        }
+
+        if !self.can_move_any() {
+            Some(Outcome::Stalemate)
+        } else {
+            None
+        }
     }
 
     pub fn is_stalemate(&self) -> bool {
@@ -331,12 +715,51 @@ impl Board {
             }
         }
 
-        false
+        !self.get_drops().is_empty()
     }
 
     pub fn can_move(&self, mv: &Move) -> bool {
-        let moves = self.get_moves(&mv.from, false);
-        moves.iter().any(|m| m == mv)
+        let moves = if mv.is_drop() {
+            self.get_drops()
+        } else {
+            self.get_moves(&mv.from, false)
+        };
+
+        moves
+            .iter()
+            .any(|m| m.from == mv.from && m.to == mv.to && m.drop == mv.drop)
+    }
+
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = vec![];
+        let last = if self.turn == 1 { 7 } else { 0 };
+
+        let mut promotions = vec![Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+        if self.variant == Variant::Antichess {
+            promotions.push(Piece::King);
+        }
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let square = self.get(x, y);
+
+                if square.player != self.turn {
+                    continue;
+                }
+
+                for mv in self.get_moves(&Position::new(x, y), false) {
+                    if square.piece == Piece::Pawn && mv.to.y == last {
+                        moves.extend(promotions.iter().map(|piece| mv.with_promotion(*piece)));
+                    } else {
+                        moves.push(mv);
+                    }
+                }
+            }
+        }
+
+        moves.extend(self.get_drops());
+        moves
     }
 
     pub fn select(&mut self, pos_str: Option<&&str>) -> bool {
@@ -374,6 +797,13 @@ impl Board {
             return false;
         }
 
+        if mv.is_drop() {
+            self.perform_move(mv);
+            self.prev_move = Some(*mv);
+            self.next_turn();
+            return true;
+        }
+
         let from_sq = self.getp(&mv.from);
         let to_sq = self.getp(&mv.to);
 
@@ -390,29 +820,217 @@ impl Board {
 
         if self.get_promoting().is_none() {
             self.next_turn();
+        } else if mv.promotion != Piece::None {
+            return self.promote(mv.promotion);
         }
 
         true
     }
 
+    /// Makes a fully specified move without checking legality, for searching
+    pub fn make_move(&mut self, mv: &Move) {
+        self.perform_move(mv);
+
+        let square = *self.getp(&mv.to);
+
+        if !mv.is_drop() && square.piece == Piece::Pawn && (mv.to.y == 0 || mv.to.y == 7) {
+            let piece = if mv.promotion == Piece::None {
+                Piece::Queen
+            } else {
+                mv.promotion
+            };
+
+            let mut promoted = Square::from(piece, square.player);
+            promoted.moved = true;
+            promoted.promoted = true;
+            self.setp(&mv.to, &promoted);
+        }
+
+        self.prev_move = Some(*mv);
+        self.next_turn();
+    }
+
+    /// Passes the turn, used for null move pruning
+    pub fn make_null_move(&mut self) {
+        self.push_state();
+        self.hash ^= zobrist::en_passant_key(self);
+        self.en_passant = None;
+        self.en_passant_capturable = false;
+        self.next_turn();
+    }
+
+    pub fn unmake_move(&mut self) {
+        self.undo_move();
+    }
+
+    fn push_state(&mut self) {
+        self.history.push(State {
+            turn: self.turn,
+            prev_move: self.prev_move,
+            grid: self.grid,
+            pockets: self.pockets,
+            en_passant: self.en_passant,
+            en_passant_capturable: self.en_passant_capturable,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        });
+    }
+
+    /// Moves the pieces without passing the turn
     fn perform_move(&mut self, mv: &Move) {
-        let mut from_sq = self.getp(&mv.from).clone();
+        self.push_state();
+
+        let rights = self.castling_rights();
+        let en_passant = self.en_passant;
+        self.hash ^= zobrist::en_passant_key(self);
+        self.en_passant = None;
+        self.en_passant_capturable = false;
+        self.halfmove_clock += 1;
+
+        if mv.is_drop() {
+            let mut square = Square::from(mv.drop, self.turn);
+            square.moved = true;
+            self.pocket_remove(self.turn, mv.drop);
+            self.setp(&mv.to, &square);
+            return;
+        }
+
+        let mut from_sq = *self.getp(&mv.from);
+        let mut to_sq = *self.getp(&mv.to);
+
+        if from_sq.piece == Piece::Pawn || !to_sq.is_none() {
+            self.halfmove_clock = 0;
+        }
+
+        // En passant captures the pawn beside the destination square
+        if from_sq.piece == Piece::Pawn && en_passant == Some(mv.to) && mv.from.x != mv.to.x {
+            let captured = Position::new(mv.to.x, mv.from.y);
+            to_sq = *self.getp(&captured);
+            self.setp(&captured, &Square::from(Piece::None, 0));
+        }
+
+        // Captured pieces go to the capturer's pocket, promoted pieces as pawns
+        if self.variant == Variant::Crazyhouse && !to_sq.is_none() {
+            let piece = if to_sq.promoted {
+                Piece::Pawn
+            } else {
+                to_sq.piece
+            };
+
+            self.pocket_add(from_sq.player, piece);
+        }
 
         from_sq.moved = true;
         self.setp(&mv.to, &from_sq);
         self.setp(&mv.from, &Square::from(Piece::None, 0));
 
+        if from_sq.piece == Piece::Pawn && (mv.to.y - mv.from.y).abs() == 2 {
+            self.en_passant = Some(Position::new(mv.from.x, (mv.from.y + mv.to.y) / 2));
+
+            // Only a capture the opponent can actually make is part of the
+            // position, and it is the opponent who moves next
+            let mut next = self.clone();
+            next.turn = if self.turn == 1 { 2 } else { 1 };
+            self.en_passant_capturable = next.find_en_passant_capture();
+            self.hash ^= zobrist::en_passant_key(self);
+        }
+
         // Castling
         if from_sq.piece == Piece::King && (mv.from.x - mv.to.x).abs() > 1 {
             let xr = if mv.to.x < mv.from.x { 0 } else { 7 };
             let sign = if mv.to.x < mv.from.x { -1 } else { 1 };
             let pr = &Position::new(xr, mv.to.y);
 
-            let mut rook_sq = self.getp(pr).clone();
+            let mut rook_sq = *self.getp(pr);
             rook_sq.moved = true;
             self.setp(&Position::new(mv.to.x - sign, mv.to.y), &rook_sq);
-            self.setp(&pr, &Square::from(Piece::None, 0));
+            self.setp(pr, &Square::from(Piece::None, 0));
+        }
+
+        self.hash ^= zobrist::castling_key(rights) ^ zobrist::castling_key(self.castling_rights());
+    }
+
+    fn restore(&mut self, state: &State) {
+        self.turn = state.turn;
+        self.prev_move = state.prev_move;
+        self.grid = state.grid;
+        self.pockets = state.pockets;
+        self.en_passant = state.en_passant;
+        self.en_passant_capturable = state.en_passant_capturable;
+        self.halfmove_clock = state.halfmove_clock;
+        self.fullmove_number = state.fullmove_number;
+        self.hash = state.hash;
+    }
+
+    fn undo_move(&mut self) -> bool {
+        match self.history.pop() {
+            Some(state) => {
+                self.restore(&state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves played on this board, oldest first
+    pub fn game_moves(&self) -> Vec<Move> {
+        if self.history.is_empty() {
+            return Vec::new();
+        }
+
+        // Each state remembers the move that led to it
+        let mut moves: Vec<Move> = self
+            .history
+            .iter()
+            .skip(1)
+            .filter_map(|state| state.prev_move)
+            .collect();
+
+        moves.extend(self.prev_move);
+        moves
+    }
+
+    /// The position before the first move played on this board
+    pub fn start_board(&self) -> Board {
+        let mut board = self.clone();
+
+        if let Some(state) = self.history.first() {
+            board.restore(state);
+            board.prev_move = None;
         }
+
+        board
+    }
+
+    /// A copy that, unlike `clone`, keeps the moves played so far
+    pub fn clone_with_history(&self) -> Board {
+        let mut board = self.start_board();
+
+        for mv in self.game_moves() {
+            board.make_move(&mv);
+        }
+
+        board
+    }
+
+    /// Takes back the last move made with `do_move`
+    pub fn undo(&mut self) -> bool {
+        self.select(None);
+        self.undo_move()
+    }
+
+    /// Number of earlier occurrences of the current position
+    pub fn repetitions(&self) -> usize {
+        self.history
+            .iter()
+            .filter(|state| state.hash == self.hash)
+            .count()
+    }
+
+    /// Hashes of all earlier positions in the game, oldest first
+    pub fn position_hashes(&self) -> Vec<u64> {
+        self.history.iter().map(|state| state.hash).collect()
     }
 
     pub fn get_promoting(&self) -> Option<Position> {
@@ -436,20 +1054,32 @@ impl Board {
             return false;
         }
 
+        let king = piece == Piece::King && self.variant == Variant::Antichess;
+
         if piece != Piece::Bishop
             && piece != Piece::Knight
             && piece != Piece::Queen
             && piece != Piece::Rook
+            && !king
         {
             return false;
         }
 
-        self.setp(&pos.unwrap(), &Square::from(piece, self.turn));
+        let mut square = Square::from(piece, self.turn);
+        square.moved = true;
+        square.promoted = true;
+        self.setp(&pos.unwrap(), &square);
+        self.prev_move = self.prev_move.map(|mv| mv.with_promotion(piece));
         self.next_turn();
         true
     }
 
     pub fn next_turn(&mut self) {
+        if self.turn == 2 {
+            self.fullmove_number += 1;
+        }
+
         self.turn = if self.turn == 1 { 2 } else { 1 };
+        self.hash ^= zobrist::turn_key();
     }
 }
@@ -0,0 +1,184 @@
+use crate::chess::{Board, Piece, Position, Square, Variant};
+
+impl Board {
+    /// Parses a FEN or EPD string, pockets may follow the placement in brackets
+    pub fn from_fen(fen: &str, variant: Variant) -> Option<Board> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+
+        if fields.len() < 4 {
+            return None;
+        }
+
+        let mut board = Board::new_variant(variant);
+        board.grid = [[Square::from(Piece::None, 0); 8]; 8];
+
+        let (placement, pocket) = match fields[0].find('[') {
+            Some(idx) => (
+                &fields[0][..idx],
+                fields[0][idx + 1..].trim_end_matches(']'),
+            ),
+            None => (fields[0], ""),
+        };
+
+        let ranks: Vec<&str> = placement.split('/').collect();
+
+        if ranks.len() != 8 {
+            return None;
+        }
+
+        for (idx, rank) in ranks.iter().enumerate() {
+            let y = 7 - idx as i32;
+            let mut x = 0;
+
+            for c in rank.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    x += empty as i32;
+                    continue;
+                }
+
+                if c == '~' && x > 0 {
+                    board.grid[y as usize][(x - 1) as usize].promoted = true;
+                    continue;
+                }
+
+                let piece = Piece::from_letter(c);
+
+                if piece == Piece::None || x >= 8 {
+                    return None;
+                }
+
+                let player = if c.is_ascii_uppercase() { 1 } else { 2 };
+                let mut square = Square::from(piece, player);
+                square.moved = piece == Piece::King || piece == Piece::Rook;
+                board.grid[y as usize][x as usize] = square;
+                x += 1;
+            }
+
+            if x != 8 {
+                return None;
+            }
+        }
+
+        for c in pocket.chars() {
+            let player = if c.is_ascii_uppercase() { 1 } else { 2 };
+            board.pockets[player - 1].add(Piece::from_letter(c));
+        }
+
+        board.turn = match fields[1] {
+            "w" => 1,
+            "b" => 2,
+            _ => return None,
+        };
+
+        for c in fields[2].chars() {
+            let (x, y) = match c {
+                'K' => (7, 0),
+                'Q' => (0, 0),
+                'k' => (7, 7),
+                'q' => (0, 7),
+                '-' => continue,
+                _ => return None,
+            };
+
+            board.grid[y][4].moved = false;
+            board.grid[y][x].moved = false;
+        }
+
+        board.en_passant = match fields[3] {
+            "-" => None,
+            pos => Some(Position::from(pos.as_bytes())?),
+        };
+
+        board.halfmove_clock = fields.get(4).and_then(|f| f.parse().ok()).unwrap_or(0);
+        board.fullmove_number = fields.get(5).and_then(|f| f.parse().ok()).unwrap_or(1);
+        board.rehash();
+        Some(board)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut ranks: Vec<String> = Vec::new();
+
+        for y in (0..8).rev() {
+            let mut rank = String::new();
+            let mut empty = 0;
+
+            for x in 0..8 {
+                let square = self.get(x, y);
+
+                if square.is_none() {
+                    empty += 1;
+                    continue;
+                }
+
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                    empty = 0;
+                }
+
+                rank.push(piece_char(square.piece, square.player));
+
+                if square.promoted && self.variant == Variant::Crazyhouse {
+                    rank.push('~');
+                }
+            }
+
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+
+            ranks.push(rank);
+        }
+
+        let mut placement = ranks.join("/");
+
+        if self.variant == Variant::Crazyhouse {
+            placement.push('[');
+
+            for player in 1..=2 {
+                let pocket = self.get_pocket(player);
+
+                for piece in pocket.pieces() {
+                    for _ in 0..pocket.get(piece) {
+                        placement.push(piece_char(piece, player));
+                    }
+                }
+            }
+
+            placement.push(']');
+        }
+
+        let turn = if self.turn == 1 { "w" } else { "b" };
+
+        let rights = self.castling_rights();
+        let castling: String = ['K', 'Q', 'k', 'q']
+            .iter()
+            .zip(rights.iter())
+            .filter(|(_, right)| **right)
+            .map(|(c, _)| *c)
+            .collect();
+
+        let castling = if castling.is_empty() {
+            String::from("-")
+        } else {
+            castling
+        };
+
+        let en_passant = match self.en_passant {
+            Some(pos) => pos.to_string(),
+            None => String::from("-"),
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement, turn, castling, en_passant, self.halfmove_clock, self.fullmove_number
+        )
+    }
+}
+
+fn piece_char(piece: Piece, player: i32) -> char {
+    if player == 1 {
+        piece.letter()
+    } else {
+        piece.letter().to_ascii_lowercase()
+    }
+}
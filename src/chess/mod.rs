@@ -1,11 +1,21 @@
 mod board;
+mod fen;
 mod moves;
+mod outcome;
+mod perft;
 mod piece;
+mod pocket;
 mod position;
+mod san;
 mod square;
+mod variant;
+pub mod zobrist;
 
 pub use self::board::Board;
 pub use self::moves::Move;
+pub use self::outcome::Outcome;
 pub use self::piece::Piece;
+pub use self::pocket::Pocket;
 pub use self::position::Position;
 pub use self::square::Square;
+pub use self::variant::Variant;
@@ -1,21 +1,30 @@
-use crate::chess::Position;
+use crate::chess::{Piece, Position};
 use std::fmt;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Move {
     pub from: Position,
     pub to: Position,
+    pub drop: Piece,
+    pub promotion: Piece,
 }
 
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {}", self.from, self.to)
+        if self.is_drop() {
+            write!(f, "{}@{}", self.drop.letter(), self.to)
+        } else {
+            write!(f, "{} {}", self.from, self.to)
+        }
     }
 }
 
 impl PartialEq for Move {
     fn eq(&self, other: &Self) -> bool {
-        return self.from == other.from && self.to == other.to;
+        return self.from == other.from
+            && self.to == other.to
+            && self.drop == other.drop
+            && self.promotion == other.promotion;
     }
 }
 
@@ -24,13 +33,37 @@ impl Move {
         if from.x < 0 || from.x >= 8 || from.y < 0 || from.y >= 8 {
             None
         } else {
-            Some(Move { from, to })
+            Some(Move {
+                from,
+                to,
+                drop: Piece::None,
+                promotion: Piece::None,
+            })
+        }
+    }
+
+    pub fn new_drop(piece: Piece, to: Position) -> Option<Move> {
+        if piece == Piece::None || piece == Piece::King || !to.is_valid() {
+            None
+        } else {
+            Some(Move {
+                from: to,
+                to,
+                drop: piece,
+                promotion: Piece::None,
+            })
         }
     }
 
     pub fn from(move_str: &str) -> Option<Move> {
         let bytes = move_str.as_bytes();
 
+        if bytes.len() == 4 && bytes[1] == b'@' {
+            let piece = Piece::from_letter(bytes[0] as char);
+            let to = Position::from(&bytes[2..4])?;
+            return Move::new_drop(piece, to);
+        }
+
         if bytes.len() != 5 {
             None
         } else {
@@ -43,8 +76,59 @@ impl Move {
                 Some(Move {
                     from: from.unwrap(),
                     to: to.unwrap(),
+                    drop: Piece::None,
+                    promotion: Piece::None,
                 })
             }
         }
     }
+
+    /// Parses long algebraic notation such as `e2e4`, `e7e8q` or `P@e4`
+    pub fn from_uci(move_str: &str) -> Option<Move> {
+        let bytes = move_str.as_bytes();
+
+        if bytes.len() == 4 && bytes[1] == b'@' {
+            return Move::from(move_str);
+        }
+
+        if bytes.len() != 4 && bytes.len() != 5 {
+            return None;
+        }
+
+        let from = Position::from(&bytes[0..2])?;
+        let to = Position::from(&bytes[2..4])?;
+        let mut mv = Move::new(from, to)?;
+
+        if bytes.len() == 5 {
+            mv.promotion = Piece::from_letter(bytes[4] as char);
+
+            if mv.promotion == Piece::None || mv.promotion == Piece::Pawn {
+                return None;
+            }
+        }
+
+        Some(mv)
+    }
+
+    pub fn to_uci(self) -> String {
+        if self.is_drop() {
+            self.to_string()
+        } else if self.promotion != Piece::None {
+            let letter = self.promotion.letter().to_ascii_lowercase();
+            format!("{}{}{}", self.from, self.to, letter)
+        } else {
+            format!("{}{}", self.from, self.to)
+        }
+    }
+
+    pub fn with_promotion(&self, piece: Piece) -> Move {
+        Move {
+            promotion: piece,
+            ..*self
+        }
+    }
+
+    pub fn is_drop(&self) -> bool {
+        self.drop != Piece::None
+    }
 }
@@ -0,0 +1,42 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Checkmate(i32),
+    Stalemate,
+    VariantWin(i32),
+    VariantDraw,
+    Repetition,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Checkmate(_) => write!(f, "Checkmate!"),
+            Outcome::Stalemate => write!(f, "Stalemate!"),
+            Outcome::VariantWin(1) => write!(f, "White wins!"),
+            Outcome::VariantWin(_) => write!(f, "Black wins!"),
+            Outcome::VariantDraw => write!(f, "Draw!"),
+            Outcome::Repetition => write!(f, "Draw by repetition!"),
+        }
+    }
+}
+
+impl Outcome {
+    /// Result in PGN notation
+    pub fn result(&self) -> &'static str {
+        match self.winner() {
+            1 => "1-0",
+            2 => "0-1",
+            _ => "1/2-1/2",
+        }
+    }
+
+    /// Winning player, or 0 for a draw
+    pub fn winner(&self) -> i32 {
+        match self {
+            Outcome::Checkmate(player) | Outcome::VariantWin(player) => *player,
+            _ => 0,
+        }
+    }
+}
@@ -30,4 +30,28 @@ impl Piece {
             _ => Piece::None,
         }
     }
+
+    pub fn from_letter(letter: char) -> Piece {
+        match letter.to_ascii_uppercase() {
+            'B' => Piece::Bishop,
+            'K' => Piece::King,
+            'N' => Piece::Knight,
+            'P' => Piece::Pawn,
+            'Q' => Piece::Queen,
+            'R' => Piece::Rook,
+            _ => Piece::None,
+        }
+    }
+
+    pub fn letter(&self) -> char {
+        match self {
+            Piece::None => ' ',
+            Piece::Bishop => 'B',
+            Piece::King => 'K',
+            Piece::Knight => 'N',
+            Piece::Pawn => 'P',
+            Piece::Queen => 'Q',
+            Piece::Rook => 'R',
+        }
+    }
 }
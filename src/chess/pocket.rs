@@ -0,0 +1,47 @@
+use crate::chess::Piece;
+
+pub const POCKET_PIECES: [Piece; 5] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pocket {
+    counts: [i32; 7],
+}
+
+impl Pocket {
+    pub fn new() -> Pocket {
+        Pocket { counts: [0; 7] }
+    }
+
+    pub fn get(&self, piece: Piece) -> i32 {
+        self.counts[piece as usize]
+    }
+
+    pub fn add(&mut self, piece: Piece) {
+        if piece != Piece::None && piece != Piece::King {
+            self.counts[piece as usize] += 1;
+        }
+    }
+
+    pub fn remove(&mut self, piece: Piece) -> bool {
+        if self.get(piece) <= 0 {
+            return false;
+        }
+
+        self.counts[piece as usize] -= 1;
+        true
+    }
+
+    pub fn pieces(&self) -> Vec<Piece> {
+        POCKET_PIECES
+            .iter()
+            .filter(|piece| self.get(**piece) > 0)
+            .copied()
+            .collect()
+    }
+}
@@ -0,0 +1,195 @@
+use crate::chess::{Board, Move, Piece, Position};
+
+impl Board {
+    /// Standard algebraic notation for a legal move in this position
+    pub fn to_san(&self, mv: &Move) -> String {
+        let mut san = if mv.is_drop() {
+            format!("{}@{}", mv.drop.letter(), mv.to)
+        } else {
+            let piece = self.getp(&mv.from).piece;
+
+            if piece == Piece::King && (mv.to.x - mv.from.x).abs() == 2 {
+                if mv.to.x > mv.from.x {
+                    String::from("O-O")
+                } else {
+                    String::from("O-O-O")
+                }
+            } else if piece == Piece::Pawn {
+                let mut san = String::new();
+
+                if self.is_capture(mv) {
+                    san.push(file_char(mv.from.x));
+                    san.push('x');
+                }
+
+                san.push_str(&mv.to.to_string());
+
+                if mv.promotion != Piece::None {
+                    san.push('=');
+                    san.push(mv.promotion.letter());
+                }
+
+                san
+            } else {
+                let mut san = piece.letter().to_string();
+                san.push_str(&self.disambiguation(mv, piece));
+
+                if self.is_capture(mv) {
+                    san.push('x');
+                }
+
+                san.push_str(&mv.to.to_string());
+                san
+            }
+        };
+
+        let mut after = self.clone();
+        after.make_move(mv);
+
+        if after.is_check() {
+            san.push(if after.can_move_any() { '+' } else { '#' });
+        }
+
+        san
+    }
+
+    fn disambiguation(&self, mv: &Move, piece: Piece) -> String {
+        let others: Vec<Move> = self
+            .get_legal_moves()
+            .into_iter()
+            .filter(|other| {
+                !other.is_drop()
+                    && other.to == mv.to
+                    && other.from != mv.from
+                    && self.getp(&other.from).piece == piece
+            })
+            .collect();
+
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|other| other.from.x != mv.from.x) {
+            file_char(mv.from.x).to_string()
+        } else if others.iter().all(|other| other.from.y != mv.from.y) {
+            (mv.from.y + 1).to_string()
+        } else {
+            mv.from.to_string()
+        }
+    }
+
+    /// A line of moves in SAN with move numbers, such as "12... Nf6 13. e5"
+    pub fn line_to_san(&self, moves: &[Move]) -> String {
+        let mut board = self.clone();
+        let mut parts = Vec::new();
+
+        for (idx, mv) in moves.iter().enumerate() {
+            if board.turn == 1 {
+                parts.push(format!("{}.", board.fullmove_number));
+            } else if idx == 0 {
+                parts.push(format!("{}...", board.fullmove_number));
+            }
+
+            parts.push(board.to_san(mv));
+            board.make_move(mv);
+        }
+
+        parts.join(" ")
+    }
+
+    /// Parses standard algebraic notation, falling back to long algebraic
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        let san = san.trim_end_matches(|c| "+#!?".contains(c));
+        let moves = self.get_legal_moves();
+
+        let castling = match san {
+            "O-O" | "0-0" => Some(2),
+            "O-O-O" | "0-0-0" => Some(-2),
+            _ => None,
+        };
+
+        if let Some(dx) = castling {
+            return moves.into_iter().find(|mv| {
+                !mv.is_drop()
+                    && self.getp(&mv.from).piece == Piece::King
+                    && mv.to.x - mv.from.x == dx
+            });
+        }
+
+        if let Some(idx) = san.find('@') {
+            let piece = match &san[..idx] {
+                "" => Piece::Pawn,
+                letter => Piece::from_letter(letter.chars().next()?),
+            };
+
+            let mv = Move::new_drop(piece, Position::from(&san.as_bytes()[idx + 1..])?)?;
+            return moves.into_iter().find(|m| *m == mv);
+        }
+
+        if let Some(mv) = Move::from_uci(san) {
+            if let Some(found) = moves.iter().find(|m| **m == mv) {
+                return Some(*found);
+            }
+        }
+
+        let (san, promotion) = match san.find('=') {
+            Some(idx) => (
+                &san[..idx],
+                Piece::from_letter(san[idx + 1..].chars().next()?),
+            ),
+            None => (san, Piece::None),
+        };
+
+        if san.len() < 2 {
+            return None;
+        }
+
+        let to = Position::from(&san.as_bytes()[san.len() - 2..])?;
+        let first = san.chars().next()?;
+
+        let (piece, rest) = if "NBRQK".contains(first) {
+            (Piece::from_letter(first), &san[1..san.len() - 2])
+        } else {
+            (Piece::Pawn, &san[..san.len() - 2])
+        };
+
+        let mut from_x = None;
+        let mut from_y = None;
+
+        for c in rest.chars() {
+            match c {
+                'a'..='h' => from_x = Some(c as i32 - 'a' as i32),
+                '1'..='8' => from_y = Some(c as i32 - '1' as i32),
+                'x' | '-' => (),
+                _ => return None,
+            }
+        }
+
+        // A missing promotion piece means a queen
+        let promotion =
+            if promotion == Piece::None && piece == Piece::Pawn && (to.y == 0 || to.y == 7) {
+                Piece::Queen
+            } else {
+                promotion
+            };
+
+        let mut candidates = moves.into_iter().filter(|mv| {
+            !mv.is_drop()
+                && mv.to == to
+                && mv.promotion == promotion
+                && self.getp(&mv.from).piece == piece
+                && from_x.is_none_or(|x| mv.from.x == x)
+                && from_y.is_none_or(|y| mv.from.y == y)
+        });
+
+        let mv = candidates.next()?;
+
+        if candidates.next().is_some() {
+            return None;
+        }
+
+        Some(mv)
+    }
+}
+
+fn file_char(x: i32) -> char {
+    (b'a' + x as u8) as char
+}
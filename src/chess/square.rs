@@ -5,6 +5,7 @@ pub struct Square {
     pub piece: Piece,
     pub player: i32,
     pub moved: bool,
+    pub promoted: bool,
 }
 
 impl Square {
@@ -13,6 +14,7 @@ impl Square {
             piece,
             player,
             moved: false,
+            promoted: false,
         }
     }
 
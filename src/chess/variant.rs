@@ -0,0 +1,48 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variant {
+    Standard,
+    Crazyhouse,
+    Antichess,
+    RacingKings,
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Variant::Standard => "standard",
+            Variant::Crazyhouse => "crazyhouse",
+            Variant::Antichess => "antichess",
+            Variant::RacingKings => "racingkings",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Variant {
+    pub fn from(name: &str) -> Option<Variant> {
+        match name.to_lowercase().as_str() {
+            "standard" | "chess" => Some(Variant::Standard),
+            "crazyhouse" | "zh" => Some(Variant::Crazyhouse),
+            "antichess" | "giveaway" => Some(Variant::Antichess),
+            "racingkings" | "racing-kings" => Some(Variant::RacingKings),
+            _ => None,
+        }
+    }
+
+    /// Name used in the PGN Variant tag
+    pub fn pgn_name(&self) -> &'static str {
+        match self {
+            Variant::Standard => "Standard",
+            Variant::Crazyhouse => "Crazyhouse",
+            Variant::Antichess => "Antichess",
+            Variant::RacingKings => "Racing Kings",
+        }
+    }
+
+    pub fn has_castling(&self) -> bool {
+        *self == Variant::Standard || *self == Variant::Crazyhouse
+    }
+}
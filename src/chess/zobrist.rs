@@ -0,0 +1,145 @@
+use crate::chess::{Board, Piece, Square};
+
+const MAX_POCKET: usize = 16;
+
+pub struct Keys {
+    pieces: [[[u64; 64]; 7]; 2],
+    pockets: [[[u64; MAX_POCKET + 1]; 7]; 2],
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+    turn: u64,
+}
+
+pub static KEYS: Keys = Keys::generate();
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+impl Keys {
+    const fn generate() -> Keys {
+        let mut keys = Keys {
+            pieces: [[[0; 64]; 7]; 2],
+            pockets: [[[0; MAX_POCKET + 1]; 7]; 2],
+            castling: [0; 4],
+            en_passant: [0; 8],
+            turn: 0,
+        };
+
+        let mut state = 0x2545_F491_4F6C_DD1D;
+        let mut value;
+
+        let mut player = 0;
+        while player < 2 {
+            let mut piece = 0;
+            while piece < 7 {
+                let mut idx = 0;
+                while idx < 64 {
+                    (state, value) = splitmix64(state);
+                    keys.pieces[player][piece][idx] = value;
+                    idx += 1;
+                }
+
+                // Count 0 is left as zero so an empty pocket adds nothing
+                let mut count = 1;
+                while count <= MAX_POCKET {
+                    (state, value) = splitmix64(state);
+                    keys.pockets[player][piece][count] = value;
+                    count += 1;
+                }
+
+                piece += 1;
+            }
+            player += 1;
+        }
+
+        let mut idx = 0;
+        while idx < 4 {
+            (state, value) = splitmix64(state);
+            keys.castling[idx] = value;
+            idx += 1;
+        }
+
+        idx = 0;
+        while idx < 8 {
+            (state, value) = splitmix64(state);
+            keys.en_passant[idx] = value;
+            idx += 1;
+        }
+
+        (_, value) = splitmix64(state);
+        keys.turn = value;
+        keys
+    }
+}
+
+pub fn square_key(square: &Square, x: i32, y: i32) -> u64 {
+    if square.piece == Piece::None || square.player < 1 {
+        return 0;
+    }
+
+    KEYS.pieces[(square.player - 1) as usize][square.piece as usize][(y * 8 + x) as usize]
+}
+
+pub fn pocket_key(player: i32, piece: Piece, count: i32) -> u64 {
+    let count = (count.max(0) as usize).min(MAX_POCKET);
+    KEYS.pockets[(player - 1) as usize][piece as usize][count]
+}
+
+pub fn castling_key(rights: [bool; 4]) -> u64 {
+    let mut key = 0;
+
+    for (idx, right) in rights.iter().enumerate() {
+        if *right {
+            key ^= KEYS.castling[idx];
+        }
+    }
+
+    key
+}
+
+/// The key of the en passant file when the side to move can capture there,
+/// so a square no pawn can use does not make a position look different
+pub fn en_passant_key(board: &Board) -> u64 {
+    match board.en_passant {
+        Some(pos) if board.can_capture_en_passant() => KEYS.en_passant[pos.x as usize],
+        _ => 0,
+    }
+}
+
+pub fn turn_key() -> u64 {
+    KEYS.turn
+}
+
+/// Computes the full hash of a board from scratch
+pub fn hash(board: &Board) -> u64 {
+    let mut key = 0;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            key ^= square_key(board.get(x, y), x, y);
+        }
+    }
+
+    for player in 1..=2 {
+        let pocket = board.get_pocket(player);
+
+        for piece in pocket.pieces() {
+            key ^= pocket_key(player, piece, pocket.get(piece));
+        }
+    }
+
+    key ^= castling_key(board.castling_rights());
+
+    key ^= en_passant_key(board);
+
+    if board.turn == 2 {
+        key ^= turn_key();
+    }
+
+    key
+}
@@ -0,0 +1,153 @@
+use crate::engine::Clock;
+use std::time::{Duration, Instant};
+
+/// Time used by both sides of a game being played, counting down from a
+/// time control when there is one
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    /// Time left for each side, with the increment added after each move
+    pub control: Option<Clock>,
+    /// Time used by white and black before the current turn
+    pub used: [Duration; 2],
+    /// Time control the game started with, for starting again
+    initial: Option<Clock>,
+    turn: i32,
+    turn_start: Instant,
+    stopped: bool,
+    flagged: Option<i32>,
+}
+
+impl GameClock {
+    pub fn new(control: Option<Clock>, turn: i32) -> GameClock {
+        GameClock {
+            control,
+            used: [Duration::ZERO; 2],
+            initial: control,
+            turn,
+            turn_start: Instant::now(),
+            stopped: false,
+            flagged: None,
+        }
+    }
+
+    /// Continues a saved game with the times it was saved with, which are
+    /// the time left with a time control and otherwise the time used
+    pub fn resume(control: Option<Clock>, times: [Duration; 2], turn: i32) -> GameClock {
+        let mut clock = GameClock::new(control, turn);
+
+        match clock.control.as_mut() {
+            Some(control) => {
+                control.white = times[0];
+                control.black = times[1];
+            }
+            None => clock.used = times,
+        }
+
+        clock
+    }
+
+    /// Time control the game started with
+    pub fn initial(&self) -> Option<Clock> {
+        self.initial
+    }
+
+    /// Sets both clocks back to the start for a new game
+    pub fn reset(&mut self, turn: i32) {
+        *self = GameClock::new(self.initial, turn);
+    }
+
+    /// Charges the time since the turn started to the side that just moved
+    /// once the turn has changed, and stops the clock if that side ran out
+    /// of time
+    pub fn update(&mut self, turn: i32) {
+        if self.stopped || turn == self.turn {
+            return;
+        }
+
+        let elapsed = self.turn_start.elapsed();
+        self.used[(self.turn - 1) as usize] += elapsed;
+
+        if let Some(control) = self.control.as_mut() {
+            if !control.spend(self.turn, elapsed) {
+                self.flagged = Some(self.turn);
+                self.stopped = true;
+            }
+        }
+
+        self.turn = turn;
+        self.turn_start = Instant::now();
+    }
+
+    /// Stops the clock when the side to move has no time left while still
+    /// thinking, for views that can end the game without waiting for a move
+    pub fn check_flag(&mut self) -> Option<i32> {
+        if !self.stopped && self.control.is_some() && self.shown(self.turn) == Duration::ZERO {
+            self.update_used();
+            self.flagged = Some(self.turn);
+            self.stopped = true;
+        }
+
+        self.flagged
+    }
+
+    pub fn stop(&mut self) {
+        if !self.stopped {
+            self.update_used();
+            self.stopped = true;
+        }
+    }
+
+    fn update_used(&mut self) {
+        let elapsed = self.turn_start.elapsed();
+        self.used[(self.turn - 1) as usize] += elapsed;
+
+        if let Some(control) = self.control.as_mut() {
+            control.spend(self.turn, elapsed);
+        }
+
+        self.turn_start = Instant::now();
+    }
+
+    /// The side that ran out of time
+    pub fn flagged(&self) -> Option<i32> {
+        self.flagged
+    }
+
+    /// Time left with a time control, otherwise time used so far
+    pub fn shown(&self, player: i32) -> Duration {
+        let running = if player == self.turn && !self.stopped {
+            self.turn_start.elapsed()
+        } else {
+            Duration::ZERO
+        };
+
+        match self.control {
+            Some(control) => control.time(player).saturating_sub(running),
+            None => self.used[(player - 1) as usize] + running,
+        }
+    }
+
+    /// Time left on both clocks as of now, for engines to plan with
+    pub fn remaining(&self) -> Option<Clock> {
+        let mut control = self.control?;
+
+        if self.turn == 1 {
+            control.white = self.shown(1);
+        } else {
+            control.black = self.shown(2);
+        }
+
+        Some(control)
+    }
+}
+
+/// Minutes and seconds, with tenths under ten seconds
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+
+    if seconds < 10 {
+        format!("0:0{:.1}", time.as_secs_f64())
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
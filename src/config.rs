@@ -0,0 +1,115 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Per-user settings as `key = value` lines, skipping lines starting with `#`
+pub struct Config {
+    path: PathBuf,
+    values: BTreeMap<String, String>,
+    /// The file as it was read, so saving keeps its comments and order
+    lines: Vec<String>,
+    /// Keys set since the file was read
+    changed: BTreeSet<String>,
+}
+
+/// `~/.rust-chess/config`, or the working directory without a home
+pub fn default_path() -> PathBuf {
+    match env::var_os("HOME") {
+        Some(home) => Path::new(&home).join(".rust-chess").join("config"),
+        None => PathBuf::from("rust-chess.config"),
+    }
+}
+
+impl Config {
+    /// Reads the settings, starting empty when the file does not exist yet
+    pub fn open(path: &Path) -> io::Result<Config> {
+        let mut config = Config {
+            path: path.to_path_buf(),
+            values: BTreeMap::new(),
+            lines: Vec::new(),
+            changed: BTreeSet::new(),
+        };
+
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(config),
+            Err(err) => return Err(err),
+        };
+
+        config.lines = text.lines().map(String::from).collect();
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split_once('=') {
+                Some((key, value)) => {
+                    config.values.insert(key_of(key), value.trim().to_string());
+                }
+                None => {
+                    let message = format!("line {}: expected 'key = value'", idx + 1);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Writes the file back as it was read with the lines of the keys that
+    /// were set replaced, adding the keys it did not have at the end
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            if !dir.as_os_str().is_empty() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+
+        let mut text = String::new();
+        let mut written = BTreeSet::new();
+
+        for line in self.lines.iter() {
+            let key = match line.split_once('=') {
+                Some((key, _)) if !line.trim_start().starts_with('#') => key_of(key),
+                _ => String::new(),
+            };
+
+            if self.changed.contains(&key) {
+                text.push_str(&format!("{} = {}\n", key, self.values[&key]));
+                written.insert(key);
+            } else {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+
+        for key in self.changed.difference(&written) {
+            text.push_str(&format!("{} = {}\n", key, self.values[key]));
+        }
+
+        fs::write(&self.path, text)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|value| value.as_str())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+        self.changed.insert(key.to_string());
+    }
+}
+
+/// Keys are matched without surrounding spaces or case
+fn key_of(text: &str) -> String {
+    text.trim().to_lowercase()
+}
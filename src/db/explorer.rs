@@ -0,0 +1,112 @@
+use super::GameDb;
+use crate::chess::{Board, Move};
+
+/// How the games in the database continued with one move
+#[derive(Debug, Clone)]
+pub struct MoveStats {
+    pub mv: Move,
+    pub games: u32,
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+    /// Sum and count of the ratings of the players making the move
+    rating_sum: u64,
+    rated: u32,
+}
+
+impl MoveStats {
+    /// Share of finished games with each result as white win, draw and
+    /// black win percentages
+    pub fn percentages(&self) -> (f64, f64, f64) {
+        let finished = self.white_wins + self.draws + self.black_wins;
+
+        if finished == 0 {
+            return (0.0, 0.0, 0.0);
+        }
+
+        let percent = |count: u32| count as f64 * 100.0 / finished as f64;
+
+        (
+            percent(self.white_wins),
+            percent(self.draws),
+            percent(self.black_wins),
+        )
+    }
+
+    pub fn average_rating(&self) -> Option<u32> {
+        if self.rated == 0 {
+            None
+        } else {
+            Some((self.rating_sum / self.rated as u64) as u32)
+        }
+    }
+}
+
+impl GameDb {
+    /// Legal moves played from the board's position, most played first
+    pub fn explore(&self, board: &Board) -> Vec<MoveStats> {
+        let legal = board.get_legal_moves();
+        let mut moves: Vec<MoveStats> = Vec::new();
+        let mut previous: Option<(u32, Move)> = None;
+
+        for entry in self.position(board.hash).iter() {
+            let mv = match entry.mv {
+                Some(mv) if legal.contains(&mv) => mv,
+                _ => continue,
+            };
+
+            // Entries of a game are next to each other, and a game repeating
+            // the position with the same move counts once
+            if previous == Some((entry.game, mv)) {
+                continue;
+            }
+
+            previous = Some((entry.game, mv));
+
+            let game = match self.games.get(entry.game as usize) {
+                Some(game) => game,
+                None => continue,
+            };
+
+            let idx = match moves.iter().position(|stats| stats.mv == mv) {
+                Some(idx) => idx,
+                None => {
+                    moves.push(MoveStats {
+                        mv,
+                        games: 0,
+                        white_wins: 0,
+                        draws: 0,
+                        black_wins: 0,
+                        rating_sum: 0,
+                        rated: 0,
+                    });
+                    moves.len() - 1
+                }
+            };
+
+            let stats = &mut moves[idx];
+            stats.games += 1;
+
+            match game.result.as_str() {
+                "1-0" => stats.white_wins += 1,
+                "0-1" => stats.black_wins += 1,
+                "1/2-1/2" => stats.draws += 1,
+                _ => {}
+            }
+
+            let rating = if board.turn == 1 {
+                game.white_elo
+            } else {
+                game.black_elo
+            };
+
+            if let Some(rating) = rating {
+                stats.rating_sum += rating as u64;
+                stats.rated += 1;
+            }
+        }
+
+        moves.sort_by_key(|stats| std::cmp::Reverse(stats.games));
+        moves
+    }
+}
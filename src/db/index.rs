@@ -0,0 +1,210 @@
+use crate::chess::{Board, Move, Piece, Position};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const ENTRY_SIZE: usize = 16;
+
+/// Order of the pieces in a material signature
+const SIGNATURE_PIECES: [Piece; 6] = [
+    Piece::King,
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Pawn,
+];
+
+/// A game reaching a position or material signature, with the move played
+/// from there
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub key: u64,
+    pub game: u32,
+    pub ply: u16,
+    pub mv: Option<Move>,
+}
+
+impl Entry {
+    fn from(bytes: &[u8]) -> Entry {
+        let mut key = [0; 8];
+        key.copy_from_slice(&bytes[0..8]);
+
+        Entry {
+            key: u64::from_be_bytes(key),
+            game: u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+            ply: u16::from_be_bytes([bytes[12], bytes[13]]),
+            mv: decode_move(u16::from_be_bytes([bytes[14], bytes[15]])),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.game.to_be_bytes());
+        bytes[12..14].copy_from_slice(&self.ply.to_be_bytes());
+        bytes[14..16].copy_from_slice(&encode_move(self.mv).to_be_bytes());
+        bytes
+    }
+}
+
+/// Entries sorted by key, so all games with a key are found by a binary
+/// search
+pub struct Index {
+    entries: Vec<Entry>,
+}
+
+impl Index {
+    /// Reads the index, starting empty when the file does not exist yet
+    pub fn open(path: &Path) -> io::Result<Index> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "index size is not a multiple of 16 bytes",
+            ));
+        }
+
+        Ok(Index {
+            entries: bytes.chunks(ENTRY_SIZE).map(Entry::from).collect(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * ENTRY_SIZE);
+
+        for entry in self.entries.iter() {
+            bytes.extend_from_slice(&entry.to_bytes());
+        }
+
+        fs::write(path, bytes)
+    }
+
+    /// Adds entries and restores the order
+    pub fn extend(&mut self, entries: Vec<Entry>) {
+        self.entries.extend(entries);
+        self.entries
+            .sort_by(|a, b| a.key.cmp(&b.key).then(a.game.cmp(&b.game)));
+    }
+
+    pub fn find(&self, key: u64) -> &[Entry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+}
+
+/// Squares in the low twelve bits, then the promotion or dropped piece with
+/// the top bit set for drops. Zero, a move from a1 to a1, stands for none.
+fn encode_move(mv: Option<Move>) -> u16 {
+    let mv = match mv {
+        Some(mv) => mv,
+        None => return 0,
+    };
+
+    let square = |pos: Position| (pos.x | pos.y << 3) as u16;
+
+    let piece = if mv.is_drop() {
+        mv.drop as u16 | 8
+    } else {
+        mv.promotion as u16
+    };
+
+    square(mv.from) | square(mv.to) << 6 | piece << 12
+}
+
+fn decode_move(data: u16) -> Option<Move> {
+    if data == 0 {
+        return None;
+    }
+
+    let data = data as i32;
+    let square = |bits: i32| Position::new(bits & 7, (bits >> 3) & 7);
+    let to = square(data >> 6);
+
+    let piece = match (data >> 12) & 7 {
+        1 => Piece::Bishop,
+        2 => Piece::King,
+        3 => Piece::Knight,
+        4 => Piece::Pawn,
+        5 => Piece::Queen,
+        6 => Piece::Rook,
+        _ => Piece::None,
+    };
+
+    if data & 0x8000 != 0 {
+        Move::new_drop(piece, to)
+    } else {
+        Some(Move::new(square(data), to)?.with_promotion(piece))
+    }
+}
+
+/// Pieces on the board such as `KRPvKR`, white first
+pub fn material_signature(board: &Board) -> String {
+    let mut counts = [[0; 6]; 2];
+
+    for row in board.grid.iter() {
+        for square in row.iter() {
+            if let Some(idx) = SIGNATURE_PIECES.iter().position(|p| *p == square.piece) {
+                counts[(square.player == 2) as usize][idx] += 1;
+            }
+        }
+    }
+
+    format_signature(&counts)
+}
+
+/// Puts the letters of a signature such as `kpkr` or `KRvKRP` in the usual
+/// order, or None if it is not one
+pub fn normalize_signature(text: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+
+    let sides: Vec<&str> = match lower.split_once('v') {
+        Some((white, black)) => vec![white, black],
+        // Without a separator the second king starts black's pieces
+        None => match lower.get(1..)?.find('k') {
+            Some(idx) => vec![&lower[..idx + 1], &lower[idx + 1..]],
+            None => return None,
+        },
+    };
+
+    let mut counts = [[0; 6]; 2];
+
+    for (side, letters) in sides.iter().enumerate() {
+        for letter in letters.chars() {
+            let piece = Piece::from_letter(letter);
+            let idx = SIGNATURE_PIECES.iter().position(|p| *p == piece)?;
+            counts[side][idx] += 1;
+        }
+
+        if counts[side][0] != 1 {
+            return None;
+        }
+    }
+
+    Some(format_signature(&counts))
+}
+
+fn format_signature(counts: &[[usize; 6]; 2]) -> String {
+    let side = |counts: &[usize; 6]| -> String {
+        SIGNATURE_PIECES
+            .iter()
+            .zip(counts.iter())
+            .map(|(piece, count)| piece.letter().to_string().repeat(*count))
+            .collect()
+    };
+
+    format!("{}v{}", side(&counts[0]), side(&counts[1]))
+}
+
+/// FNV-1a hash of a material signature, used as its index key
+pub fn signature_key(signature: &str) -> u64 {
+    signature.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
@@ -0,0 +1,389 @@
+mod explorer;
+mod index;
+
+pub use self::explorer::MoveStats;
+pub use self::index::{material_signature, normalize_signature, Entry};
+
+use self::index::{signature_key, Index};
+use crate::eco;
+use crate::pgn::Game;
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const GAMES_FILE: &str = "games.pgn";
+const LIST_FILE: &str = "games.tsv";
+const POSITIONS_FILE: &str = "positions.idx";
+const MATERIAL_FILE: &str = "material.idx";
+
+/// Where a game is stored and the tags it is searched by
+#[derive(Debug, Clone)]
+pub struct GameInfo {
+    pub id: u32,
+    offset: u64,
+    length: u64,
+    pub white: String,
+    pub black: String,
+    pub result: String,
+    pub date: String,
+    pub eco: String,
+    pub event: String,
+    pub white_elo: Option<u32>,
+    pub black_elo: Option<u32>,
+    /// Hash of the tags and moves, see `game_key`
+    key: u64,
+}
+
+impl GameInfo {
+    fn from(line: &str) -> Option<GameInfo> {
+        let fields: Vec<&str> = line.split('\t').collect();
+
+        // Lists written before games had keys have eleven fields
+        if fields.len() != 11 && fields.len() != 12 {
+            return None;
+        }
+
+        Some(GameInfo {
+            id: fields[0].parse().ok()?,
+            offset: fields[1].parse().ok()?,
+            length: fields[2].parse().ok()?,
+            white: fields[3].to_string(),
+            black: fields[4].to_string(),
+            result: fields[5].to_string(),
+            date: fields[6].to_string(),
+            eco: fields[7].to_string(),
+            event: fields[8].to_string(),
+            white_elo: fields[9].parse().ok(),
+            black_elo: fields[10].parse().ok(),
+            key: fields.get(11).and_then(|key| key.parse().ok()).unwrap_or(0),
+        })
+    }
+
+    fn to_line(&self) -> String {
+        let elo = |elo: Option<u32>| elo.map(|elo| elo.to_string()).unwrap_or_default();
+
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.id,
+            self.offset,
+            self.length,
+            self.white,
+            self.black,
+            self.result,
+            self.date,
+            self.eco,
+            self.event,
+            elo(self.white_elo),
+            elo(self.black_elo),
+            self.key
+        )
+    }
+}
+
+/// Inclusive range of tag values, where the upper end also matches values
+/// that only start with it, so "2020" covers every date in that year
+#[derive(Debug, Clone)]
+pub struct Range {
+    pub low: String,
+    pub high: String,
+}
+
+impl Range {
+    /// `<value>` or `<low>-<high>`
+    pub fn from(text: &str) -> Range {
+        let (low, high) = text.split_once('-').unwrap_or((text, text));
+
+        Range {
+            low: low.trim().to_uppercase(),
+            high: high.trim().to_uppercase(),
+        }
+    }
+
+    fn contains(&self, value: &str) -> bool {
+        let value = value.to_uppercase();
+        let prefix = value.get(..self.high.len()).unwrap_or(&value);
+        value >= self.low && prefix <= self.high.as_str()
+    }
+}
+
+/// Conditions a game has to meet, all of them when several are given
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Either side's name contains this, ignoring case
+    pub player: Option<String>,
+    pub white: Option<String>,
+    pub black: Option<String>,
+    pub result: Option<String>,
+    pub date: Option<Range>,
+    pub eco: Option<Range>,
+    /// Zobrist key of a position reached in the game
+    pub position: Option<u64>,
+    /// Material signature reached in the game, see `normalize_signature`
+    pub material: Option<String>,
+}
+
+/// Games in a directory: their PGN text one after another, a list with
+/// their tags, and indexes of the positions and material signatures they
+/// reach
+pub struct GameDb {
+    dir: PathBuf,
+    pub games: Vec<GameInfo>,
+    positions: Index,
+    material: Index,
+}
+
+/// `~/.rust-chess/games`, or the working directory without a home
+pub fn default_path() -> PathBuf {
+    match env::var_os("HOME") {
+        Some(home) => Path::new(&home).join(".rust-chess").join("games"),
+        None => PathBuf::from("games"),
+    }
+}
+
+impl GameDb {
+    /// Reads the database, starting empty when the directory does not exist
+    pub fn open(dir: &Path) -> io::Result<GameDb> {
+        let text = match fs::read_to_string(dir.join(LIST_FILE)) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+
+        let games = text
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(GameInfo::from)
+            .collect();
+
+        Ok(GameDb {
+            dir: dir.to_path_buf(),
+            games,
+            positions: Index::open(&dir.join(POSITIONS_FILE))?,
+            material: Index::open(&dir.join(MATERIAL_FILE))?,
+        })
+    }
+
+    /// Stores the games and indexes them, returning how many were added and
+    /// how many were skipped because the same tags and moves are already
+    /// stored. Games whose start position cannot be set up are left out.
+    pub fn import(&mut self, games: &[Game]) -> io::Result<(usize, usize)> {
+        fs::create_dir_all(&self.dir)?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(GAMES_FILE))?;
+
+        let mut offset = file.seek(SeekFrom::End(0))?;
+        let mut positions = Vec::new();
+        let mut material = Vec::new();
+        let mut added = 0;
+        let mut skipped = 0;
+        let mut keys: HashSet<u64> = self.games.iter().map(|game| game.key).collect();
+
+        for game in games.iter() {
+            let start = match game.start_board() {
+                Some(board) => board,
+                None => continue,
+            };
+
+            let key = game_key(game);
+
+            if !keys.insert(key) {
+                skipped += 1;
+                continue;
+            }
+
+            let mut board = start.clone();
+
+            let id = self.games.len() as u32;
+            let mut signature = String::new();
+
+            for ply in 0..=game.moves.len() {
+                let mv = game.moves.get(ply).copied();
+                let ply = ply.min(u16::MAX as usize) as u16;
+
+                positions.push(Entry {
+                    key: board.hash,
+                    game: id,
+                    ply,
+                    mv,
+                });
+
+                let current = material_signature(&board);
+
+                if current != signature {
+                    material.push(Entry {
+                        key: signature_key(&current),
+                        game: id,
+                        ply,
+                        mv: None,
+                    });
+                    signature = current;
+                }
+
+                if let Some(mv) = mv {
+                    board.make_move(&mv);
+                }
+            }
+
+            let text = format!("{}\n", game.to_pgn());
+            file.write_all(text.as_bytes())?;
+
+            let tag = |name: &str| game.tag(name).unwrap_or("").replace(['\t', '\n'], " ");
+
+            let elo = |name: &str| tag(name).parse::<u32>().ok();
+
+            let eco = match game.tag("ECO") {
+                Some(_) => tag("ECO"),
+                None => eco::classify_line(&start, &game.moves)
+                    .map(|opening| opening.eco.clone())
+                    .unwrap_or_default(),
+            };
+
+            self.games.push(GameInfo {
+                id,
+                offset,
+                length: text.len() as u64,
+                white: tag("White"),
+                black: tag("Black"),
+                result: game.result.clone(),
+                date: tag("Date"),
+                eco,
+                event: tag("Event"),
+                white_elo: elo("WhiteElo"),
+                black_elo: elo("BlackElo"),
+                key,
+            });
+
+            offset += text.len() as u64;
+            added += 1;
+        }
+
+        // Material often changes back and forth, so only the first time a
+        // game reaches a signature is kept
+        material.sort_by_key(|entry| (entry.game, entry.key, entry.ply));
+        material.dedup_by_key(|entry| (entry.game, entry.key));
+
+        self.positions.extend(positions);
+        self.material.extend(material);
+        self.save()?;
+
+        Ok((added, skipped))
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let mut list = File::create(self.dir.join(LIST_FILE))?;
+        writeln!(
+            list,
+            "# id\toffset\tlength\twhite\tblack\tresult\tdate\teco\tevent\twhiteelo\tblackelo\tkey"
+        )?;
+
+        for game in self.games.iter() {
+            writeln!(list, "{}", game.to_line())?;
+        }
+
+        self.positions.save(&self.dir.join(POSITIONS_FILE))?;
+        self.material.save(&self.dir.join(MATERIAL_FILE))
+    }
+
+    /// Games reaching a position, each with the ply it was reached at and
+    /// the move played from it
+    pub fn position(&self, key: u64) -> &[Entry] {
+        self.positions.find(key)
+    }
+
+    /// Games matching the query in the order they were imported
+    pub fn search(&self, query: &Query) -> Vec<&GameInfo> {
+        let mut ids: Option<Vec<u32>> = None;
+
+        let mut restrict = |entries: &[Entry]| {
+            let mut found: Vec<u32> = entries.iter().map(|entry| entry.game).collect();
+            found.sort_unstable();
+            found.dedup();
+
+            ids = Some(match ids.take() {
+                Some(ids) => ids
+                    .into_iter()
+                    .filter(|id| found.binary_search(id).is_ok())
+                    .collect(),
+                None => found,
+            });
+        };
+
+        if let Some(key) = query.position {
+            restrict(self.position(key));
+        }
+
+        if let Some(signature) = &query.material {
+            restrict(self.material.find(signature_key(signature)));
+        }
+
+        let contains = |name: &str, part: &Option<String>| match part {
+            Some(part) => name.to_lowercase().contains(&part.to_lowercase()),
+            None => true,
+        };
+
+        let candidates: Box<dyn Iterator<Item = &GameInfo>> = match ids {
+            Some(ids) => Box::new(
+                ids.into_iter()
+                    .filter_map(move |id| self.games.get(id as usize)),
+            ),
+            None => Box::new(self.games.iter()),
+        };
+
+        candidates
+            .filter(|game| contains(&game.white, &query.white))
+            .filter(|game| contains(&game.black, &query.black))
+            .filter(|game| {
+                query.player.is_none()
+                    || contains(&game.white, &query.player)
+                    || contains(&game.black, &query.player)
+            })
+            .filter(|game| match &query.result {
+                Some(result) => game.result == *result,
+                None => true,
+            })
+            .filter(|game| match &query.date {
+                Some(range) => range.contains(&game.date),
+                None => true,
+            })
+            .filter(|game| match &query.eco {
+                Some(range) => range.contains(&game.eco),
+                None => true,
+            })
+            .collect()
+    }
+
+    /// The game's PGN text as it was stored
+    pub fn read_text(&self, game: &GameInfo) -> io::Result<String> {
+        let mut file = File::open(self.dir.join(GAMES_FILE))?;
+        file.seek(SeekFrom::Start(game.offset))?;
+
+        let mut bytes = vec![0; game.length as usize];
+        file.read_exact(&mut bytes)?;
+
+        String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// Hash of a game's tags, result and moves, the same FNV hash as material
+/// signatures use
+fn game_key(game: &Game) -> u64 {
+    let mut text = String::new();
+
+    for (name, value) in game.tags.iter() {
+        text.push_str(&format!("{}\t{}\n", name, value));
+    }
+
+    text.push_str(&game.result);
+
+    for mv in game.moves.iter() {
+        text.push(' ');
+        text.push_str(&mv.to_uci());
+    }
+
+    signature_key(&text)
+}
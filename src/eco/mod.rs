@@ -0,0 +1,115 @@
+use crate::chess::{Board, Move, Variant};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Tab-separated ECO code, name, variation and moves from the initial
+/// position
+const OPENINGS: &str = include_str!("openings.tsv");
+
+#[derive(Debug, Clone)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String,
+    /// Empty when the opening has no named variation
+    pub variation: String,
+}
+
+impl Opening {
+    /// Name and variation as in "Sicilian Defense: Najdorf Variation"
+    pub fn full_name(&self) -> String {
+        if self.variation.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}: {}", self.name, self.variation)
+        }
+    }
+}
+
+/// Openings with the key of the position each one ends in
+struct Table {
+    openings: Vec<Opening>,
+    positions: HashMap<u64, usize>,
+}
+
+static TABLE: OnceLock<Table> = OnceLock::new();
+
+fn table() -> &'static Table {
+    TABLE.get_or_init(|| {
+        let mut table = Table {
+            openings: Vec::new(),
+            positions: HashMap::new(),
+        };
+
+        for line in OPENINGS.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+
+            if fields.len() != 4 {
+                continue;
+            }
+
+            let mut board = Board::new();
+            let mut parsed = true;
+
+            // Move numbers are skipped, and a line with a move that does not
+            // parse is left out
+            for san in fields[3].split_whitespace().filter(|s| !s.ends_with('.')) {
+                match board.parse_san(san) {
+                    Some(mv) => board.make_move(&mv),
+                    None => {
+                        parsed = false;
+                        break;
+                    }
+                }
+            }
+
+            if !parsed {
+                continue;
+            }
+
+            // The first and so more general name wins for repeated positions
+            table
+                .positions
+                .entry(board.hash)
+                .or_insert(table.openings.len());
+
+            table.openings.push(Opening {
+                eco: fields[0].to_string(),
+                name: fields[1].to_string(),
+                variation: fields[2].to_string(),
+            });
+        }
+
+        table
+    })
+}
+
+/// The most specific opening reached along the moves, recognising
+/// transpositions since openings are looked up by position
+pub fn classify_line(start: &Board, moves: &[Move]) -> Option<&'static Opening> {
+    if start.variant != Variant::Standard {
+        return None;
+    }
+
+    let table = table();
+    let mut board = start.clone();
+    let mut opening = None;
+
+    for mv in moves.iter() {
+        board.make_move(mv);
+
+        if let Some(idx) = table.positions.get(&board.hash) {
+            opening = Some(&table.openings[*idx]);
+        }
+    }
+
+    opening
+}
+
+/// The opening of the game played on the board so far
+pub fn classify(board: &Board) -> Option<&'static Opening> {
+    classify_line(&board.start_board(), &board.game_moves())
+}
@@ -0,0 +1,114 @@
+use crate::chess::{Board, Move, Variant};
+use crate::engine::{format_score, Engine, Limits, DEFAULT_HASH_MB};
+use std::time::Duration;
+
+/// Tactical test positions with their best moves in long algebraic notation
+const POSITIONS: [(&str, &str, &str); 12] = [
+    (
+        "WAC.001",
+        "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - -",
+        "g3g6",
+    ),
+    (
+        "WAC.002",
+        "8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - -",
+        "b3b2",
+    ),
+    (
+        "WAC.003",
+        "5rk1/1ppb3p/p1pb4/6q1/3P1p1r/2P1R2P/PP1BQ1P1/5RKN w - -",
+        "e3g3",
+    ),
+    (
+        "WAC.004",
+        "r1bq2rk/pp3pbp/2p1p1pQ/7P/3P4/2PB1N2/PP3PPR/2KR4 w - -",
+        "h6h7",
+    ),
+    (
+        "WAC.005",
+        "5k2/6pp/p1qN4/1p1p4/3P4/2PKP2Q/PP3r2/3R4 b - -",
+        "c6c4",
+    ),
+    ("WAC.006", "7k/p7/1R5K/6r1/6p1/6P1/8/8 w - -", "b6b7"),
+    (
+        "WAC.007",
+        "rnbqkb1r/pppp1ppp/8/4P3/6n1/7P/PPPNPPP1/R1BQKBNR b KQkq -",
+        "g4e3",
+    ),
+    (
+        "WAC.008",
+        "r4q1k/p2bR1rp/2p2Q1N/5p2/5p2/2P5/PP3PPP/R5K1 w - -",
+        "e7f7",
+    ),
+    (
+        "WAC.009",
+        "3q1rk1/p4pp1/2pb3p/3p4/6Pr/1PNQ4/P1PB1PP1/4RRK1 b - -",
+        "d6h2",
+    ),
+    (
+        "WAC.010",
+        "2br2k1/2q3rn/p2NppQ1/2p1P3/Pp5R/4P3/1P3PPP/3R2K1 w - -",
+        "h4h7",
+    ),
+    ("Back rank", "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - -", "d1d8"),
+    (
+        "Scholar's mate",
+        "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq -",
+        "f3f7",
+    ),
+];
+
+/// Searches every test position and reports speed and solved positions
+pub fn run_bench(depth: i32, threads: usize) {
+    let mut total_nodes = 0;
+    let mut total_time = Duration::from_secs(0);
+    let mut solved = 0;
+
+    let limits = Limits {
+        depth: Some(depth),
+        ..Limits::default()
+    };
+
+    for (idx, (name, fen, best)) in POSITIONS.iter().enumerate() {
+        let board = Board::from_fen(fen, Variant::Standard).unwrap();
+        let mut engine = Engine::new(DEFAULT_HASH_MB);
+        engine.set_threads(threads);
+        let result = engine.search(&board, &limits, &mut |_| ());
+
+        let expected = Move::from_uci(best);
+        let found = result.best_move == expected;
+
+        let played = match result.best_move {
+            Some(mv) => mv.to_uci(),
+            None => String::from("none"),
+        };
+
+        println!(
+            "{:>2}/{} {:<15} {:<6} {:<6} {:>7} {:>10} nodes {:>6} ms  {}",
+            idx + 1,
+            POSITIONS.len(),
+            name,
+            played,
+            format_score(result.score),
+            format!("depth {}", result.depth),
+            result.nodes,
+            result.time.as_millis(),
+            if found { "solved" } else { "" },
+        );
+
+        total_nodes += result.nodes;
+        total_time += result.time;
+
+        if found {
+            solved += 1;
+        }
+    }
+
+    let nps = total_nodes as f64 / total_time.as_secs_f64().max(0.001);
+
+    println!();
+    println!("Nodes:  {}", total_nodes);
+    println!("Time:   {} ms", total_time.as_millis());
+    println!("NPS:    {:.0}", nps);
+    println!("Solved: {}/{}", solved, POSITIONS.len());
+}
@@ -0,0 +1,229 @@
+use crate::chess::{Board, Piece, Variant};
+
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+#[rustfmt::skip]
+const KING_END_TABLE: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+/// Non-pawn material at the start of the game, used to taper the king table
+const START_PHASE: i32 = 2 * (2 * 320 + 2 * 330 + 2 * 500 + 900);
+
+pub fn piece_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::None => 0,
+        Piece::Pawn => 100,
+        Piece::Knight => 320,
+        Piece::Bishop => 330,
+        Piece::Rook => 500,
+        Piece::Queen => 900,
+        Piece::King => 0,
+    }
+}
+
+/// Static evaluation in centipawns from the side to move's point of view
+pub fn evaluate(board: &Board) -> i32 {
+    let score = match board.variant {
+        Variant::Antichess => evaluate_antichess(board),
+        Variant::RacingKings => evaluate_racing_kings(board),
+        _ => evaluate_standard(board),
+    };
+
+    if board.turn == 1 {
+        score
+    } else {
+        -score
+    }
+}
+
+fn evaluate_standard(board: &Board) -> i32 {
+    let mut score = 0;
+    let mut phase = 0;
+    let mut kings = [0; 2];
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let square = board.get(x, y);
+
+            if square.is_none() {
+                continue;
+            }
+
+            let sign = if square.is_white() { 1 } else { -1 };
+            let idx = table_index(x, y, square.player);
+
+            let positional = match square.piece {
+                Piece::Pawn => PAWN_TABLE[idx],
+                Piece::Knight => KNIGHT_TABLE[idx],
+                Piece::Bishop => BISHOP_TABLE[idx],
+                Piece::Rook => ROOK_TABLE[idx],
+                Piece::Queen => QUEEN_TABLE[idx],
+                Piece::King => {
+                    kings[(square.player - 1) as usize] = idx;
+                    0
+                }
+                Piece::None => 0,
+            };
+
+            if square.piece != Piece::Pawn {
+                phase += piece_value(square.piece);
+            }
+
+            score += sign * (piece_value(square.piece) + positional);
+        }
+    }
+
+    // Pieces in hand are worth a little more than on the board
+    if board.variant == Variant::Crazyhouse {
+        for player in 1..=2 {
+            let sign = if player == 1 { 1 } else { -1 };
+            let pocket = board.get_pocket(player);
+
+            for piece in pocket.pieces() {
+                score += sign * pocket.get(piece) * (piece_value(piece) + 20);
+            }
+        }
+    }
+
+    let phase = phase.min(START_PHASE);
+
+    for (player, idx) in kings.iter().enumerate() {
+        let sign = if player == 0 { 1 } else { -1 };
+        let king =
+            (KING_TABLE[*idx] * phase + KING_END_TABLE[*idx] * (START_PHASE - phase)) / START_PHASE;
+        score += sign * king;
+    }
+
+    score
+}
+
+fn evaluate_antichess(board: &Board) -> i32 {
+    let mut score = 0;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let square = board.get(x, y);
+
+            if square.is_none() {
+                continue;
+            }
+
+            // Every piece left is a liability
+            let sign = if square.is_white() { -1 } else { 1 };
+            score += sign * (100 + piece_value(square.piece) / 4);
+        }
+    }
+
+    score
+}
+
+fn evaluate_racing_kings(board: &Board) -> i32 {
+    let mut score = 0;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let square = board.get(x, y);
+
+            if square.is_none() {
+                continue;
+            }
+
+            let sign = if square.is_white() { 1 } else { -1 };
+
+            let value = if square.piece == Piece::King {
+                // Both kings race towards the eighth rank
+                y * y * 30
+            } else {
+                piece_value(square.piece)
+            };
+
+            score += sign * value;
+        }
+    }
+
+    score
+}
+
+/// Index into the tables above, which are laid out from white's point of view
+fn table_index(x: i32, y: i32, player: i32) -> usize {
+    let row = if player == 1 { 7 - y } else { y };
+    (row * 8 + x) as usize
+}
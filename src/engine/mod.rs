@@ -0,0 +1,253 @@
+mod bench;
+mod eval;
+mod ordering;
+mod search;
+mod skill;
+mod tt;
+
+pub use self::bench::run_bench;
+pub use self::search::{
+    format_score, mate_distance, tablebase_score, Clock, Limits, SearchResult, MATE,
+};
+pub use self::skill::{Skill, DEFAULT_ELO, MAX_ELO, MAX_LEVEL, MIN_ELO};
+
+use self::ordering::MoveOrdering;
+use self::search::Search;
+use self::tt::TranspositionTable;
+use crate::chess::{Board, Move};
+use crate::syzygy::Tablebase;
+use rand::Rng;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_HASH_MB: usize = 16;
+pub const MAX_THREADS: usize = 256;
+
+pub struct Engine {
+    tt: Arc<TranspositionTable>,
+    ordering: MoveOrdering,
+    stop: Arc<AtomicBool>,
+    threads: usize,
+    tablebase: Option<Arc<Tablebase>>,
+    skill: Option<Skill>,
+}
+
+impl Engine {
+    pub fn new(hash_mb: usize) -> Engine {
+        Engine {
+            tt: Arc::new(TranspositionTable::new(hash_mb)),
+            ordering: MoveOrdering::new(),
+            stop: Arc::new(AtomicBool::new(false)),
+            threads: 1,
+            tablebase: None,
+            skill: None,
+        }
+    }
+
+    pub fn set_hash(&mut self, hash_mb: usize) {
+        self.tt = Arc::new(TranspositionTable::new(hash_mb));
+    }
+
+    /// With a single thread the search is deterministic for a given depth or node limit
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.clamp(1, MAX_THREADS);
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Option<Arc<Tablebase>>) {
+        self.tablebase = tablebase;
+    }
+
+    /// Plays weaker than full strength, or at full strength with `None`
+    pub fn set_skill(&mut self, skill: Option<Skill>) {
+        self.skill = skill.filter(|skill| skill.level() < MAX_LEVEL);
+        // Noisy scores must not leak into searches at another level
+        self.clear();
+    }
+
+    /// Forgets everything learned from earlier searches
+    pub fn clear(&mut self) {
+        self.tt.clear();
+        self.ordering = MoveOrdering::new();
+    }
+
+    /// Flag that stops a running search when set from another thread. The
+    /// search leaves it set, so whoever sets it clears it for the next one.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn hashfull(&self) -> usize {
+        self.tt.hashfull()
+    }
+
+    /// Lazy SMP: helper threads search the same root at staggered depths and
+    /// share their results with the main thread through the hash table
+    pub fn search(
+        &mut self,
+        board: &Board,
+        limits: &Limits,
+        report: &mut dyn FnMut(&SearchResult),
+    ) -> SearchResult {
+        // Lower levels play endgames by searching like the rest of the game
+        let tablebase = self.tablebase.as_deref().filter(|_| self.skill.is_none());
+
+        // Within the tables the best move is known without searching
+        if let Some(tablebase) = tablebase {
+            let start = Instant::now();
+
+            if let Some((mv, wdl, _)) = tablebase.best_move(board) {
+                let result = SearchResult {
+                    best_move: Some(mv),
+                    score: tablebase_score(wdl, 0),
+                    depth: 1,
+                    time: start.elapsed(),
+                    pv: vec![mv],
+                    ..SearchResult::default()
+                };
+
+                report(&result);
+                return result;
+            }
+        }
+
+        self.tt.new_search();
+        self.ordering.age();
+
+        let limits = &match &self.skill {
+            Some(skill) => skill.limit(limits),
+            None => *limits,
+        };
+
+        let noise = self.skill.map_or(0, |skill| skill.noise());
+        let seed = rand::thread_rng().gen::<u64>();
+        let deadline = limits.time.map(|time| Instant::now() + time);
+
+        let tt = &*self.tt;
+        let stop = &*self.stop;
+
+        // Helpers get a flag of their own so finishing does not look like a
+        // stop request to the next search
+        let helpers_stop = AtomicBool::new(false);
+        let helpers_stop = &helpers_stop;
+        let ordering = &mut self.ordering;
+        let threads = self.threads;
+        let nodes = AtomicU64::new(0);
+        let nodes = &nodes;
+
+        let mut result = thread::scope(|scope| {
+            for idx in 1..threads {
+                scope.spawn(move || {
+                    let mut ordering = MoveOrdering::new();
+                    let mut search = Search::new(
+                        board,
+                        tt,
+                        &mut ordering,
+                        helpers_stop,
+                        nodes,
+                        *limits,
+                        tablebase,
+                    );
+                    search.set_noise(noise, seed);
+                    search.run(1 + (idx % 2) as i32, &mut |_| ());
+                });
+            }
+
+            let mut search = Search::new(board, tt, ordering, stop, nodes, *limits, tablebase);
+            search.set_noise(noise, seed);
+            let result = search.run(1, report);
+
+            // The main thread decides when the helpers are done
+            helpers_stop.store(true, Ordering::Relaxed);
+            result
+        });
+
+        result.nodes = nodes.load(Ordering::Relaxed);
+
+        if let Some(mv) = self.mistake(board, limits, deadline, &result) {
+            result.best_move = Some(mv);
+            result.pv = vec![mv];
+        }
+
+        result
+    }
+
+    /// Now and then at lower levels, a random move that scores within the
+    /// skill's margin of the best one. The replies are searched within what
+    /// is left of the time and nodes of the search, and no move is picked if
+    /// those run out or the search was stopped.
+    fn mistake(
+        &mut self,
+        board: &Board,
+        limits: &Limits,
+        deadline: Option<Instant>,
+        best: &SearchResult,
+    ) -> Option<Move> {
+        let skill = self.skill?;
+        let mut rng = rand::thread_rng();
+
+        if !rng.gen_bool(skill.mistake_chance()) {
+            return None;
+        }
+
+        let mut board = board.clone();
+        let moves = board.get_legal_moves();
+
+        if moves.is_empty() {
+            return None;
+        }
+
+        let mut candidates = Vec::new();
+        let nodes = AtomicU64::new(0);
+
+        for mv in moves {
+            if Some(mv) == best.best_move {
+                continue;
+            }
+
+            let time = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+            if self.stop.load(Ordering::Relaxed) || time == Some(Duration::ZERO) {
+                return None;
+            }
+
+            let limits = Limits {
+                depth: Some((limits.depth.unwrap_or(1) - 1).max(1)),
+                nodes: limits.nodes.map(|n| n.saturating_sub(best.nodes)),
+                time,
+                ..*limits
+            };
+
+            board.make_move(&mv);
+
+            let mut search = Search::new(
+                &board,
+                &self.tt,
+                &mut self.ordering,
+                &self.stop,
+                &nodes,
+                limits,
+                None,
+            );
+            let score = -search.run(1, &mut |_| ()).score;
+
+            // A reply cut short has no score worth comparing
+            if search.stopped() {
+                return None;
+            }
+
+            board.unmake_move();
+
+            if score >= best.score - skill.mistake_margin() {
+                candidates.push(mv);
+            }
+        }
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        Some(candidates[rng.gen_range(0, candidates.len())])
+    }
+}
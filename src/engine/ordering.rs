@@ -0,0 +1,89 @@
+use crate::chess::{Board, Move, Piece};
+use crate::engine::eval::piece_value;
+use std::cmp::Reverse;
+
+pub const MAX_PLY: usize = 128;
+
+const TT_MOVE_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+const KILLER_SCORE: i32 = 90_000;
+
+/// Killer and history tables gathered while searching
+pub struct MoveOrdering {
+    killers: Vec<[Option<Move>; 2]>,
+    history: Vec<i32>,
+}
+
+impl MoveOrdering {
+    pub fn new() -> MoveOrdering {
+        MoveOrdering {
+            killers: vec![[None; 2]; MAX_PLY],
+            history: vec![0; 2 * 64 * 64],
+        }
+    }
+
+    /// Forgets killers and fades history between searches
+    pub fn age(&mut self) {
+        self.killers.iter_mut().for_each(|k| *k = [None; 2]);
+        self.history.iter_mut().for_each(|h| *h /= 8);
+    }
+
+    pub fn add_killer(&mut self, ply: usize, mv: &Move) {
+        let killers = &mut self.killers[ply];
+
+        if killers[0] != Some(*mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(*mv);
+        }
+    }
+
+    pub fn add_history(&mut self, player: i32, mv: &Move, depth: i32) {
+        let idx = history_index(player, mv);
+        self.history[idx] = (self.history[idx] + depth * depth).min(KILLER_SCORE / 2);
+    }
+
+    /// Sorts moves best first: hash move, captures by MVV-LVA, killers, history
+    pub fn sort(&self, board: &Board, moves: &mut Vec<Move>, tt_move: Option<Move>, ply: usize) {
+        let killers = self.killers[ply.min(MAX_PLY - 1)];
+
+        let mut scored: Vec<(i32, Move)> = moves
+            .iter()
+            .map(|mv| {
+                let score = if Some(*mv) == tt_move {
+                    TT_MOVE_SCORE
+                } else if board.is_capture(mv) || mv.promotion != Piece::None {
+                    CAPTURE_SCORE + mvv_lva(board, mv)
+                } else if Some(*mv) == killers[0] {
+                    KILLER_SCORE
+                } else if Some(*mv) == killers[1] {
+                    KILLER_SCORE - 1
+                } else {
+                    self.history[history_index(board.turn, mv)]
+                };
+
+                (score, *mv)
+            })
+            .collect();
+
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+        *moves = scored.into_iter().map(|(_, mv)| mv).collect();
+    }
+}
+
+/// Most valuable victim, least valuable attacker
+pub fn mvv_lva(board: &Board, mv: &Move) -> i32 {
+    let victim = if board.is_en_passant(mv) {
+        Piece::Pawn
+    } else {
+        board.getp(&mv.to).piece
+    };
+
+    let attacker = board.getp(&mv.from).piece;
+    piece_value(victim) * 10 + piece_value(mv.promotion) - piece_value(attacker) / 10
+}
+
+fn history_index(player: i32, mv: &Move) -> usize {
+    let from = (mv.from.y * 8 + mv.from.x) as usize;
+    let to = (mv.to.y * 8 + mv.to.x) as usize;
+    ((player - 1) as usize) * 4096 + from * 64 + to
+}
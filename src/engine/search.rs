@@ -0,0 +1,615 @@
+use crate::chess::{Board, Move, Piece, Variant};
+use crate::engine::eval::{evaluate, piece_value};
+use crate::engine::ordering::{MoveOrdering, MAX_PLY};
+use crate::engine::tt::{Bound, TranspositionTable};
+use crate::syzygy::{Tablebase, Wdl};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+pub const MATE: i32 = 30000;
+pub const INFINITY: i32 = 32000;
+
+const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+/// Tablebase wins score below any mate so mates are still preferred
+const TABLEBASE_WIN: i32 = MATE_BOUND - MAX_PLY as i32;
+const DELTA_MARGIN: i32 = 200;
+const NULL_MOVE_REDUCTION: i32 = 2;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    pub depth: Option<i32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    /// Answer only once stopped, as for UCI `go infinite` and `go ponder`
+    pub infinite: bool,
+}
+
+impl Limits {
+    pub fn time(time: Duration) -> Limits {
+        Limits {
+            time: Some(time),
+            ..Limits::default()
+        }
+    }
+
+    /// Spreads the remaining time over the moves left, keeping a safety margin
+    pub fn clock(time: Duration, increment: Duration, moves_to_go: Option<u32>) -> Limits {
+        let budget = time / moves_to_go.unwrap_or(30).max(1) + increment * 3 / 4;
+        let margin = Duration::from_millis(50);
+        let budget = budget
+            .min(time.saturating_sub(margin))
+            .max(Duration::from_millis(10));
+
+        Limits::time(budget)
+    }
+}
+
+/// Time left for white and black with the increment added after each move
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Clock {
+    pub white: Duration,
+    pub black: Duration,
+    pub increment: Duration,
+}
+
+impl Clock {
+    pub fn new(time: Duration, increment: Duration) -> Clock {
+        Clock {
+            white: time,
+            black: time,
+            increment,
+        }
+    }
+
+    pub fn time(&self, player: i32) -> Duration {
+        if player == 1 {
+            self.white
+        } else {
+            self.black
+        }
+    }
+
+    /// Charges a move to a player, returning false when the flag fell
+    pub fn spend(&mut self, player: i32, elapsed: Duration) -> bool {
+        let time = if player == 1 {
+            &mut self.white
+        } else {
+            &mut self.black
+        };
+
+        if elapsed > *time {
+            *time = Duration::ZERO;
+            return false;
+        }
+
+        *time = *time - elapsed + self.increment;
+        true
+    }
+
+    /// Search limits for the player to move
+    pub fn limits(&self, player: i32) -> Limits {
+        Limits::clock(self.time(player), self.increment, None)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub depth: i32,
+    pub nodes: u64,
+    pub time: Duration,
+    pub pv: Vec<Move>,
+}
+
+pub struct Search<'a> {
+    board: Board,
+    tt: &'a TranspositionTable,
+    ordering: &'a mut MoveOrdering,
+    stop: &'a AtomicBool,
+    total_nodes: &'a AtomicU64,
+    limits: Limits,
+    tablebase: Option<&'a Tablebase>,
+    noise: i32,
+    noise_seed: u64,
+    start: Instant,
+    nodes: u64,
+    flushed_nodes: u64,
+    stopped: bool,
+    hashes: Vec<u64>,
+    pv: Vec<Vec<Move>>,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(
+        board: &Board,
+        tt: &'a TranspositionTable,
+        ordering: &'a mut MoveOrdering,
+        stop: &'a AtomicBool,
+        total_nodes: &'a AtomicU64,
+        limits: Limits,
+        tablebase: Option<&'a Tablebase>,
+    ) -> Search<'a> {
+        Search {
+            board: board.clone(),
+            tt,
+            ordering,
+            stop,
+            total_nodes,
+            limits,
+            tablebase,
+            noise: 0,
+            noise_seed: 0,
+            start: Instant::now(),
+            nodes: 0,
+            flushed_nodes: 0,
+            stopped: false,
+            hashes: board.position_hashes(),
+            pv: vec![Vec::new(); MAX_PLY + 1],
+        }
+    }
+
+    /// Adds up to `noise` centipawns to every evaluation, the same amount
+    /// for a position throughout the search for a given seed
+    pub fn set_noise(&mut self, noise: i32, seed: u64) {
+        self.noise = noise;
+        self.noise_seed = seed;
+    }
+
+    fn evaluate(&self) -> i32 {
+        let score = evaluate(&self.board);
+
+        if self.noise == 0 {
+            return score;
+        }
+
+        // SplitMix64 finalizer to spread the hash bits
+        let mut x = self.board.hash ^ self.noise_seed;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+
+        let range = self.noise as u64 * 2 + 1;
+        score + (x % range) as i32 - self.noise
+    }
+
+    /// Iterative deepening from `start_depth`, reporting each completed depth
+    pub fn run(&mut self, start_depth: i32, report: &mut dyn FnMut(&SearchResult)) -> SearchResult {
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY as i32 - 1);
+        // Always have a move to play, even if the first iteration is cut short
+        let mut result = SearchResult {
+            best_move: self.board.get_legal_moves().first().copied(),
+            ..SearchResult::default()
+        };
+
+        for depth in start_depth..=max_depth {
+            let score = self.negamax(depth, -INFINITY, INFINITY, 0, true);
+
+            if self.stopped && depth > start_depth {
+                break;
+            }
+
+            self.flush_nodes();
+
+            result.score = score;
+            result.depth = depth;
+            result.nodes = self.total_nodes.load(Ordering::Relaxed);
+            result.time = self.start.elapsed();
+            result.pv = self.pv[0].clone();
+
+            if let Some(mv) = result.pv.first() {
+                result.best_move = Some(*mv);
+            }
+
+            report(&result);
+
+            if self.stopped || score.abs() >= MATE_BOUND {
+                break;
+            }
+
+            // Another iteration would most likely not finish in time
+            if let Some(time) = self.limits.time {
+                if self.start.elapsed() > time / 2 {
+                    break;
+                }
+            }
+        }
+
+        self.flush_nodes();
+        result.nodes = self.total_nodes.load(Ordering::Relaxed);
+        result.time = self.start.elapsed();
+        result
+    }
+
+    /// Whether a limit or a stop request cut the search short
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Adds the nodes searched since the last call to the combined count
+    fn flush_nodes(&mut self) {
+        self.total_nodes
+            .fetch_add(self.nodes - self.flushed_nodes, Ordering::Relaxed);
+        self.flushed_nodes = self.nodes;
+    }
+
+    fn check_limits(&mut self) {
+        self.flush_nodes();
+
+        if self.stop.load(Ordering::Relaxed) {
+            self.stopped = true;
+        }
+
+        if let Some(nodes) = self.limits.nodes {
+            if self.total_nodes.load(Ordering::Relaxed) >= nodes {
+                self.stopped = true;
+            }
+        }
+
+        if let Some(time) = self.limits.time {
+            if self.start.elapsed() >= time {
+                self.stopped = true;
+            }
+        }
+    }
+
+    fn is_draw(&self) -> bool {
+        // A single repetition is enough to score the position as a draw
+        self.board.halfmove_clock >= 100 || self.hashes.contains(&self.board.hash)
+    }
+
+    /// Score for a position without legal moves, or a variant win or loss
+    fn terminal_score(&self, in_check: bool, ply: usize) -> Option<i32> {
+        let mate = MATE - ply as i32;
+
+        match self.board.variant {
+            Variant::Antichess => {
+                if self.board.can_move_any() {
+                    None
+                } else {
+                    Some(mate)
+                }
+            }
+            Variant::RacingKings => match self.board.get_outcome()?.winner() {
+                0 => Some(0),
+                player if player == self.board.turn => Some(mate),
+                _ => Some(-mate),
+            },
+            _ => {
+                if self.board.can_move_any() {
+                    None
+                } else if in_check {
+                    Some(-mate)
+                } else {
+                    Some(0)
+                }
+            }
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        depth: i32,
+        mut alpha: i32,
+        beta: i32,
+        ply: usize,
+        allow_null: bool,
+    ) -> i32 {
+        self.pv[ply].clear();
+
+        if self.nodes & 2047 == 0 {
+            self.check_limits();
+        }
+
+        if self.stopped {
+            return 0;
+        }
+
+        if ply > 0 && self.is_draw() {
+            return 0;
+        }
+
+        if ply >= MAX_PLY - 1 {
+            return self.evaluate();
+        }
+
+        let in_check = self.board.is_check();
+
+        // Check extension
+        let depth = if in_check { depth + 1 } else { depth };
+
+        if depth <= 0 {
+            return self.quiescence(alpha, beta, ply);
+        }
+
+        self.nodes += 1;
+
+        let hash = self.board.hash;
+
+        // Probe right after captures and pawn moves, where the 50-move counter is reset
+        if ply > 0 && self.board.halfmove_clock == 0 {
+            if let Some(wdl) = self.tablebase.and_then(|tb| tb.probe_wdl(&self.board)) {
+                let score = tablebase_score(wdl, ply);
+                self.tt.store(hash, depth, score, Bound::Exact, None);
+                return score;
+            }
+        }
+
+        let mut tt_move = None;
+
+        if let Some(entry) = self.tt.probe(hash) {
+            tt_move = entry.best_move;
+
+            if ply > 0 && entry.depth >= depth {
+                let score = score_from_tt(entry.score, ply);
+
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => (),
+                }
+            }
+        }
+
+        let mut moves = self.board.get_legal_moves();
+
+        if moves.is_empty() || self.board.variant == Variant::RacingKings {
+            if let Some(score) = self.terminal_score(in_check, ply) {
+                return score;
+            }
+        }
+
+        // Null move pruning, skipped where zugzwang is common
+        if allow_null
+            && ply > 0
+            && !in_check
+            && depth >= 3
+            && self.null_move_allowed()
+            && self.evaluate() >= beta
+        {
+            self.hashes.push(hash);
+            self.board.make_null_move();
+            let score = -self.negamax(
+                depth - 1 - NULL_MOVE_REDUCTION,
+                -beta,
+                -beta + 1,
+                ply + 1,
+                false,
+            );
+            self.board.unmake_move();
+            self.hashes.pop();
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta && score < MATE_BOUND {
+                return beta;
+            }
+        }
+
+        self.ordering.sort(&self.board, &mut moves, tt_move, ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+
+        for (idx, mv) in moves.iter().enumerate() {
+            let quiet = !self.board.is_capture(mv) && mv.promotion == Piece::None;
+
+            self.hashes.push(hash);
+            self.board.make_move(mv);
+
+            let gives_check = self.board.is_check();
+
+            let score = if idx == 0 {
+                -self.negamax(depth - 1, -beta, -alpha, ply + 1, true)
+            } else {
+                // Late move reductions for quiet moves ordered towards the back
+                let reduction = if depth >= 3 && idx >= 4 && quiet && !in_check && !gives_check {
+                    if idx >= 12 {
+                        2
+                    } else {
+                        1
+                    }
+                } else {
+                    0
+                };
+
+                let mut score =
+                    -self.negamax(depth - 1 - reduction, -alpha - 1, -alpha, ply + 1, true);
+
+                if score > alpha && reduction > 0 {
+                    score = -self.negamax(depth - 1, -alpha - 1, -alpha, ply + 1, true);
+                }
+
+                if score > alpha && score < beta {
+                    score = -self.negamax(depth - 1, -beta, -alpha, ply + 1, true);
+                }
+
+                score
+            };
+
+            self.board.unmake_move();
+            self.hashes.pop();
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(*mv);
+
+                if score > alpha {
+                    alpha = score;
+
+                    let mut pv = vec![*mv];
+                    pv.extend_from_slice(&self.pv[ply + 1]);
+                    self.pv[ply] = pv;
+
+                    if alpha >= beta {
+                        if quiet {
+                            self.ordering.add_killer(ply, mv);
+                            self.ordering.add_history(self.board.turn, mv, depth);
+                        }
+
+                        break;
+                    }
+                }
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        self.tt
+            .store(hash, depth, score_to_tt(best_score, ply), bound, best_move);
+        best_score
+    }
+
+    /// Capture-only search to settle the position before evaluating it
+    fn quiescence(&mut self, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        self.pv[ply].clear();
+        self.nodes += 1;
+
+        if self.nodes & 2047 == 0 {
+            self.check_limits();
+        }
+
+        if self.stopped {
+            return 0;
+        }
+
+        let stand_pat = self.evaluate();
+
+        if ply >= MAX_PLY - 1 || stand_pat >= beta {
+            return stand_pat;
+        }
+
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+
+        let mut moves: Vec<Move> = self
+            .board
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mv| self.board.is_capture(mv) || mv.promotion == Piece::Queen)
+            .collect();
+
+        self.ordering.sort(&self.board, &mut moves, None, ply);
+
+        for mv in moves.iter() {
+            // Delta pruning: skip captures that cannot raise alpha
+            let victim = if self.board.is_en_passant(mv) {
+                Piece::Pawn
+            } else {
+                self.board.getp(&mv.to).piece
+            };
+
+            let gain = piece_value(victim) + piece_value(mv.promotion);
+
+            if self.board.variant != Variant::Antichess && stand_pat + gain + DELTA_MARGIN < alpha {
+                continue;
+            }
+
+            self.board.make_move(mv);
+            let score = -self.quiescence(-beta, -alpha, ply + 1);
+            self.board.unmake_move();
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score > alpha {
+                alpha = score;
+
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        alpha
+    }
+
+    fn null_move_allowed(&self) -> bool {
+        if self.board.variant == Variant::Antichess || self.board.variant == Variant::RacingKings {
+            return false;
+        }
+
+        // Without pieces other than pawns zugzwang is likely
+        for y in 0..8 {
+            for x in 0..8 {
+                let square = self.board.get(x, y);
+
+                if square.player == self.board.turn
+                    && square.piece != Piece::Pawn
+                    && square.piece != Piece::King
+                {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// Mate scores are stored relative to the position rather than the root
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// Score of a tablebase result, preferring wins found closer to the root
+pub fn tablebase_score(wdl: Wdl, ply: usize) -> i32 {
+    match wdl {
+        Wdl::Win => TABLEBASE_WIN - ply as i32,
+        Wdl::CursedWin => 1,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -1,
+        Wdl::Loss => -TABLEBASE_WIN + ply as i32,
+    }
+}
+
+/// Moves until mate, negative when the side to move is getting mated
+pub fn mate_distance(score: i32) -> Option<i32> {
+    if score >= MATE_BOUND {
+        Some((MATE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        Some(-(MATE + score + 1) / 2)
+    } else {
+        None
+    }
+}
+
+/// Formats a score as pawns, or as moves to mate
+pub fn format_score(score: i32) -> String {
+    match mate_distance(score) {
+        Some(moves) => format!("#{}", moves),
+        None => format!("{:+.2}", score as f64 / 100.0),
+    }
+}
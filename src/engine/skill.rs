@@ -0,0 +1,68 @@
+use crate::engine::search::Limits;
+
+/// Full strength, where nothing is weakened
+pub const MAX_LEVEL: i32 = 20;
+pub const MIN_ELO: i32 = 800;
+pub const MAX_ELO: i32 = 2400;
+pub const DEFAULT_ELO: i32 = 1500;
+
+/// Weakened play for a level from 0 to `MAX_LEVEL`, combining a shallower
+/// search, noise in the evaluation and occasional deliberate inaccuracies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Skill {
+    level: i32,
+}
+
+impl Skill {
+    pub fn new(level: i32) -> Skill {
+        Skill {
+            level: level.clamp(0, MAX_LEVEL),
+        }
+    }
+
+    /// Level that plays at about the given rating, calibrated linearly so
+    /// level 0 is a beginner and the top of the range is full strength
+    pub fn from_elo(elo: i32) -> Skill {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        Skill::new((elo - MIN_ELO) * MAX_LEVEL / (MAX_ELO - MIN_ELO))
+    }
+
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    fn weakness(&self) -> i32 {
+        MAX_LEVEL - self.level
+    }
+
+    /// Caps depth and nodes so lower levels see less
+    pub fn limit(&self, limits: &Limits) -> Limits {
+        if self.weakness() == 0 {
+            return *limits;
+        }
+
+        let depth = 1 + self.level / 3;
+        let nodes = 200 << (self.level / 2);
+
+        Limits {
+            depth: Some(limits.depth.map_or(depth, |d| d.min(depth))),
+            nodes: Some(limits.nodes.map_or(nodes, |n| n.min(nodes))),
+            ..*limits
+        }
+    }
+
+    /// Largest random change to a static evaluation in centipawns
+    pub fn noise(&self) -> i32 {
+        self.weakness() * 10
+    }
+
+    /// Chance of replacing the best move with a worse one
+    pub fn mistake_chance(&self) -> f64 {
+        self.weakness() as f64 / 40.0
+    }
+
+    /// How much worse than the best move, in centipawns, a replacement may be
+    pub fn mistake_margin(&self) -> i32 {
+        self.weakness() * 15
+    }
+}
@@ -0,0 +1,193 @@
+use crate::chess::{Move, Piece, Position};
+use std::mem;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub depth: i32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+    age: u8,
+}
+
+/// Pieces in the order of their enum discriminants
+const PIECES: [Piece; 7] = [
+    Piece::None,
+    Piece::Bishop,
+    Piece::King,
+    Piece::Knight,
+    Piece::Pawn,
+    Piece::Queen,
+    Piece::Rook,
+];
+
+/// The key is stored xor'ed with the data so that torn writes from
+/// concurrent threads are detected as a key mismatch instead of being used
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    mask: usize,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    /// Allocates the table, rounding the entry count down to a power of two
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let bytes = size_mb.max(1) * 1024 * 1024;
+        let count = bytes / mem::size_of::<Slot>();
+        let count = 1 << (usize::BITS - 1 - count.leading_zeros());
+
+        let slots = (0..count)
+            .map(|_| Slot {
+                key: AtomicU64::new(0),
+                data: AtomicU64::new(0),
+            })
+            .collect();
+
+        TranspositionTable {
+            slots,
+            mask: count - 1,
+            age: AtomicU8::new(0),
+        }
+    }
+
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Marks existing entries as stale so they are replaced first
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = &self.slots[key as usize & self.mask];
+        let data = slot.data.load(Ordering::Relaxed);
+
+        if slot.key.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+
+        unpack(data)
+    }
+
+    pub fn store(&self, key: u64, depth: i32, score: i32, bound: Bound, best_move: Option<Move>) {
+        let slot = &self.slots[key as usize & self.mask];
+        let age = self.age.load(Ordering::Relaxed);
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let old_key = slot.key.load(Ordering::Relaxed) ^ old_data;
+        let old = unpack(old_data);
+
+        // Replace by depth, but always replace entries from earlier searches
+        if let Some(old) = old {
+            if old_key != key && old.age == age && old.depth > depth {
+                return;
+            }
+        }
+
+        // Keep the previous best move when a later search did not find one
+        let best_move = match (best_move, old) {
+            (None, Some(old)) if old_key == key => old.best_move,
+            _ => best_move,
+        };
+
+        let data = pack(&Entry {
+            depth,
+            score,
+            bound,
+            best_move,
+            age,
+        });
+
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// Permille of the table in use by the current search
+    pub fn hashfull(&self) -> usize {
+        let age = self.age.load(Ordering::Relaxed);
+        let sample = self.slots.len().min(1000);
+
+        self.slots[..sample]
+            .iter()
+            .filter_map(|slot| unpack(slot.data.load(Ordering::Relaxed)))
+            .filter(|entry| entry.age == age)
+            .count()
+            * 1000
+            / sample
+    }
+}
+
+/// Bits 0-15 score, 16-23 depth, 24-25 bound, 26-33 age, 34-52 move
+fn pack(entry: &Entry) -> u64 {
+    let score = entry.score.clamp(i16::MIN as i32, i16::MAX as i32) as i16 as u16 as u64;
+    let depth = entry.depth.clamp(i8::MIN as i32, i8::MAX as i32) as i8 as u8 as u64;
+
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+
+    let mv = match entry.best_move {
+        Some(mv) => {
+            1 | ((mv.from.y * 8 + mv.from.x) as u64) << 1
+                | ((mv.to.y * 8 + mv.to.x) as u64) << 7
+                | (mv.drop as u64) << 13
+                | (mv.promotion as u64) << 16
+        }
+        None => 0,
+    };
+
+    score | depth << 16 | bound << 24 | (entry.age as u64) << 26 | mv << 34
+}
+
+fn unpack(data: u64) -> Option<Entry> {
+    let bound = match (data >> 24) & 3 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        3 => Bound::Upper,
+        _ => return None,
+    };
+
+    let mv = data >> 34;
+
+    let best_move = if mv & 1 == 1 {
+        let from = ((mv >> 1) & 63) as i32;
+        let to = ((mv >> 7) & 63) as i32;
+
+        Some(Move {
+            from: Position::new(from % 8, from / 8),
+            to: Position::new(to % 8, to / 8),
+            drop: PIECES[((mv >> 13) & 7) as usize],
+            promotion: PIECES[((mv >> 16) & 7) as usize],
+        })
+    } else {
+        None
+    };
+
+    Some(Entry {
+        depth: (data >> 16) as u8 as i8 as i32,
+        score: data as u16 as i16 as i32,
+        bound,
+        best_move,
+        age: (data >> 26) as u8,
+    })
+}
@@ -1,83 +1,1646 @@
 use ansi_term::{ANSIString, Colour};
+mod analysis;
+mod arena;
+mod book;
 mod chess;
+mod clock;
+mod config;
+mod db;
+mod eco;
+mod engine;
 mod net;
+mod pgn;
+mod prompt;
+mod ratings;
 mod render;
+mod savegame;
+mod setup;
+mod syzygy;
+mod theme;
+mod tournament;
+mod tui;
+mod uci;
 
-use chess::{Board, Move, Piece, Position};
-use net::{DummyInterface, Interface, TcpInterface};
+use book::{Book, Selection};
+use chess::{Board, Move, Piece, Position, Variant};
+use clock::GameClock;
+use config::Config;
+use crossterm::event::{self, Event, KeyEventKind};
+use crossterm::terminal;
+use engine::{format_score, Engine, Limits, SearchResult, Skill, DEFAULT_HASH_MB, MAX_LEVEL};
+use net::{ComputerInterface, DummyInterface, Interface, TcpInterface};
 use rand::Rng;
+use savegame::{Opponent, SavedGame};
 use std::env;
+use std::fs;
 use std::io::{stdin, stdout, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use syzygy::Tablebase;
+use theme::Theme;
+
+const REVIEW_FILE: &str = "review.pgn";
+const HINT_TIME: Duration = Duration::from_secs(3);
 
 struct Context<'a> {
     board: Board,
     interface: Box<dyn Interface>,
     message: ANSIString<'a>,
     player: i32,
+    tablebase: Option<Arc<Tablebase>>,
+    engine: Engine,
+    /// White and black names when the game is rated
+    names: Option<[String; 2]>,
+    ratings: PathBuf,
+    resigned: bool,
+    /// Game database shown under the board, see `render::draw_explorer`
+    explorer: Option<db::GameDb>,
+    db_path: PathBuf,
+    /// Per-user settings, saved when the theme is changed
+    config: Config,
+    clock: GameClock,
+    prompt: prompt::Prompt,
+    opponent: Opponent,
+    /// File the game is written to after every move
+    autosave: PathBuf,
+    /// Moves as of the last autosave
+    autosaved: Vec<Move>,
+    /// Board turned around from the player's side with the flip command
+    flipped: bool,
+}
+
+impl<'a> Context<'a> {
+    /// Whether black is drawn at the bottom
+    fn reversed(&self) -> bool {
+        (self.player == 2) != self.flipped
+    }
+}
+
+const HELP: &str = "Usage: rust-chess [command] [options]
+
+Commands:
+  play                       Play at this terminal, the default
+  host <port>                Wait for a player to join over the network
+  join <host>:<port>         Join a game hosted with host
+  uci                        Talk to a chess GUI over UCI
+  setup                      Set up a position to play or export as FEN
+  perft <depth>              Count the positions reachable from --fen
+  analyze                    Search the --fen position to --depth
+  pgn <file> [number]        List the games in a PGN file or show one
+  review <file>              Annotate the games in a PGN file
+  export svg|png|gif <file>  Write a diagram or animation of --fen, or of
+                             game --game <n> in --pgn <file>, see export
+  db, ratings, match, tournament, makebook, bench
+
+Options:
+  --color white|black|random Side to play, asked when not given
+  --tc <seconds>[+<inc>]     Time control, such as 300+2
+  --fen <fen>                Start from this position
+  --resume <file>            Continue a game written by save or autosave
+  --autosave <file>          Where every move is saved, default
+                             ~/.rust-chess/autosave.pgn
+  --computer                 Play against the engine
+  --level <0-20>             Engine strength, 20 is full strength
+  --time <seconds>           Engine thinking time without --tc
+  --variant <name>           standard, crazyhouse, antichess or racingkings
+  --theme <name>             grey, green, blue, high-contrast, colour-blind
+  --pieces <set>             filled, outlined or letters
+  --display <mode>           colour, ascii or accessible
+  --tui                      Full-screen view with mouse input
+  --explorer                 Show the opening explorer
+  --name <name>              Your name for rated games
+  --config <file>            Settings file, default ~/.rust-chess/config
+  -h, --help                 Show this help
+
+Type help during a game for the commands at the move prompt.
+
+Any option can be given a default in the config file as a line such as
+'level = 10' or 'computer = true'.";
+
+/// Settings used by the commands, from the command line or the config file
+struct Options {
+    variant: Variant,
+    computer: bool,
+    explorer: bool,
+    full_screen: bool,
+    color: Option<i32>,
+    think_time: Duration,
+    threads: usize,
+    book_depth: i32,
+    book: Option<Book>,
+    tablebase: Option<Arc<Tablebase>>,
+    level: i32,
+    review_depth: i32,
+    ratings_path: PathBuf,
+    system: ratings::System,
+    db_path: PathBuf,
+    name: String,
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+
+    if take_flag(&mut args, "--help") || take_flag(&mut args, "-h") {
+        println!("{}", HELP);
+        return;
+    }
+
+    let config_path = take_option(&mut args, "--config")
+        .map(PathBuf::from)
+        .unwrap_or_else(config::default_path);
+
+    let config = match Config::open(&config_path) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("Could not read '{}': {}", config_path.display(), err);
+            return;
+        }
+    };
+
+    let options = set_display(&mut args, &config, &config_path)
+        .and_then(|()| read_options(&mut args, &config));
+
+    let options = match options {
+        Ok(options) => options,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    // Options only used when playing may still follow the program name
+    let command = match args.get(1) {
+        Some(arg) if !arg.starts_with('-') => arg.clone(),
+        _ => {
+            args.insert(1, String::from("play"));
+            String::from("play")
+        }
+    };
+
+    let result = match command.as_str() {
+        "play" | "host" | "join" | "setup" => run_play(args, &command, config, options),
+        "help" => {
+            println!("{}", HELP);
+            Ok(())
+        }
+        "bench" => run_bench(&args, options.threads),
+        "uci" => run_uci(),
+        "perft" => {
+            run_perft(&mut args, options.variant);
+            Ok(())
+        }
+        "analyze" => {
+            analyze_position(
+                &mut args,
+                options.variant,
+                options.review_depth,
+                options.threads,
+                &options.tablebase,
+            );
+            Ok(())
+        }
+        "pgn" => {
+            show_pgn(&args[2..]);
+            Ok(())
+        }
+        "makebook" => {
+            make_book(&args[2..], options.book_depth);
+            Ok(())
+        }
+        "match" => {
+            play_match(
+                &mut args,
+                options.variant,
+                options.threads,
+                &options.tablebase,
+            );
+            Ok(())
+        }
+        "tournament" => {
+            play_tournament(
+                &mut args,
+                options.variant,
+                options.think_time,
+                options.threads,
+                &options.tablebase,
+            );
+            Ok(())
+        }
+        "ratings" => {
+            show_ratings(&args[2..], &options.ratings_path, options.system);
+            Ok(())
+        }
+        "db" => {
+            game_db(&mut args, &options.db_path, options.variant);
+            Ok(())
+        }
+        "review" => {
+            review_file(
+                &args[2..],
+                options.review_depth,
+                options.threads,
+                &options.tablebase,
+            );
+            Ok(())
+        }
+        "export" => {
+            export_position(&mut args, options.variant);
+            Ok(())
+        }
+        _ => Err(format!("Unknown command '{}', see --help", command)),
+    };
+
+    if let Err(err) = result {
+        println!("{}", err);
+    }
+}
+
+/// Sets the theme, pieces and display mode
+fn set_display(args: &mut Vec<String>, config: &Config, config_path: &Path) -> Result<(), String> {
+    let in_config = |err: String| format!("{} in '{}'", err, config_path.display());
+
+    let mut theme = Theme::from_config(config).map_err(in_config)?;
+    let overrides = theme::Overrides::from_config(config).map_err(in_config)?;
+
+    if let Some(name) = take_option(args, "--theme") {
+        let preset = Theme::preset(&name).ok_or(format!(
+            "Unknown theme '{}', expected {}",
+            name,
+            theme::PRESETS.join(", ")
+        ))?;
+
+        theme = Theme {
+            glyphs: theme.glyphs,
+            ..preset
+        };
+    }
+
+    if let Some(name) = take_option(args, "--pieces") {
+        theme.glyphs = theme::Glyphs::from(&name).ok_or(format!(
+            "Unknown piece set '{}', expected {}",
+            name,
+            theme::GLYPH_SETS.join(", ")
+        ))?;
+    }
+
+    theme::set_overrides(overrides);
+    theme::set(theme);
+
+    // Detected from NO_COLOR and the terminal unless chosen
+    if let Some(name) = setting(args, config, "display") {
+        let mode = render::Mode::from(&name).ok_or(format!(
+            "Unknown display '{}', expected colour, ascii or accessible",
+            name
+        ))?;
+
+        render::set_mode(mode);
+    }
+
+    Ok(())
+}
+
+fn read_options(args: &mut Vec<String>, config: &Config) -> Result<Options, String> {
+    let variant = match setting(args, config, "variant") {
+        Some(name) => Variant::from(&name).ok_or(format!("Invalid variant '{}'", name))?,
+        None => Variant::Standard,
+    };
+
+    let computer = setting_flag(args, config, "computer");
+    let explorer = setting_flag(args, config, "explorer");
+    let full_screen = setting_flag(args, config, "tui");
+
+    let color = match setting(args, config, "color") {
+        Some(name) => match name.as_str() {
+            "w" | "white" => Some(1),
+            "b" | "black" => Some(2),
+            "r" | "random" => Some(rand::thread_rng().gen_range(1, 3)),
+            _ => {
+                return Err(format!(
+                    "Invalid color '{}', expected white, black or random",
+                    name
+                ))
+            }
+        },
+        None => None,
+    };
+
+    let think_time = match setting(args, config, "time") {
+        Some(time) => match time.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 => Duration::from_secs_f64(seconds),
+            _ => return Err(format!("Invalid time '{}'", time)),
+        },
+        None => Duration::from_secs(3),
+    };
+
+    let threads = match setting(args, config, "threads") {
+        Some(threads) => match threads.parse::<usize>() {
+            Ok(threads) if threads > 0 => threads,
+            _ => return Err(format!("Invalid thread count '{}'", threads)),
+        },
+        None => 1,
+    };
+
+    let book_depth = match setting(args, config, "book-depth") {
+        Some(depth) => match depth.parse::<i32>() {
+            Ok(depth) if depth > 0 => depth,
+            _ => return Err(format!("Invalid book depth '{}'", depth)),
+        },
+        None => book::DEFAULT_BOOK_DEPTH,
+    };
+
+    let book_best = setting_flag(args, config, "book-best");
+
+    let book = match setting(args, config, "book") {
+        Some(path) => match Book::open(&path) {
+            Ok(mut book) => {
+                book.depth = book_depth;
+
+                if book_best {
+                    book.selection = Selection::Best;
+                }
+
+                Some(book)
+            }
+            Err(err) => return Err(format!("Could not open book '{}': {}", path, err)),
+        },
+        None => None,
+    };
+
+    let tablebase = match setting(args, config, "syzygy") {
+        Some(path) => match Tablebase::open(&path) {
+            Ok(tablebase) => Some(Arc::new(tablebase)),
+            Err(err) => return Err(format!("Could not read tablebases in '{}': {}", path, err)),
+        },
+        None => None,
+    };
+
+    let level = match setting(args, config, "level") {
+        Some(level) => match level.parse::<i32>() {
+            Ok(level) if (0..=MAX_LEVEL).contains(&level) => level,
+            _ => {
+                return Err(format!(
+                    "Invalid level '{}', expected 0 to {}",
+                    level, MAX_LEVEL
+                ))
+            }
+        },
+        None => MAX_LEVEL,
+    };
+
+    let review_depth = match setting(args, config, "depth") {
+        Some(depth) => match depth.parse::<i32>() {
+            Ok(depth) if depth > 0 => depth,
+            _ => return Err(format!("Invalid depth '{}'", depth)),
+        },
+        None => analysis::DEFAULT_REVIEW_DEPTH,
+    };
+
+    let ratings_path = setting(args, config, "ratings")
+        .map(PathBuf::from)
+        .unwrap_or_else(ratings::default_path);
+
+    let system = match setting(args, config, "system") {
+        Some(name) => ratings::System::from(&name).ok_or(format!(
+            "Unknown rating system '{}', expected elo or glicko2",
+            name
+        ))?,
+        None => ratings::System::Elo,
+    };
+
+    let db_path = setting(args, config, "db")
+        .map(PathBuf::from)
+        .unwrap_or_else(db::default_path);
+
+    let name = setting(args, config, "name")
+        .or_else(|| env::var("USER").ok())
+        .map(|name| name.replace('\t', " ").trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| String::from("Player"));
+
+    Ok(Options {
+        variant,
+        computer,
+        explorer,
+        full_screen,
+        color,
+        think_time,
+        threads,
+        book_depth,
+        book,
+        tablebase,
+        level,
+        review_depth,
+        ratings_path,
+        system,
+        db_path,
+        name,
+    })
+}
+
+fn run_bench(args: &[String], threads: usize) -> Result<(), String> {
+    let depth = args.get(2).and_then(|d| d.parse::<i32>().ok()).unwrap_or(6);
+    engine::run_bench(depth, threads);
+    Ok(())
+}
+
+fn run_uci() -> Result<(), String> {
+    uci::run();
+    Ok(())
+}
+
+/// Plays a game at this terminal, over the network with `host` and `join`,
+/// or from a position set up first with `setup`
+fn run_play(
+    mut args: Vec<String>,
+    command: &str,
+    config: Config,
+    mut options: Options,
+) -> Result<(), String> {
+    // The editor starts an ordinary game from the position it sets up
+    let setup = command == "setup";
+    let command = if setup { "play" } else { command };
+
+    let mut control = match setting(&mut args, &config, "tc") {
+        Some(tc) => match arena::parse_time_control(&tc) {
+            Some(clock) => Some(clock),
+            None => {
+                return Err(format!(
+                    "Invalid time control '{}', expected <seconds>[+<increment>]",
+                    tc
+                ))
+            }
+        },
+        None => None,
+    };
+
+    let mut start = match take_option(&mut args, "--fen") {
+        Some(fen) if command == "play" => match Board::from_fen(&fen, options.variant) {
+            Some(board) => Some(board),
+            None => return Err(format!("Invalid FEN '{}'", fen)),
+        },
+        Some(_) => {
+            return Err(String::from(
+                "A game over the network starts from the initial position",
+            ))
+        }
+        None => None,
+    };
+
+    let resume = match take_option(&mut args, "--resume") {
+        Some(_) if setup => {
+            return Err(String::from(
+                "A resumed game cannot be edited, use load in the game instead",
+            ))
+        }
+        Some(_) if start.is_some() => {
+            return Err(String::from(
+                "A resumed game continues from where it was saved, without --fen",
+            ))
+        }
+        Some(path) if command == "play" => match savegame::read(Path::new(&path)) {
+            Ok(saved) if !saved.in_progress() => {
+                return Err(format!("The game in '{}' has already ended", path))
+            }
+            Ok(saved) if saved.opponent == Opponent::Network => {
+                return Err(format!(
+                    "The game in '{}' was played over the network, load it at the prompt instead",
+                    path
+                ))
+            }
+            Ok(saved) => Some((PathBuf::from(path), saved)),
+            Err(err) => return Err(format!("Could not resume '{}': {}", path, err)),
+        },
+        Some(_) => return Err(String::from("A game over the network cannot be resumed")),
+        None => None,
+    };
+
+    // The saved game decides everything about how it is played
+    if let Some((path, saved)) = &resume {
+        let board = saved
+            .board()
+            .ok_or(format!("Invalid FEN tag in '{}'", path.display()))?;
+
+        options.variant = board.variant;
+        start = Some(board);
+        options.color = Some(saved.player);
+        control = saved.control;
+        options.computer = false;
+
+        if let Opponent::Computer { level } = saved.opponent {
+            options.computer = true;
+            options.level = level;
+        }
+    }
+
+    let autosave_path = match &resume {
+        Some((path, _)) => path.clone(),
+        None => setting(&mut args, &config, "autosave")
+            .map(PathBuf::from)
+            .unwrap_or_else(savegame::default_path),
+    };
+
+    if let Some(arg) = args.get(if command == "play" { 2 } else { 3 }) {
+        return Err(format!("Unexpected argument '{}', see --help", arg));
+    }
+
+    let mut prompt = prompt::Prompt::new(prompt::default_path());
+
+    if setup {
+        let board = start
+            .take()
+            .unwrap_or_else(|| Board::new_variant(options.variant));
+
+        match setup::run(board, &mut prompt) {
+            Some(board) => start = Some(board),
+            None => return Ok(()),
+        }
+    }
+
+    let Pairing {
+        interface,
+        player,
+        opponent,
+    } = open_interface(&args, command, &mut options)?;
+
+    if player != 1 && player != 2 {
+        return Err(format!("Invalid player '{}'", player));
+    }
+
+    let board = start.unwrap_or_else(|| Board::new_variant(options.variant));
+
+    let clock = match &resume {
+        Some((_, saved)) => GameClock::resume(control, saved.times, board.turn),
+        None => GameClock::new(control, board.turn),
+    };
+
+    let opponent_kind = if command != "play" {
+        Opponent::Network
+    } else if options.computer {
+        Opponent::Computer {
+            level: options.level,
+        }
+    } else {
+        Opponent::Local
+    };
+
+    if resume.is_none() {
+        if let Ok(saved) = savegame::read(&autosave_path) {
+            if saved.in_progress() && !saved.game.moves.is_empty() {
+                println!(
+                    "An unfinished game is saved in '{}', which the first move replaces. \
+                     Continue it with --resume instead.",
+                    autosave_path.display()
+                );
+            }
+        }
+    }
+
+    let mut ctx = Context {
+        player,
+        autosaved: board.game_moves(),
+        clock,
+        board,
+        interface: interface,
+        message: ANSIString::from(""),
+        engine: new_engine(options.threads, &options.tablebase),
+        tablebase: options.tablebase,
+        names: None,
+        ratings: options.ratings_path,
+        resigned: false,
+        explorer: None,
+        db_path: options.db_path,
+        config,
+        prompt,
+        opponent: opponent_kind,
+        autosave: autosave_path,
+        flipped: false,
+    };
+
+    if options.explorer {
+        toggle_explorer(&mut ctx);
+    }
+
+    let color = player_color(ctx.player);
+
+    if !ctx.interface.is_local() {
+        let mut message = format!("You are playing as {}", color);
+
+        if let Some((opponent, rating)) = opponent {
+            message += &format!(" against {} ({})", opponent, rating);
+
+            ctx.names = Some(if player == 1 {
+                [options.name, opponent]
+            } else {
+                [opponent, options.name]
+            });
+        }
+
+        ctx.message = ANSIString::from(message);
+    }
+
+    //ctx.board.randomize();
+    //ctx.board.test();
+
+    if options.full_screen {
+        tui::run(&mut ctx).map_err(|err| format!("Could not run the full-screen view: {}", err))?;
+    } else {
+        main_loop(&mut ctx);
+    }
+
+    // Record how the game ended, which may have been a resignation
+    if !ctx.board.game_moves().is_empty() {
+        ctx.autosaved.clear();
+        autosave(&mut ctx);
+    }
+
+    record_rating(&ctx);
+
+    if ctx.board.get_outcome().is_some() {
+        offer_review(&mut ctx, options.review_depth);
+    }
+
+    Ok(())
+}
+
+/// Who the game is played against and with which side
+struct Pairing {
+    interface: Box<dyn Interface>,
+    player: i32,
+    /// Name and rating, when the opponent has one
+    opponent: Option<(String, i32)>,
+}
+
+/// Joining takes the variant from the host
+fn open_interface(
+    args: &[String],
+    command: &str,
+    options: &mut Options,
+) -> Result<Pairing, String> {
+    match command {
+        "join" => {
+            let host = match args.get(2) {
+                Some(host) if host.contains(':') => host,
+                Some(host) => {
+                    return Err(format!(
+                        "Invalid address '{}', expected <host>:<port>",
+                        host
+                    ))
+                }
+                None => return Err(String::from("Usage: join <host>:<port>")),
+            };
+
+            let mut client = TcpInterface::client(host)
+                .map_err(|err| format!("Could not connect to '{}': {}", host, err))?;
+
+            let player = match (client.get_player(), client.get_variant()) {
+                (Some(side), Some(variant)) => {
+                    options.variant = variant;
+                    side
+                }
+                _ => return Err(format!("'{}' did not start a game", host)),
+            };
+
+            let opponent = client.get_rating();
+            let rating = own_rating(&options.ratings_path, &options.name, options.system);
+            client.send_rating(&options.name, rating);
+            Ok(Pairing {
+                interface: Box::new(client),
+                player,
+                opponent,
+            })
+        }
+        "host" => {
+            let port = match args.get(2).map(|port| port.parse::<u16>()) {
+                Some(Ok(port)) if port > 0 => port,
+                Some(_) => return Err(format!("Invalid port '{}', expected 1 to 65535", args[2])),
+                None => return Err(String::from("Usage: host <port>")),
+            };
+
+            println!("Waiting for a player to join on port {}...", port);
+
+            let mut server = TcpInterface::server(port)
+                .map_err(|err| format!("Could not host on port {}: {}", port, err))?;
+
+            let player = options.color.unwrap_or_else(select_player);
+            server.send_player(player);
+            server.send_variant(options.variant);
+            let rating = own_rating(&options.ratings_path, &options.name, options.system);
+            server.send_rating(&options.name, rating);
+            let opponent = server.get_rating();
+            Ok(Pairing {
+                interface: Box::new(server),
+                player,
+                opponent,
+            })
+        }
+        _ if options.computer => {
+            let player = options.color.unwrap_or_else(select_player);
+            let level = options.level;
+            let mut engine = Engine::new(DEFAULT_HASH_MB);
+            engine.set_threads(options.threads);
+            engine.set_tablebase(options.tablebase.clone());
+            engine.set_skill(Some(Skill::new(level)));
+
+            let engine_name = if level == MAX_LEVEL {
+                String::from("rust-chess")
+            } else {
+                format!("rust-chess level {}", level)
+            };
+
+            let rating = own_rating(&options.ratings_path, &engine_name, options.system);
+
+            let interface = ComputerInterface::new(
+                engine,
+                Limits::time(options.think_time),
+                options.book.take(),
+            );
+
+            Ok(Pairing {
+                interface: Box::new(interface),
+                player,
+                opponent: Some((engine_name, rating)),
+            })
+        }
+        _ => Ok(Pairing {
+            interface: Box::new(DummyInterface {}),
+            player: options.color.unwrap_or(1),
+            opponent: None,
+        }),
+    }
+}
+
+/// A named option from the command line, or else its default from the
+/// config file
+fn setting(args: &mut Vec<String>, config: &Config, name: &str) -> Option<String> {
+    take_option(args, &format!("--{}", name)).or_else(|| config.get(name).map(String::from))
+}
+
+fn setting_flag(args: &mut Vec<String>, config: &Config, name: &str) -> bool {
+    take_flag(args, &format!("--{}", name))
+        || matches!(config.get(name), Some("true" | "yes" | "on" | "1"))
+}
+
+/// Updates the ratings of both players once a rated game is decided
+fn record_rating(ctx: &Context) {
+    let names = match &ctx.names {
+        Some(names) => names,
+        None => return,
+    };
+
+    if ctx.board.game_moves().is_empty() {
+        return;
+    }
+
+    let winner = match game_winner(ctx) {
+        Some(winner) => winner,
+        None => return,
+    };
+
+    ratings::record_game(&ctx.ratings, &names[0], &names[1], winner);
+}
+
+/// Winner of a finished game, 0 for a draw, including games lost on time
+/// or by resigning
+fn game_winner(ctx: &Context) -> Option<i32> {
+    match (ctx.board.get_outcome(), ctx.clock.flagged()) {
+        (Some(outcome), _) => Some(outcome.winner()),
+        (None, Some(player)) => Some(3 - player),
+        _ if ctx.interface.has_resigned() => Some(ctx.player),
+        _ if ctx.resigned => Some(3 - ctx.player),
+        _ => None,
+    }
+}
+
+fn own_rating(path: &Path, name: &str, system: ratings::System) -> i32 {
+    match ratings::RatingStore::open(path) {
+        Ok(store) => store.get(name).value(system),
+        Err(_) => ratings::RatingStore::default_rating(system),
+    }
+}
+
+fn show_ratings(names: &[String], path: &Path, system: ratings::System) {
+    let store = match ratings::RatingStore::open(path) {
+        Ok(store) => store,
+        Err(err) => {
+            println!("Could not read '{}': {}", path.display(), err);
+            return;
+        }
+    };
+
+    let ranking: Vec<_> = store
+        .ranking(system)
+        .into_iter()
+        .filter(|(name, _)| names.is_empty() || names.contains(name))
+        .collect();
+
+    if ranking.is_empty() {
+        println!("No rated players in '{}'", path.display());
+        return;
+    }
+
+    let width = ranking
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+
+    println!(
+        "{:>3}  {:<width$}  {:>6}  {:>5}  {:>5}  {:>5}  {:>5}  {:>5}",
+        "#",
+        "Player",
+        "Rating",
+        "RD",
+        "Games",
+        "Won",
+        "Lost",
+        "Drawn",
+        width = width
+    );
+
+    for (rank, (name, rating)) in ranking.iter().enumerate() {
+        let deviation = match system {
+            ratings::System::Elo => String::from("-"),
+            ratings::System::Glicko2 => format!("{:.0}", rating.deviation),
+        };
+
+        println!(
+            "{:>3}  {:<width$}  {:>6}  {:>5}  {:>5}  {:>5}  {:>5}  {:>5}",
+            rank + 1,
+            name,
+            rating.value(system),
+            deviation,
+            rating.games(),
+            rating.wins,
+            rating.losses,
+            rating.draws,
+            width = width
+        );
+    }
+}
+
+fn make_book(args: &[String], depth: i32) {
+    if args.len() < 2 {
+        println!("Usage: makebook <book.bin> <games.pgn>...");
+        return;
+    }
+
+    let mut games = Vec::new();
+
+    for path in args[1..].iter() {
+        match pgn::read_file(path) {
+            Ok(mut file_games) => games.append(&mut file_games),
+            Err(err) => {
+                println!("Could not read '{}': {}", path, err);
+                return;
+            }
+        }
+    }
+
+    let book = book::build_book(&games, depth as usize * 2);
+
+    match book.save(&args[0]) {
+        Ok(()) => println!(
+            "Wrote {} entries from {} games to '{}'",
+            book.len(),
+            games.len(),
+            args[0]
+        ),
+        Err(err) => println!("Could not write '{}': {}", args[0], err),
+    }
+}
+
+/// What can follow the file name of the export command, in a game or not
+const EXPORT_OPTIONS: &str =
+    "Options: e2e4 for an arrow, d5 for a circled square, a number for the size
+         in pixels, flip, plain to not highlight moves, and for gif the
+         seconds per move such as 1.5s";
+
+/// Writes a diagram of the starting position, a FEN or the final position
+/// of a PGN game, or an animation of the game, as for the export command
+/// during a game
+fn export_position(args: &mut Vec<String>, variant: Variant) {
+    const USAGE: &str = "Usage: export svg|png|gif <file> [--fen <fen>] [--pgn <file> [--game <n>]]
+                    [--flip] [options]
+Draws the starting position, the --fen position, or the last position of
+game <n> in the --pgn file, the first game without --game, whose moves a
+gif animates";
+
+    let flipped = take_flag(args, "--flip");
+    let fen = take_option(args, "--fen");
+    let pgn_path = take_option(args, "--pgn");
+    let number = take_option(args, "--game").and_then(|n| n.parse::<usize>().ok());
+
+    if args.len() < 4 {
+        println!("{}\n{}", USAGE, EXPORT_OPTIONS);
+        return;
+    }
+
+    let board = if let Some(path) = pgn_path {
+        let games = match pgn::read_file(&path) {
+            Ok(games) => games,
+            Err(err) => {
+                println!("Could not read '{}': {}", path, err);
+                return;
+            }
+        };
+
+        let game = match games.get(number.unwrap_or(1).saturating_sub(1)) {
+            Some(game) => game,
+            None => {
+                println!("No game {} in '{}'", number.unwrap_or(1), path);
+                return;
+            }
+        };
+
+        let mut board = match game.start_board() {
+            Some(board) => board,
+            None => {
+                println!("Invalid FEN tag in '{}'", path);
+                return;
+            }
+        };
+
+        for mv in game.moves.iter() {
+            board.make_move(mv);
+        }
+
+        board
+    } else if let Some(fen) = fen {
+        match Board::from_fen(&fen, variant) {
+            Some(board) => board,
+            None => {
+                println!("Invalid FEN '{}'", fen);
+                return;
+            }
+        }
+    } else {
+        Board::new_variant(variant)
+    };
+
+    let args: Vec<&str> = args[2..].iter().map(String::as_str).collect();
+
+    match export_diagram(&board, flipped, &args) {
+        Ok(message) | Err(message) => println!("{}", message),
+    }
+}
+
+/// Writes the board to a file as `svg|png <file>` or the game so far as
+/// `gif <file>`, followed by options, returning the message to show
+fn export_diagram(board: &Board, flipped: bool, args: &[&str]) -> Result<String, String> {
+    const USAGE: &str = "Usage: export svg|png|gif <file> [options]";
+
+    let (format, path) = match args {
+        [format, path, ..] => (*format, *path),
+        _ => return Err(format!("{}\n{}", USAGE, EXPORT_OPTIONS)),
+    };
+
+    let mut diagram = render::Diagram {
+        flipped,
+        ..render::Diagram::default()
+    };
+
+    let mut delay = 1.0;
+
+    for arg in args[2..].iter() {
+        if let Ok(size) = arg.parse::<u32>() {
+            diagram.size = size;
+        } else if let Some(seconds) = arg.strip_suffix('s').and_then(|s| s.parse::<f64>().ok()) {
+            delay = seconds;
+        } else if *arg == "flip" {
+            diagram.flipped = !diagram.flipped;
+        } else if *arg == "plain" {
+            diagram.last_move = false;
+        } else if !diagram.mark(arg) {
+            return Err(format!("Invalid option '{}'\n{}", arg, EXPORT_OPTIONS));
+        }
+    }
+
+    if diagram.size == 0 || diagram.size > 4096 {
+        return Err(String::from("The size must be from 1 to 4096 pixels"));
+    }
+
+    let contents = match format {
+        "svg" => Ok(render::board_svg(board, &diagram).into_bytes()),
+        "png" => render::board_png(board, &diagram),
+        "gif" => {
+            // GIF delays are in hundredths of a second
+            let delay = (delay.max(0.0) * 100.0).round().min(u16::MAX as f64) as u16;
+            render::game_gif(&board.start_board(), &board.game_moves(), &diagram, delay)
+        }
+        _ => return Err(format!("Unknown format '{}'\n{}", format, USAGE)),
+    };
+
+    match contents.and_then(|contents| fs::write(path, contents)) {
+        Ok(()) => Ok(format!("Wrote '{}'", path)),
+        Err(err) => Err(format!("Could not write '{}': {}", path, err)),
+    }
+}
+
+/// Imports games into the local database, or lists or exports those
+/// matching the filters
+fn game_db(args: &mut Vec<String>, path: &Path, variant: Variant) {
+    const USAGE: &str = "Usage: db import <games.pgn>...
+       db search [filters]
+       db export <out.pgn> [filters]
+Filters: --player, --white, --black <name>, --result 1-0|0-1|1/2-1/2,
+         --date <from>[-<to>], --eco <from>[-<to>], --fen <position>,
+         --moves \"<san moves>\", --material <signature such as KRPvKR>";
+
+    let mut query = db::Query {
+        player: take_option(args, "--player"),
+        white: take_option(args, "--white"),
+        black: take_option(args, "--black"),
+        result: take_option(args, "--result"),
+        date: take_option(args, "--date").map(|date| db::Range::from(&date)),
+        eco: take_option(args, "--eco").map(|eco| db::Range::from(&eco)),
+        ..db::Query::default()
+    };
+
+    if let Some(fen) = take_option(args, "--fen") {
+        match Board::from_fen(&fen, variant) {
+            Some(board) => query.position = Some(board.hash),
+            None => {
+                println!("Invalid FEN '{}'", fen);
+                return;
+            }
+        }
+    }
+
+    if let Some(moves) = take_option(args, "--moves") {
+        let mut board = Board::new_variant(variant);
+
+        for san in moves.split_whitespace() {
+            match board.parse_san(san) {
+                Some(mv) => board.make_move(&mv),
+                None => {
+                    println!("Invalid move '{}'", san);
+                    return;
+                }
+            }
+        }
+
+        query.position = Some(board.hash);
+    }
+
+    if let Some(material) = take_option(args, "--material") {
+        match db::normalize_signature(&material) {
+            Some(signature) => query.material = Some(signature),
+            None => {
+                println!("Invalid material signature '{}'", material);
+                return;
+            }
+        }
+    }
+
+    let mut database = match db::GameDb::open(path) {
+        Ok(database) => database,
+        Err(err) => {
+            println!("Could not open the database '{}': {}", path.display(), err);
+            return;
+        }
+    };
+
+    match args.get(2).map(|s| s.as_str()) {
+        Some("import") if args.len() > 3 => {
+            for file in args[3..].iter() {
+                let result = pgn::read_file(file).and_then(|games| database.import(&games));
+
+                match result {
+                    Ok((added, 0)) => println!("Imported {} games from '{}'", added, file),
+                    Ok((added, skipped)) => println!(
+                        "Imported {} games from '{}', skipped {} already stored",
+                        added, file, skipped
+                    ),
+                    Err(err) => println!("Could not import '{}': {}", file, err),
+                }
+            }
+
+            println!("{} games in '{}'", database.games.len(), path.display());
+        }
+        Some("search") => {
+            let games = database.search(&query);
+
+            for game in games.iter() {
+                println!(
+                    "{:>6}  {} - {}  {}  {}  {}  {}",
+                    game.id + 1,
+                    game.white,
+                    game.black,
+                    game.result,
+                    game.date,
+                    game.eco,
+                    game.event
+                );
+            }
+
+            println!("{} of {} games", games.len(), database.games.len());
+        }
+        Some("export") if args.len() > 3 => {
+            let games = database.search(&query);
+
+            let result = std::fs::File::create(&args[3]).and_then(|mut file| {
+                for game in games.iter() {
+                    file.write_all(database.read_text(game)?.as_bytes())?;
+                }
+
+                Ok(())
+            });
+
+            match result {
+                Ok(()) => println!("Wrote {} games to '{}'", games.len(), args[3]),
+                Err(err) => println!("Could not write '{}': {}", args[3], err),
+            }
+        }
+        _ => println!("{}", USAGE),
+    }
+}
+
+/// Engine-vs-engine games, see `arena::run_match`
+fn play_match(
+    args: &mut Vec<String>,
+    variant: Variant,
+    threads: usize,
+    tablebase: &Option<Arc<Tablebase>>,
+) {
+    const USAGE: &str = "Usage: match --engine <spec> --engine <spec> [--games N] \
+[--concurrency N] [--tc <seconds>[+<inc>]] [--openings <file>] [--plies N] \
+[--pgnout <file>] [--sprt <elo0>,<elo1>] [--resign <cp>] [--draw <move>] [--maxmoves N]";
+
+    let mut engines = Vec::new();
+
+    while let Some(spec) = take_option(args, "--engine") {
+        match arena::EngineSpec::from(&spec) {
+            Some(engine) => engines.push(engine),
+            None => {
+                println!("Invalid engine '{}'", spec);
+                return;
+            }
+        }
+    }
+
+    if engines.len() != 2 {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let number = |args: &mut Vec<String>, name: &str, default: usize| -> Option<usize> {
+        match take_option(args, name) {
+            Some(value) => match value.parse::<usize>() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    println!("Invalid value '{}' for {}", value, name);
+                    None
+                }
+            },
+            None => Some(default),
+        }
+    };
+
+    let games = match number(args, "--games", 10) {
+        Some(games) => games,
+        None => return,
+    };
+    let concurrency = match number(args, "--concurrency", 1) {
+        Some(concurrency) => concurrency,
+        None => return,
+    };
+    let plies = match number(args, "--plies", 8) {
+        Some(plies) => plies,
+        None => return,
+    };
+
+    let mut adjudication = arena::Adjudication {
+        tablebase: tablebase.clone(),
+        ..arena::Adjudication::default()
+    };
+
+    match number(args, "--resign", adjudication.resign_score as usize) {
+        Some(score) => adjudication.resign_score = score as i32,
+        None => return,
+    }
+    match number(args, "--draw", adjudication.draw_move as usize) {
+        Some(mv) => adjudication.draw_move = mv as i32,
+        None => return,
+    }
+    match number(args, "--maxmoves", adjudication.max_moves as usize) {
+        Some(moves) => adjudication.max_moves = moves as i32,
+        None => return,
+    }
+
+    let tc = take_option(args, "--tc").unwrap_or_else(|| String::from("10+0.1"));
+
+    let clock = match arena::parse_time_control(&tc) {
+        Some(clock) => clock,
+        None => {
+            println!("Invalid time control '{}'", tc);
+            return;
+        }
+    };
+
+    let sprt = match take_option(args, "--sprt") {
+        Some(bounds) => {
+            let elos: Vec<f64> = bounds.split(',').filter_map(|b| b.parse().ok()).collect();
+
+            if elos.len() != 2 || elos[0] >= elos[1] {
+                println!("Invalid SPRT bounds '{}'", bounds);
+                return;
+            }
+
+            arena::Sprt::new(elos[0], elos[1])
+        }
+        None => arena::Sprt::new(0.0, 5.0),
+    };
+
+    let openings = match take_option(args, "--openings") {
+        Some(path) => match arena::load_openings(&path, plies, variant) {
+            Ok(openings) if !openings.is_empty() => openings,
+            Ok(_) => {
+                println!("No openings found in '{}'", path);
+                return;
+            }
+            Err(err) => {
+                println!("Could not read '{}': {}", path, err);
+                return;
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let settings = arena::MatchSettings {
+        engines: [engines[0].clone(), engines[1].clone()],
+        games,
+        concurrency,
+        openings,
+        variant,
+        clock,
+        adjudication,
+        pgn_out: take_option(args, "--pgnout"),
+        sprt,
+        threads,
+    };
+
+    arena::run_match(&settings);
+}
+
+/// Round robin or Swiss events between engines and people, see
+/// `tournament::run_tournament`
+fn play_tournament(
+    args: &mut Vec<String>,
+    variant: Variant,
+    think_time: Duration,
+    threads: usize,
+    tablebase: &Option<Arc<Tablebase>>,
+) {
+    const USAGE: &str = "Usage: tournament --player [name=]<spec> --player [name=]<spec>... \
+[--format roundrobin|swiss] [--rounds N] [--tc <seconds>[+<inc>]] [--pgnout <file>]
+Specs: human, tcp:<port>, internal, internal:<level> or a UCI engine command";
+
+    let mut participants = Vec::new();
+
+    while let Some(spec) = take_option(args, "--player") {
+        match tournament::Participant::from(&spec) {
+            Some(participant) => participants.push(participant),
+            None => {
+                println!("Invalid player '{}'", spec);
+                return;
+            }
+        }
+    }
+
+    if participants.len() < 2 {
+        println!("{}", USAGE);
+        return;
+    }
+
+    let format = match take_option(args, "--format") {
+        Some(name) => match tournament::Format::from(&name) {
+            Some(format) => format,
+            None => {
+                println!("Unknown format '{}'", name);
+                return;
+            }
+        },
+        None => tournament::Format::RoundRobin,
+    };
+
+    // Enough Swiss rounds to separate the field, or one round robin cycle
+    let default_rounds = match format {
+        tournament::Format::RoundRobin => 1,
+        tournament::Format::Swiss => {
+            participants.len().next_power_of_two().trailing_zeros() as usize + 1
+        }
+    };
+
+    let rounds = match take_option(args, "--rounds") {
+        Some(rounds) => match rounds.parse::<usize>() {
+            Ok(rounds) if rounds > 0 => rounds,
+            _ => {
+                println!("Invalid number of rounds '{}'", rounds);
+                return;
+            }
+        },
+        None => default_rounds,
+    };
+
+    let clock = match take_option(args, "--tc") {
+        Some(tc) => match arena::parse_time_control(&tc) {
+            Some(clock) => Some(clock),
+            None => {
+                println!("Invalid time control '{}'", tc);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let mut settings = tournament::TournamentSettings {
+        participants,
+        format,
+        rounds,
+        variant,
+        clock,
+        limits: Limits::time(think_time),
+        adjudication: arena::Adjudication {
+            tablebase: tablebase.clone(),
+            ..arena::Adjudication::default()
+        },
+        pgn_out: take_option(args, "--pgnout"),
+        threads,
+    };
+
+    tournament::run_tournament(&mut settings);
+}
+
+fn review_file(args: &[String], depth: i32, threads: usize, tablebase: &Option<Arc<Tablebase>>) {
+    if args.is_empty() {
+        println!("Usage: review <games.pgn> [annotated.pgn]");
+        return;
+    }
+
+    let games = match pgn::read_file(&args[0]) {
+        Ok(games) => games,
+        Err(err) => {
+            println!("Could not read '{}': {}", args[0], err);
+            return;
+        }
+    };
+
+    let output = args.get(1).map(|s| s.as_str()).unwrap_or(REVIEW_FILE);
+    let mut engine = new_engine(threads, tablebase);
+    let mut text = String::new();
+
+    for (idx, game) in games.iter().enumerate() {
+        println!("Game {} of {}", idx + 1, games.len());
+
+        match analysis::review_game(&mut engine, game, depth) {
+            Some(review) => {
+                analysis::print_summary(&review);
+                text.push_str(&review.game.to_pgn());
+                text.push('\n');
+            }
+            None => println!("Skipping game with an invalid start position"),
+        }
+    }
+
+    match std::fs::write(output, text) {
+        Ok(()) => println!("Wrote annotated games to '{}'", output),
+        Err(err) => println!("Could not write '{}': {}", output, err),
+    }
+}
+
+/// The --fen position, or the initial one, printing an error for a bad FEN
+fn fen_option(args: &mut Vec<String>, variant: Variant) -> Option<Board> {
+    match take_option(args, "--fen") {
+        Some(fen) => {
+            let board = Board::from_fen(&fen, variant);
+
+            if board.is_none() {
+                println!("Invalid FEN '{}'", fen);
+            }
+
+            board
+        }
+        None => Some(Board::new_variant(variant)),
+    }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+/// Counts the move sequences of each length up to the depth, splitting the
+/// deepest count by first move
+fn run_perft(args: &mut Vec<String>, variant: Variant) {
+    let mut board = match fen_option(args, variant) {
+        Some(board) => board,
+        None => return,
+    };
 
-    let player: i32;
+    let depth = match args.get(2).map(|depth| depth.parse::<u32>()) {
+        Some(Ok(depth)) if depth > 0 => depth,
+        _ => {
+            println!("Usage: perft <depth> [--fen <fen>] [--variant <name>]");
+            return;
+        }
+    };
 
-    let interface: Box<dyn Interface> = match args.get(1) {
-        Some(s) => {
-            if s.contains(':') {
-                let mut client = TcpInterface::client(s);
-                player = client.get_player().unwrap();
-                Box::new(client)
-            } else {
-                let port = s.to_string().parse::<u16>().unwrap_or(0);
+    let start = std::time::Instant::now();
+    let mut total = 0;
 
-                if port == 0 {
-                    println!("Invalid port");
-                    return;
-                }
+    for mv in board.get_legal_moves() {
+        let san = board.to_san(&mv);
+        board.make_move(&mv);
+        let nodes = board.perft(depth - 1);
+        board.unmake_move();
+
+        println!("{:<8} {}", san, nodes);
+        total += nodes;
+    }
+
+    let seconds = start.elapsed().as_secs_f64();
+    println!("\nNodes: {}", total);
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        seconds,
+        total as f64 / seconds.max(1e-9)
+    );
+}
+
+/// Prints the evaluation and principal variation of the --fen position at
+/// each depth
+fn analyze_position(
+    args: &mut Vec<String>,
+    variant: Variant,
+    depth: i32,
+    threads: usize,
+    tablebase: &Option<Arc<Tablebase>>,
+) {
+    let board = match fen_option(args, variant) {
+        Some(board) => board,
+        None => return,
+    };
+
+    if board.get_outcome().is_some() {
+        println!("The game is over in this position");
+        return;
+    }
+
+    let mut engine = new_engine(threads, tablebase);
+
+    let limits = Limits {
+        depth: Some(depth),
+        ..Limits::default()
+    };
+
+    let mut report = |result: &SearchResult| {
+        println!(
+            "depth {:>2}  {:>7}  nodes {:>9}  {}",
+            result.depth,
+            white_score(&board, result.score),
+            result.nodes,
+            board.line_to_san(&result.pv)
+        );
+    };
+
+    let result = engine.search(&board, &limits, &mut report);
+
+    if let Some(mv) = result.best_move {
+        println!("\nBest move: {}", board.to_san(&mv));
+    }
+}
 
-                let mut server = TcpInterface::server(port);
-                player = select_player();
-                server.send_player(player);
-                Box::new(server)
+/// Lists the games in a PGN file, or shows the moves and final position of
+/// the game with the given number
+fn show_pgn(args: &[String]) {
+    let path = match args.first() {
+        Some(path) => path,
+        None => {
+            println!("Usage: pgn <games.pgn> [number]");
+            return;
+        }
+    };
+
+    let games = match pgn::read_file(path) {
+        Ok(games) => games,
+        Err(err) => {
+            println!("Could not read '{}': {}", path, err);
+            return;
+        }
+    };
+
+    let number = match args.get(1).map(|n| n.parse::<usize>()) {
+        Some(Ok(number)) => number,
+        Some(Err(_)) => {
+            println!("Invalid game number '{}'", args[1]);
+            return;
+        }
+        None => {
+            for (idx, game) in games.iter().enumerate() {
+                println!(
+                    "{:>4}  {} - {}  {}  {} moves",
+                    idx + 1,
+                    game.tag("White").unwrap_or("?"),
+                    game.tag("Black").unwrap_or("?"),
+                    game.result,
+                    game.moves.len().div_ceil(2)
+                );
             }
+
+            return;
         }
+    };
+
+    let game = match number.checked_sub(1).and_then(|idx| games.get(idx)) {
+        Some(game) => game,
         None => {
-            player = 1;
-            Box::new(DummyInterface {})
+            println!(
+                "No game {} in '{}', which has {}",
+                number,
+                path,
+                games.len()
+            );
+            return;
         }
     };
 
-    if player != 1 && player != 2 {
-        println!("Invalid player '{}'", player);
+    let mut board = match game.start_board() {
+        Some(board) => board,
+        None => {
+            println!("Invalid FEN tag in game {}", number);
+            return;
+        }
+    };
+
+    for mv in game.moves.iter() {
+        board.make_move(mv);
+    }
+
+    print!("{}", game.to_pgn());
+    render::draw_board(&board, false);
+}
+
+/// Lets the player replay a finished game through the engine
+fn offer_review(ctx: &mut Context, depth: i32) {
+    print!(
+        "Review the game? ({}es/{}o): ",
+        render::paint(&Colour::Green.paint("y")),
+        render::paint(&Colour::Green.paint("n"))
+    );
+
+    stdout().flush().unwrap();
+
+    if !matches!(read_line().trim(), "y" | "yes") {
         return;
     }
 
-    let mut ctx = Context {
-        player,
-        board: Board::new(),
-        interface: interface,
-        message: ANSIString::from(""),
-    };
+    let game = pgn::Game::from_board(&ctx.board);
 
-    let color = player_color(ctx.player);
+    if let Some(review) = analysis::review_game(&mut ctx.engine, &game, depth) {
+        analysis::print_summary(&review);
 
-    if !ctx.interface.is_local() {
-        ctx.message = ANSIString::from(format!("You are playing as {}", color));
+        match std::fs::write(REVIEW_FILE, review.game.to_pgn()) {
+            Ok(()) => println!("Wrote annotated game to '{}'", REVIEW_FILE),
+            Err(err) => println!("Could not write '{}': {}", REVIEW_FILE, err),
+        }
+    }
+}
+
+fn new_engine(threads: usize, tablebase: &Option<Arc<Tablebase>>) -> Engine {
+    let mut engine = Engine::new(DEFAULT_HASH_MB);
+    engine.set_threads(threads);
+    engine.set_tablebase(tablebase.clone());
+    engine
+}
+
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(idx) => {
+            args.remove(idx);
+            true
+        }
+        None => false,
     }
+}
 
-    //ctx.board.randomize();
-    //ctx.board.test();
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let idx = args.iter().position(|arg| arg == name)?;
 
-    main_loop(&mut ctx);
+    if idx + 1 >= args.len() {
+        args.remove(idx);
+        return None;
+    }
+
+    let value = args.remove(idx + 1);
+    args.remove(idx);
+    Some(value)
 }
 
 fn select_player() -> i32 {
     loop {
         print!(
             "Select color ({}hite/{}lack/{}andom): ",
-            Colour::Green.paint("w"),
-            Colour::Green.paint("b"),
-            Colour::Green.paint("r")
+            render::paint(&Colour::Green.paint("w")),
+            render::paint(&Colour::Green.paint("b")),
+            render::paint(&Colour::Green.paint("r"))
         );
 
         stdout().flush().unwrap();
@@ -95,32 +1658,62 @@ fn select_player() -> i32 {
 
 fn main_loop(ctx: &mut Context) {
     loop {
-        render::draw_board(&ctx.board, ctx.player == 2);
+        ctx.clock.update(ctx.board.turn);
+        autosave(ctx);
+        render::draw_board(&ctx.board, ctx.reversed());
+
+        if let Some(opening) = eco::classify(&ctx.board) {
+            let name = format!("{} {}", opening.eco, opening.full_name());
+            println!("\n{}", render::paint(&Colour::White.paint(name)));
+        }
+
+        if let Some(database) = &ctx.explorer {
+            render::draw_explorer(&ctx.board, &database.explore(&ctx.board));
+        }
 
         println!();
 
         if ctx.message.len() > 0 {
-            println!("{}", ctx.message.to_string());
+            println!("{}", render::paint(&ctx.message));
             ctx.message = ANSIString::from("");
         }
 
-        if ctx.board.is_checkmate() {
-            println!("{}", Colour::Blue.paint("Checkmate!"));
+        if let Some(outcome) = ctx.board.get_outcome() {
+            println!(
+                "{}",
+                render::paint(&Colour::Blue.paint(outcome.to_string()))
+            );
+            ctx.clock.stop();
             break;
-        } else if ctx.board.is_stalemate() {
-            println!("{}", Colour::Blue.paint("Stalemate!"));
+        } else if let Some(player) = ctx.clock.flagged() {
+            let message = format!("{} lost on time", player_color(player));
+            println!("{}", render::paint(&Colour::Blue.paint(message)));
             break;
         } else if ctx.board.is_check() {
-            println!("{}", Colour::Blue.paint("Check!"));
+            println!("{}", render::paint(&Colour::Blue.paint("Check!")));
         }
 
         let color = player_color(ctx.board.turn);
 
-        if ctx.interface.is_local() || ctx.player == ctx.board.turn {
-            print!("{} move: ", color);
-            stdout().flush().unwrap();
+        if let Some(wdl) = ctx
+            .tablebase
+            .as_ref()
+            .and_then(|tb| tb.probe_wdl(&ctx.board))
+        {
+            let hint = format!("Tablebase {} for {}", wdl, color);
+            println!("{}", render::paint(&Colour::Blue.paint(hint)));
+        }
 
-            if !handle_input(ctx) {
+        if ctx.clock.control.is_some() {
+            println!(
+                "White {}  Black {}",
+                clock::format_time(ctx.clock.shown(1)),
+                clock::format_time(ctx.clock.shown(2))
+            );
+        }
+
+        if ctx.interface.is_local() || ctx.player == ctx.board.turn {
+            if !handle_input(ctx, &format!("{} move: ", color)) {
                 return;
             }
 
@@ -128,60 +1721,508 @@ fn main_loop(ctx: &mut Context) {
         } else {
             println!("Waiting for {}...", color);
 
+            if let Some(clock) = ctx.clock.remaining() {
+                ctx.interface.set_clock(clock);
+            }
+
             if !ctx.interface.wait(&mut ctx.board) {
                 println!("Player disconnected");
                 return;
             }
+
+            if ctx.interface.has_resigned() {
+                println!(
+                    "{}",
+                    render::paint(&Colour::Blue.paint(format!("{} resigns", color)))
+                );
+                return;
+            }
         }
     }
 }
 
-fn handle_input(ctx: &mut Context) -> bool {
-    let line = read_line();
-    let input = line.trim();
+/// Reads a line at the prompt, leaving the game unfinished so it can be
+/// resumed when input ends, such as when the terminal is closed
+fn handle_input(ctx: &mut Context, prompt: &str) -> bool {
+    match ctx.prompt.read(prompt, &ctx.board) {
+        Some(line) => handle_command(ctx, line.trim()),
+        None => {
+            println!(
+                "\nThe game is saved in '{}', continue it with --resume",
+                ctx.autosave.display()
+            );
+            false
+        }
+    }
+}
 
+/// Runs a command or move typed by the player, returning false to quit
+fn handle_command(ctx: &mut Context, input: &str) -> bool {
     let args: Vec<&str> = input.split_whitespace().collect();
     let command = *args.get(0).unwrap_or(&"");
 
     match command {
-        "q" | "quit" | "exit" => {
+        "q" | "quit" | "exit" | "resign" => {
             //
             ctx.interface.send_surrender();
+            ctx.resigned = true;
             return false;
         }
-        _ => handle_move(ctx, args),
+        "help" => ctx.message = ANSIString::from(prompt::help()),
+        "flip" => ctx.flipped = !ctx.flipped,
+        "fen" => ctx.message = Colour::Green.paint(ctx.board.to_fen()),
+        "pgn" => ctx.message = ANSIString::from(current_game(ctx).to_pgn().trim_end().to_string()),
+        "save" => save_game(ctx, &args[1..]),
+        "load" => load_game(ctx, &args[1..]),
+        "moves" => list_moves(ctx),
+        "history" => show_history(ctx),
+        "undo" => undo_move(ctx),
+        "new" => new_game(ctx),
+        "hint" => handle_hint(ctx),
+        "analyze" => handle_analyze(ctx),
+        "explorer" => toggle_explorer(ctx),
+        "theme" => handle_theme(ctx, &args[1..]),
+        "export" => {
+            ctx.message = match export_diagram(&ctx.board, ctx.reversed(), &args[1..]) {
+                Ok(message) => Colour::Green.paint(message),
+                Err(message) => Colour::Red.paint(message),
+            }
+        }
+        _ => match command.parse::<usize>() {
+            Ok(number) if ctx.explorer.is_some() => handle_explorer_move(ctx, number),
+            _ => handle_move(ctx, args),
+        },
     }
 
     true
 }
 
-fn handle_move(ctx: &mut Context, args: Vec<&str>) {
-    if args.len() == 1 {
-        let pos_str = args.get(0);
+/// The game so far with the players' names when known
+fn current_game(ctx: &Context) -> pgn::Game {
+    let mut game = pgn::Game::from_board(&ctx.board);
+
+    if let Some([white, black]) = &ctx.names {
+        game.set_tag("White", white);
+        game.set_tag("Black", black);
+    }
+
+    let result = match game_winner(ctx) {
+        Some(1) => "1-0",
+        Some(2) => "0-1",
+        Some(_) => "1/2-1/2",
+        None => "*",
+    };
+
+    game.result = result.to_string();
+    game.set_tag("Result", result);
+    game
+}
+
+/// The game with the side, opponent and clocks needed to continue it
+fn saved_game(ctx: &Context) -> SavedGame {
+    SavedGame {
+        game: current_game(ctx),
+        player: ctx.player,
+        opponent: ctx.opponent,
+        control: ctx.clock.initial(),
+        times: [ctx.clock.shown(1), ctx.clock.shown(2)],
+    }
+}
+
+/// Writes the game to the autosave file when moves were played or taken
+/// back since the last time
+fn autosave(ctx: &mut Context) {
+    let moves = ctx.board.game_moves();
+
+    if moves == ctx.autosaved {
+        return;
+    }
+
+    if let Err(err) = savegame::write(&ctx.autosave, &saved_game(ctx)) {
+        ctx.message = Colour::Red.paint(format!(
+            "Could not save '{}': {}",
+            ctx.autosave.display(),
+            err
+        ));
+    }
+
+    ctx.autosaved = moves;
+}
+
+fn save_game(ctx: &mut Context, args: &[&str]) {
+    let path = match args.first() {
+        Some(path) => path,
+        None => {
+            ctx.message = Colour::Red.paint("Usage: save <file>");
+            return;
+        }
+    };
+
+    ctx.message = match savegame::write(Path::new(path), &saved_game(ctx)) {
+        Ok(()) => Colour::Green.paint(format!("Saved the game to '{}'", path)),
+        Err(err) => Colour::Red.paint(format!("Could not write '{}': {}", path, err)),
+    };
+}
+
+/// Continues from the end of a game in a PGN file, the first one unless
+/// a number is given
+fn load_game(ctx: &mut Context, args: &[&str]) {
+    if ctx.interface.is_remote() {
+        ctx.message = Colour::Red.paint("Cannot load a game over the network");
+        return;
+    }
+
+    let path = match args.first() {
+        Some(path) => path,
+        None => {
+            ctx.message = Colour::Red.paint("Usage: load <file> [number]");
+            return;
+        }
+    };
+
+    let games = match pgn::read_file(path) {
+        Ok(games) => games,
+        Err(err) => {
+            ctx.message = Colour::Red.paint(format!("Could not read '{}': {}", path, err));
+            return;
+        }
+    };
+
+    let number = match args.get(1).map(|n| n.parse::<usize>()) {
+        Some(Ok(number)) => number,
+        Some(Err(_)) => {
+            ctx.message = Colour::Red.paint(format!("Invalid game number '{}'", args[1]));
+            return;
+        }
+        None => 1,
+    };
+
+    let game = match number.checked_sub(1).and_then(|idx| games.get(idx)) {
+        Some(game) => game,
+        None => {
+            ctx.message = Colour::Red.paint(format!(
+                "No game {} in '{}', which has {}",
+                number,
+                path,
+                games.len()
+            ));
+            return;
+        }
+    };
+
+    let mut board = match game.start_board() {
+        Some(board) => board,
+        None => {
+            ctx.message = Colour::Red.paint(format!("Invalid FEN tag in game {}", number));
+            return;
+        }
+    };
+
+    for mv in game.moves.iter() {
+        board.make_move(mv);
+    }
+
+    start_game(ctx, board);
+    ctx.message = Colour::Green.paint(format!(
+        "Loaded game {} from '{}' after {} moves",
+        number,
+        path,
+        game.moves.len().div_ceil(2)
+    ));
+}
+
+fn list_moves(ctx: &mut Context) {
+    let mut moves: Vec<String> = ctx
+        .board
+        .get_legal_moves()
+        .iter()
+        .map(|mv| ctx.board.to_san(mv))
+        .collect();
+
+    moves.sort();
+
+    ctx.message = if moves.is_empty() {
+        Colour::Red.paint("No legal moves")
+    } else {
+        Colour::Green.paint(format!("{} legal moves: {}", moves.len(), moves.join(" ")))
+    };
+}
+
+fn show_history(ctx: &mut Context) {
+    let moves = ctx.board.game_moves();
+
+    ctx.message = if moves.is_empty() {
+        Colour::Green.paint("No moves played yet")
+    } else {
+        Colour::Green.paint(ctx.board.start_board().line_to_san(&moves))
+    };
+}
+
+/// Takes back the last move, and against the computer its reply as well
+/// so it is the player's turn again
+fn undo_move(ctx: &mut Context) {
+    if ctx.interface.is_remote() {
+        ctx.message = Colour::Red.paint("Cannot take back moves over the network");
+        return;
+    }
+
+    let mut plies = 0;
+
+    while ctx.board.undo() {
+        plies += 1;
+
+        if ctx.interface.is_local() || ctx.board.turn == ctx.player {
+            break;
+        }
+    }
+
+    ctx.message = match plies {
+        0 => Colour::Red.paint("No moves to take back"),
+        1 => Colour::Green.paint("Took back 1 move"),
+        _ => Colour::Green.paint(format!("Took back {} moves", plies)),
+    };
+}
+
+/// Starts again from the position the game began in
+fn new_game(ctx: &mut Context) {
+    if ctx.interface.is_remote() {
+        ctx.message = Colour::Red.paint("Cannot start a new game over the network");
+        return;
+    }
+
+    let board = ctx.board.start_board();
+    start_game(ctx, board);
+    ctx.message = Colour::Green.paint("New game");
+}
+
+fn start_game(ctx: &mut Context, board: Board) {
+    ctx.interface.new_game(&board);
+    ctx.clock.reset(board.turn);
+    ctx.board = board;
+}
+
+/// Searches briefly and selects the suggested move so it is highlighted
+fn handle_hint(ctx: &mut Context) {
+    println!("Thinking...");
+
+    let limits = Limits::time(HINT_TIME);
+    let result = ctx.engine.search(&ctx.board, &limits, &mut |_| ());
+
+    let mv = match result.best_move {
+        Some(mv) => mv,
+        None => {
+            ctx.message = Colour::Red.paint("No move to suggest");
+            return;
+        }
+    };
+
+    ctx.message = Colour::Green.paint(format!(
+        "Hint: {} ({})",
+        ctx.board.to_san(&mv),
+        white_score(&ctx.board, result.score)
+    ));
+
+    ctx.board.select(None);
+    ctx.board.cur_pos = Some(mv.from);
+    ctx.board.cur_moves = vec![mv];
+}
+
+/// Prints the evaluation and principal variation of every completed depth
+/// until a key is pressed
+fn handle_analyze(ctx: &mut Context) {
+    println!("Analyzing, press any key to stop");
+
+    // Single key presses need raw mode, where lines need a carriage return.
+    // Without a terminal, as with piped input, enter stops it instead.
+    let raw = terminal::enable_raw_mode().is_ok();
+    let newline = if raw { "\r\n" } else { "\n" };
+
+    let board = ctx.board.clone_with_history();
+    let stop = ctx.engine.stop_handle();
+    let engine = &mut ctx.engine;
+
+    thread::scope(|scope| {
+        let search = scope.spawn(move || {
+            let mut report = |result: &SearchResult| {
+                print!(
+                    "depth {:>2}  {:>7}  nodes {:>9}  {}{}",
+                    result.depth,
+                    white_score(&board, result.score),
+                    result.nodes,
+                    board.line_to_san(&result.pv),
+                    newline
+                );
+                let _ = stdout().flush();
+            };
+
+            engine.search(&board, &Limits::default(), &mut report);
+        });
+
+        if raw {
+            wait_for_key();
+        } else {
+            read_line();
+        }
+
+        stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        let _ = search.join();
+    });
 
-        if ctx.board.cur_pos.is_none() {
-            if !ctx.board.select(pos_str) {
-                ctx.message =
-                    Colour::Red.paint(format!("Invalid position '{}'", &pos_str.unwrap()));
+    if raw {
+        let _ = terminal::disable_raw_mode();
+    }
+
+    stop.store(false, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// Blocks until a key is pressed, in raw mode
+fn wait_for_key() {
+    loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => return,
+            Ok(_) => continue,
+            Err(_) => return,
+        }
+    }
+}
+
+/// Shows or hides the moves played from the position in the game database
+fn toggle_explorer(ctx: &mut Context) {
+    if ctx.explorer.take().is_some() {
+        return;
+    }
+
+    match db::GameDb::open(&ctx.db_path) {
+        Ok(database) => {
+            if database.games.is_empty() {
+                ctx.message = Colour::Red.paint(format!(
+                    "No games in '{}', add some with db import",
+                    ctx.db_path.display()
+                ));
             }
+
+            ctx.explorer = Some(database);
+        }
+        Err(err) => {
+            ctx.message = Colour::Red.paint(format!(
+                "Could not open the database '{}': {}",
+                ctx.db_path.display(),
+                err
+            ));
+        }
+    }
+}
+
+/// Lists the themes and piece sets, or switches to the named ones and
+/// saves them as the default. Colours set in the config stay.
+fn handle_theme(ctx: &mut Context, names: &[&str]) {
+    let mut theme = theme::current();
+
+    if names.is_empty() {
+        ctx.message = Colour::Green.paint(format!(
+            "Theme {} with {} pieces, available: {} and pieces {}",
+            theme.name,
+            theme.glyphs.name(),
+            theme::PRESETS.join(", "),
+            theme::GLYPH_SETS.join(", ")
+        ));
+        return;
+    }
+
+    for name in names.iter() {
+        if let Some(glyphs) = theme::Glyphs::from(name) {
+            theme.glyphs = glyphs;
+            ctx.config.set("pieces", glyphs.name());
+        } else if let Some(preset) = Theme::preset(name) {
+            theme = Theme {
+                glyphs: theme.glyphs,
+                ..preset
+            };
+            ctx.config.set("theme", preset.name);
         } else {
-            let to_opt = Position::from(args[0].as_bytes());
+            ctx.message = Colour::Red.paint(format!("Unknown theme or piece set '{}'", name));
+            return;
+        }
+    }
 
-            if to_opt.is_none() {
-                ctx.message = Colour::Red.paint("Invalid move");
-            } else {
-                let from = ctx.board.cur_pos.unwrap().clone();
-                let mv_opt = Move::new(from, to_opt.unwrap());
-                do_move(ctx, mv_opt);
-                ctx.board.select(None);
+    theme::set(theme);
+
+    ctx.message = match ctx.config.save() {
+        Ok(()) => Colour::Green.paint(format!(
+            "Theme {} with {} pieces",
+            theme.name,
+            theme.glyphs.name()
+        )),
+        Err(err) => Colour::Red.paint(format!(
+            "Could not save '{}': {}",
+            ctx.config.path().display(),
+            err
+        )),
+    };
+}
+
+/// Plays the move with this number in the explorer table
+fn handle_explorer_move(ctx: &mut Context, number: usize) {
+    let moves = match &ctx.explorer {
+        Some(database) => database.explore(&ctx.board),
+        None => return,
+    };
+
+    match number.checked_sub(1).and_then(|idx| moves.get(idx)) {
+        Some(stats) => {
+            ctx.board.select(None);
+            do_move(ctx, Some(stats.mv));
+        }
+        None => ctx.message = Colour::Red.paint(format!("No move number {}", number)),
+    }
+}
+
+/// Formats a side to move relative score from white's point of view
+fn white_score(board: &Board, score: i32) -> String {
+    format_score(if board.turn == 1 { score } else { -score })
+}
+
+/// Plays a move given as two squares, in SAN, or square by square by
+/// selecting a piece first
+fn handle_move(ctx: &mut Context, args: Vec<&str>) {
+    match args.len() {
+        0 => {
+            ctx.board.select(None);
+        }
+        1 if ctx.board.cur_pos.is_some() => {
+            let from = ctx.board.cur_pos.unwrap();
+
+            match Position::from(args[0].as_bytes()) {
+                Some(to) => do_move(ctx, Move::new(from, to)),
+                None => do_move(ctx, ctx.board.parse_san(args[0])),
             }
+
+            ctx.board.select(None);
+        }
+        1 => {
+            if !ctx.board.select(args.first()) {
+                match ctx.board.parse_san(args[0]) {
+                    Some(mv) => do_move(ctx, Some(mv)),
+                    None => {
+                        ctx.message = Colour::Red.paint(format!(
+                            "Invalid position or move '{}', type help for the commands",
+                            args[0]
+                        ))
+                    }
+                }
+            }
+        }
+        2 => {
+            let move_str = args.join(" ");
+            let mv_opt = Move::from(move_str.as_str());
+            do_move(ctx, mv_opt);
+        }
+        _ => {
+            ctx.message = Colour::Red.paint(format!(
+                "Unknown command '{}', type help for the commands",
+                args.join(" ")
+            ))
         }
-    } else if args.len() == 2 {
-        let move_str = args.join(" ");
-        let mv_opt = Move::from(move_str.as_str());
-        do_move(ctx, mv_opt);
-    } else {
-        ctx.board.select(None);
     }
 }
 
@@ -199,6 +2240,11 @@ fn do_move(ctx: &mut Context, mv_opt: Option<Move>) {
     }
 
     ctx.interface.send_move(&mv);
+
+    // Moves in SAN can name the promotion piece
+    if mv.promotion != Piece::None {
+        ctx.interface.send_promote(mv.promotion);
+    }
 }
 
 fn handle_promote(ctx: &mut Context) {
@@ -207,49 +2253,70 @@ fn handle_promote(ctx: &mut Context) {
     }
 
     ctx.message = ANSIString::from("");
-    render::draw_board(&ctx.board, ctx.player == 2);
+    render::draw_board(&ctx.board, ctx.reversed());
     println!();
 
     loop {
         let color = Colour::Green;
 
+        let king = if ctx.board.variant == Variant::Antichess {
+            format!("/{}ing", render::paint(&color.paint("ki")))
+        } else {
+            String::new()
+        };
+
         print!(
-            "Promote ({}ueen/{}ook/{}night/{}ishop): ",
-            color.paint("q"),
-            color.paint("r"),
-            color.paint("k"),
-            color.paint("b"),
+            "Promote ({}ueen/{}ook/{}night/{}ishop{}): ",
+            render::paint(&color.paint("q")),
+            render::paint(&color.paint("r")),
+            render::paint(&color.paint("k")),
+            render::paint(&color.paint("b")),
+            king,
         );
 
         stdout().flush().unwrap();
 
         let line = read_line();
-
-        let piece = match line.trim() {
-            "b" | "bishop" => Piece::Bishop,
-            "k" | "knight" => Piece::Knight,
-            "q" | "queen" => Piece::Queen,
-            "r" | "rook" => Piece::Rook,
-            _ => Piece::None,
-        };
+        let piece = promotion_piece(line.trim());
 
         if piece != Piece::None {
-            if ctx.board.promote(piece) {
-                if let Some(true) = ctx.interface.send_promote(piece) {
-                    break;
-                } else {
-                    // TODO: ctx.board.undo();
-                    println!("Promote error");
-                }
-            } else {
-                println!("{}", Colour::Red.paint("Could not promote"));
+            match promote(ctx, piece) {
+                Ok(()) => break,
+                Err(err) => println!("{}", render::paint(&Colour::Red.paint(err))),
             }
         } else {
-            println!("{}", Colour::Red.paint("Invalid piece type"));
+            println!(
+                "{}",
+                render::paint(&Colour::Red.paint("Invalid piece type"))
+            );
         }
     }
 }
 
+fn promotion_piece(text: &str) -> Piece {
+    match text {
+        "b" | "bishop" => Piece::Bishop,
+        "k" | "knight" => Piece::Knight,
+        "ki" | "king" => Piece::King,
+        "q" | "queen" => Piece::Queen,
+        "r" | "rook" => Piece::Rook,
+        _ => Piece::None,
+    }
+}
+
+/// Promotes the pawn waiting on the last rank and tells the opponent
+fn promote(ctx: &mut Context, piece: Piece) -> Result<(), &'static str> {
+    if !ctx.board.promote(piece) {
+        return Err("Could not promote");
+    }
+
+    match ctx.interface.send_promote(piece) {
+        Some(true) => Ok(()),
+        // TODO: ctx.board.undo();
+        _ => Err("Promote error"),
+    }
+}
+
 fn read_line() -> String {
     let mut input = String::new();
     input.clear();
@@ -1,17 +1,62 @@
-use crate::chess::{Board, Move, Piece};
-use std::io::{BufRead, BufReader, Write};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
-
-pub trait Interface {
+use crate::book::Book;
+use crate::chess::{Board, Move, Piece, Variant};
+use crate::engine::{Clock, Engine, Limits, MATE};
+use crate::render;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+/// Makes a `wait` running on another thread return soon
+pub type Interrupt = Box<dyn Fn() + Send>;
+
+/// The other side of a game, which has to be `Send` so the full-screen view
+/// can wait for it on another thread
+pub trait Interface: Send {
     fn is_local(&self) -> bool;
     fn send_command(&mut self, prefix: &str, data: String) -> Option<bool>;
     fn get_player(&mut self) -> Option<i32>;
+    fn get_variant(&mut self) -> Option<Variant>;
     fn wait(&mut self, board: &mut Board) -> bool;
 
+    /// Whether the other side is a person at another computer, so taking
+    /// back moves or starting again would need their agreement
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    /// Called before a new game starts from `board`
+    fn new_game(&mut self, _board: &Board) {}
+
+    /// What cuts a `wait` on another thread short, if it can be
+    fn interrupter(&self) -> Option<Interrupt> {
+        None
+    }
+
+    /// Time left on both clocks before the next call to `wait`
+    fn set_clock(&mut self, _clock: Clock) {}
+
+    /// Whether the other side has given up the game
+    fn has_resigned(&self) -> bool {
+        false
+    }
+
+    /// Evaluation of the last move played by `wait`, in centipawns for the
+    /// side that played it, if known
+    fn last_score(&self) -> Option<i32> {
+        None
+    }
+
     fn send_player(&mut self, player: i32) {
         self.send_command("player", player.to_string());
     }
 
+    fn send_variant(&mut self, variant: Variant) {
+        self.send_command("variant", variant.to_string());
+    }
+
     fn send_surrender(&mut self) -> Option<bool> {
         self.send_command("surrender", String::from(""))
     }
@@ -41,13 +86,400 @@ impl Interface for DummyInterface {
         None
     }
 
+    fn get_variant(&mut self) -> Option<Variant> {
+        None
+    }
+
     fn wait(&mut self, _: &mut Board) -> bool {
         true
     }
 }
 
+pub struct ComputerInterface {
+    engine: Engine,
+    limits: Limits,
+    book: Option<Book>,
+    clock: Option<Clock>,
+    last_score: Option<i32>,
+}
+
+impl ComputerInterface {
+    pub fn new(engine: Engine, limits: Limits, book: Option<Book>) -> ComputerInterface {
+        ComputerInterface {
+            engine,
+            limits,
+            book,
+            clock: None,
+            last_score: None,
+        }
+    }
+}
+
+impl Interface for ComputerInterface {
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    fn send_command(&mut self, _: &str, _: String) -> Option<bool> {
+        Some(true)
+    }
+
+    fn get_player(&mut self) -> Option<i32> {
+        None
+    }
+
+    fn get_variant(&mut self) -> Option<Variant> {
+        None
+    }
+
+    fn wait(&mut self, board: &mut Board) -> bool {
+        self.last_score = None;
+
+        if let Some(mv) = self.book.as_ref().and_then(|book| book.choose(board)) {
+            return board.do_move(&mv);
+        }
+
+        let limits = match self.clock {
+            Some(clock) => clock.limits(board.turn),
+            None => self.limits,
+        };
+
+        let result = self.engine.search(board, &limits, &mut |_| ());
+
+        match result.best_move {
+            Some(mv) => {
+                self.last_score = Some(result.score);
+                board.do_move(&mv)
+            }
+            None => false,
+        }
+    }
+
+    fn new_game(&mut self, _board: &Board) {
+        self.engine.clear();
+        self.engine.stop_handle().store(false, Ordering::Relaxed);
+    }
+
+    /// Stops the search, which then plays the best move found so far
+    fn interrupter(&self) -> Option<Interrupt> {
+        let stop = self.engine.stop_handle();
+        Some(Box::new(move || stop.store(true, Ordering::Relaxed)))
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+
+    fn last_score(&self) -> Option<i32> {
+        self.last_score
+    }
+}
+
+/// A person entering moves at this terminal, used where the game loop
+/// is not driven by the interactive prompt
+pub struct ConsoleInterface {
+    pub name: String,
+    clock: Option<Clock>,
+}
+
+impl ConsoleInterface {
+    pub fn new(name: &str) -> ConsoleInterface {
+        ConsoleInterface {
+            name: name.to_string(),
+            clock: None,
+        }
+    }
+}
+
+impl Interface for ConsoleInterface {
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    fn send_command(&mut self, _: &str, _: String) -> Option<bool> {
+        Some(true)
+    }
+
+    fn get_player(&mut self) -> Option<i32> {
+        None
+    }
+
+    fn get_variant(&mut self) -> Option<Variant> {
+        None
+    }
+
+    /// Reads moves in SAN or coordinates until a legal one is entered,
+    /// giving up on `resign` or end of input
+    fn wait(&mut self, board: &mut Board) -> bool {
+        render::draw_board(board, board.turn == 2);
+        println!();
+
+        let color = if board.turn == 1 { "White" } else { "Black" };
+
+        loop {
+            match self.clock {
+                Some(clock) => print!(
+                    "{} ({}, {:.1}s left) move: ",
+                    self.name,
+                    color,
+                    clock.time(board.turn).as_secs_f64()
+                ),
+                None => print!("{} ({}) move: ", self.name, color),
+            }
+
+            io::stdout().flush().unwrap();
+
+            let mut line = String::new();
+
+            if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+                return false;
+            }
+
+            let input = line.trim();
+
+            if input == "resign" {
+                return false;
+            }
+
+            let mv = match board.parse_san(input) {
+                Some(mv) => Some(mv),
+                None => Move::from(input).filter(|mv| board.can_move(mv)),
+            };
+
+            match mv {
+                Some(mv) if board.do_move(&mv) => {
+                    // Coordinates without a piece promote to a queen
+                    if board.get_promoting().is_some() {
+                        board.promote(Piece::Queen);
+                    }
+
+                    return true;
+                }
+                _ => println!("Illegal move '{}'", input),
+            }
+        }
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+}
+
+/// An external engine speaking UCI over its standard input and output
+pub struct UciInterface {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    pub name: String,
+    limits: Limits,
+    clock: Option<Clock>,
+    last_score: Option<i32>,
+}
+
+impl UciInterface {
+    /// Starts a command, split on whitespace, and waits for `uciok`
+    pub fn spawn(command: &str, limits: Limits) -> io::Result<UciInterface> {
+        let mut parts = command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let input = child.stdin.take().unwrap();
+        let output = BufReader::new(child.stdout.take().unwrap());
+
+        let mut uci = UciInterface {
+            child,
+            input,
+            output,
+            name: program.to_string(),
+            limits,
+            clock: None,
+            last_score: None,
+        };
+
+        uci.send("uci")?;
+
+        loop {
+            let line = uci.read_line()?;
+
+            if let Some(name) = line.strip_prefix("id name ") {
+                uci.name = name.trim().to_string();
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+
+        Ok(uci)
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.send(&format!("setoption name {} value {}", name, value))
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.input, "{}", line)?;
+        self.input.flush()
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+
+        if self.output.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "engine exited",
+            ));
+        }
+
+        Ok(line)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        while self.read_line()?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    fn go_command(&self) -> String {
+        let mut command = String::from("go");
+
+        if let Some(clock) = self.clock {
+            command += &format!(
+                " wtime {} btime {} winc {} binc {}",
+                clock.white.as_millis(),
+                clock.black.as_millis(),
+                clock.increment.as_millis(),
+                clock.increment.as_millis()
+            );
+        } else {
+            if let Some(depth) = self.limits.depth {
+                command += &format!(" depth {}", depth);
+            }
+            if let Some(nodes) = self.limits.nodes {
+                command += &format!(" nodes {}", nodes);
+            }
+            if let Some(time) = self.limits.time {
+                command += &format!(" movetime {}", time.as_millis());
+            }
+        }
+
+        command
+    }
+
+    /// Searches the board's position, returning the best move in UCI notation
+    fn best_move(&mut self, board: &Board) -> io::Result<String> {
+        let moves: Vec<String> = board.game_moves().iter().map(|mv| mv.to_uci()).collect();
+        let mut position = format!("position fen {}", board.start_board().to_fen());
+
+        if !moves.is_empty() {
+            position += &format!(" moves {}", moves.join(" "));
+        }
+
+        self.send(&position)?;
+        self.send(&self.go_command())?;
+
+        loop {
+            let line = self.read_line()?;
+            let args: Vec<&str> = line.split_whitespace().collect();
+
+            match args.first() {
+                Some(&"info") => {
+                    if let Some(idx) = args.iter().position(|arg| *arg == "score") {
+                        let value = args.get(idx + 2).and_then(|v| v.parse::<i32>().ok());
+
+                        self.last_score = match (args.get(idx + 1), value) {
+                            (Some(&"cp"), Some(cp)) => Some(cp),
+                            (Some(&"mate"), Some(moves)) if moves > 0 => Some(MATE - moves * 2 + 1),
+                            (Some(&"mate"), Some(moves)) => Some(-MATE - moves * 2),
+                            _ => self.last_score,
+                        };
+                    }
+                }
+                Some(&"bestmove") => return Ok(args.get(1).unwrap_or(&"0000").to_string()),
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Interface for UciInterface {
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    fn send_command(&mut self, _: &str, _: String) -> Option<bool> {
+        Some(true)
+    }
+
+    fn get_player(&mut self) -> Option<i32> {
+        None
+    }
+
+    fn get_variant(&mut self) -> Option<Variant> {
+        None
+    }
+
+    fn wait(&mut self, board: &mut Board) -> bool {
+        self.last_score = None;
+
+        let mv = match self.best_move(board) {
+            Ok(mv) => mv,
+            Err(_) => return false,
+        };
+
+        match Move::from_uci(&mv) {
+            Some(mv) => board.do_move(&mv),
+            None => false,
+        }
+    }
+
+    fn new_game(&mut self, board: &Board) {
+        if board.variant != Variant::Standard {
+            let variant = board.variant.to_string();
+            self.set_option("UCI_Variant", &variant).ok();
+        }
+
+        self.send("ucinewgame").ok();
+        self.sync().ok();
+    }
+
+    fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+
+    fn last_score(&self) -> Option<i32> {
+        self.last_score
+    }
+}
+
+impl Drop for UciInterface {
+    fn drop(&mut self) {
+        self.send("quit").ok();
+
+        // Give the engine a moment to exit on its own
+        for _ in 0..100 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
 pub struct TcpInterface {
     stream: TcpStream,
+    resigned: bool,
 }
 
 impl Interface for TcpInterface {
@@ -55,6 +487,10 @@ impl Interface for TcpInterface {
         false
     }
 
+    fn is_remote(&self) -> bool {
+        true
+    }
+
     fn send_command(&mut self, prefix: &str, data: String) -> Option<bool> {
         let cmd = format!("{} {}\n", prefix, data);
         let bytes = cmd.as_bytes();
@@ -103,6 +539,37 @@ impl Interface for TcpInterface {
         player
     }
 
+    fn get_variant(&mut self) -> Option<Variant> {
+        let variant = match self.read_line() {
+            None => None,
+            Some(line) => {
+                let args: Vec<&str> = line.split_whitespace().collect();
+
+                if args.first() == Some(&"variant") {
+                    Variant::from(args.get(1)?)
+                } else {
+                    None
+                }
+            }
+        };
+
+        self.send_response(variant.is_some());
+        variant
+    }
+
+    fn has_resigned(&self) -> bool {
+        self.resigned
+    }
+
+    /// Ends reading, so waiting returns as if the other side had left
+    fn interrupter(&self) -> Option<Interrupt> {
+        let stream = self.stream.try_clone().ok()?;
+
+        Some(Box::new(move || {
+            let _ = stream.shutdown(Shutdown::Read);
+        }))
+    }
+
     fn wait(&mut self, board: &mut Board) -> bool {
         //let start_turn = board.turn;
 
@@ -131,19 +598,53 @@ impl Interface for TcpInterface {
 }
 
 impl TcpInterface {
-    pub fn client(host: &str) -> TcpInterface {
-        TcpInterface {
-            stream: TcpStream::connect(host).unwrap(),
-        }
+    /// Connects to a game hosted at `<host>:<port>`
+    pub fn client(host: &str) -> io::Result<TcpInterface> {
+        Ok(TcpInterface {
+            stream: TcpStream::connect(host)?,
+            resigned: false,
+        })
     }
 
-    pub fn server(port: u16) -> TcpInterface {
+    /// Waits for a player to join on the port
+    pub fn server(port: u16) -> io::Result<TcpInterface> {
         let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port);
-        let listener = TcpListener::bind(addr).unwrap();
-        let (socket, addr) = listener.accept().unwrap();
-        println!("new client: {:?}", addr);
+        let listener = TcpListener::bind(addr)?;
+        let (socket, addr) = listener.accept()?;
+        println!("{} joined", addr);
+
+        Ok(TcpInterface {
+            stream: socket,
+            resigned: false,
+        })
+    }
+
+    /// Tells the other side our name and rating, sent as `rating <value> <name>`
+    pub fn send_rating(&mut self, name: &str, rating: i32) -> Option<bool> {
+        self.send_command("rating", format!("{} {}", rating, name))
+    }
+
+    /// The other side's name and rating
+    pub fn get_rating(&mut self) -> Option<(String, i32)> {
+        let rating = match self.read_line() {
+            None => None,
+            Some(line) => {
+                let args: Vec<&str> = line.split_whitespace().collect();
 
-        TcpInterface { stream: socket }
+                match (
+                    args.first(),
+                    args.get(1).and_then(|r| r.parse::<i32>().ok()),
+                ) {
+                    (Some(&"rating"), Some(rating)) if args.len() > 2 => {
+                        Some((args[2..].join(" "), rating))
+                    }
+                    _ => None,
+                }
+            }
+        };
+
+        self.send_response(rating.is_some());
+        rating
     }
 
     fn send_response(&mut self, result: bool) {
@@ -164,7 +665,7 @@ impl TcpInterface {
         }
     }
 
-    fn handle_command(&self, command: &str, board: &mut Board) -> bool {
+    fn handle_command(&mut self, command: &str, board: &mut Board) -> bool {
         let args: Vec<&str> = command.split_whitespace().collect();
 
         let data = if args.len() > 1 {
@@ -186,6 +687,10 @@ impl TcpInterface {
                     let piece = Piece::from(data.as_str());
                     return board.promote(piece);
                 }
+                &"surrender" => {
+                    self.resigned = true;
+                    return true;
+                }
                 _ => (),
             }
         }
@@ -0,0 +1,342 @@
+use crate::chess::{Board, Move, Variant};
+use crate::eco::{self, Opening};
+use std::fs;
+use std::io;
+
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// Notes attached to a move: a numeric annotation glyph, a comment and an
+/// alternative line starting from the position before the move
+#[derive(Clone, Debug, Default)]
+pub struct Annotation {
+    pub nag: Option<u8>,
+    pub comment: Option<String>,
+    pub variation: Vec<Move>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<Move>,
+    /// Empty, or one entry per move
+    pub annotations: Vec<Annotation>,
+    pub result: String,
+}
+
+impl Game {
+    pub fn new() -> Game {
+        Game {
+            tags: Vec::new(),
+            moves: Vec::new(),
+            annotations: Vec::new(),
+            result: String::from("*"),
+        }
+    }
+
+    /// The game played on a board, with its result if it has ended
+    pub fn from_board(board: &Board) -> Game {
+        let mut game = Game::new();
+        let start = board.start_board();
+
+        if board.variant != Variant::Standard {
+            game.set_tag("Variant", board.variant.pgn_name());
+        }
+
+        let fen = start.to_fen();
+
+        if fen != Board::new_variant(board.variant).to_fen() {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &fen);
+        }
+
+        game.moves = board.game_moves();
+
+        if let Some(opening) = eco::classify_line(&start, &game.moves) {
+            game.set_opening(opening);
+        }
+
+        if let Some(outcome) = board.get_outcome() {
+            game.result = outcome.result().to_string();
+        }
+
+        game.set_tag("Result", &game.result.clone());
+        game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Fills the ECO, Opening and Variation tags
+    pub fn set_opening(&mut self, opening: &Opening) {
+        self.set_tag("ECO", &opening.eco);
+        self.set_tag("Opening", &opening.name);
+
+        if !opening.variation.is_empty() {
+            self.set_tag("Variation", &opening.variation);
+        }
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.tag("Variant")
+            .and_then(|name| Variant::from(&name.to_lowercase().replace(' ', "")))
+            .unwrap_or(Variant::Standard)
+    }
+
+    /// Board before the first move, honouring the FEN and Variant tags
+    pub fn start_board(&self) -> Option<Board> {
+        match self.tag("FEN") {
+            Some(fen) => Board::from_fen(fen, self.variant()),
+            None => Some(Board::new_variant(self.variant())),
+        }
+    }
+
+    /// Winner from the result tag, 0 for a draw and None if unfinished
+    pub fn winner(&self) -> Option<i32> {
+        match self.result.as_str() {
+            "1-0" => Some(1),
+            "0-1" => Some(2),
+            "1/2-1/2" => Some(0),
+            _ => None,
+        }
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut text = String::new();
+
+        for name in SEVEN_TAG_ROSTER.iter() {
+            let value = match *name {
+                "Result" => self.result.as_str(),
+                "Date" => self.tag(name).unwrap_or("????.??.??"),
+                _ => self.tag(name).unwrap_or("?"),
+            };
+
+            text.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+        }
+
+        for (name, value) in self.tags.iter() {
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+                text.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+            }
+        }
+
+        text.push('\n');
+
+        let mut tokens = Vec::new();
+
+        if let Some(mut board) = self.start_board() {
+            let mut numbered = false;
+
+            for (idx, mv) in self.moves.iter().enumerate() {
+                if board.turn == 1 {
+                    tokens.push(format!("{}.", board.fullmove_number));
+                } else if !numbered {
+                    tokens.push(format!("{}...", board.fullmove_number));
+                }
+
+                tokens.push(board.to_san(mv));
+                numbered = true;
+
+                if let Some(annotation) = self.annotations.get(idx) {
+                    if let Some(nag) = annotation.nag {
+                        tokens.push(format!("${}", nag));
+                    }
+
+                    if let Some(comment) = &annotation.comment {
+                        tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+                        numbered = false;
+                    }
+
+                    if !annotation.variation.is_empty() {
+                        tokens.push(format!("({})", board.line_to_san(&annotation.variation)));
+                        numbered = false;
+                    }
+                }
+
+                board.make_move(mv);
+            }
+        }
+
+        tokens.push(self.result.clone());
+        text.push_str(&wrap(&tokens, 80));
+        text.push('\n');
+        text
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Joins tokens with spaces, breaking lines before they exceed the width
+pub fn wrap(tokens: &[String], width: usize) -> String {
+    let mut text = String::new();
+    let mut line_len = 0;
+
+    for token in tokens {
+        if line_len > 0 && line_len + token.len() + 1 > width {
+            text.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            text.push(' ');
+            line_len += 1;
+        }
+
+        text.push_str(token);
+        line_len += token.len();
+    }
+
+    text
+}
+
+/// Parses every game in a PGN text, skipping comments, variations and NAGs.
+/// A game with an illegal move keeps the moves before it.
+pub fn parse(text: &str) -> Vec<Game> {
+    let mut games = Vec::new();
+    let mut game = Game::new();
+    let mut board = game.start_board();
+    let mut in_moves = false;
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+
+        match c {
+            '[' => {
+                if in_moves {
+                    games.push(game);
+                    game = Game::new();
+                    in_moves = false;
+                }
+
+                let end = find(&chars, idx, ']');
+                let tag: String = chars[idx + 1..end].iter().collect();
+
+                if let Some((name, value)) = parse_tag(&tag) {
+                    if name == "Result" {
+                        game.result = value.clone();
+                    }
+
+                    game.set_tag(&name, &value);
+                }
+
+                board = game.start_board();
+                idx = end + 1;
+            }
+            '{' => idx = find(&chars, idx, '}') + 1,
+            ';' => idx = find(&chars, idx, '\n') + 1,
+            '(' => {
+                let mut depth = 0;
+
+                while idx < chars.len() {
+                    match chars[idx] {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        '{' => idx = find(&chars, idx, '}'),
+                        _ => (),
+                    }
+
+                    idx += 1;
+
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            _ if c.is_whitespace() => idx += 1,
+            _ => {
+                let start = idx;
+
+                while idx < chars.len()
+                    && !chars[idx].is_whitespace()
+                    && !"[{;(".contains(chars[idx])
+                {
+                    idx += 1;
+                }
+
+                let token: String = chars[start..idx].iter().collect();
+                in_moves = true;
+
+                match token.as_str() {
+                    "1-0" | "0-1" | "1/2-1/2" | "*" => {
+                        game.result = token;
+                        games.push(game);
+                        game = Game::new();
+                        board = game.start_board();
+                        in_moves = false;
+                        continue;
+                    }
+                    _ => (),
+                }
+
+                // Strip move numbers such as "12." or "12...", but not "0-0"
+                let san = match token.rfind('.') {
+                    Some(dot) if token.starts_with(|c: char| c.is_ascii_digit()) => {
+                        &token[dot + 1..]
+                    }
+                    _ => token.as_str(),
+                };
+
+                if san.is_empty() || san.starts_with('$') {
+                    continue;
+                }
+
+                let mv = board.as_ref().and_then(|board| board.parse_san(san));
+
+                match (mv, board.as_mut()) {
+                    (Some(mv), Some(board)) => {
+                        board.make_move(&mv);
+                        game.moves.push(mv);
+                    }
+                    _ => board = None,
+                }
+            }
+        }
+    }
+
+    if in_moves {
+        games.push(game);
+    }
+
+    games
+}
+
+pub fn read_file(path: &str) -> io::Result<Vec<Game>> {
+    Ok(parse(&fs::read_to_string(path)?))
+}
+
+fn find(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..]
+        .iter()
+        .position(|&other| other == c)
+        .map_or(chars.len(), |idx| start + idx)
+}
+
+fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let tag = tag.trim();
+    let space = tag.find(char::is_whitespace)?;
+    let name = tag[..space].to_string();
+    let value = tag[space..].trim();
+
+    if !value.starts_with('"') || !value.ends_with('"') || value.len() < 2 {
+        return None;
+    }
+
+    let value = value[1..value.len() - 1]
+        .replace("\\\"", "\"")
+        .replace("\\\\", "\\");
+
+    Some((name, value))
+}
@@ -0,0 +1,288 @@
+use crate::chess::Board;
+use crate::theme;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Commands understood at the prompt besides moves, with their help text
+pub const COMMANDS: [(&str, &str); 20] = [
+    ("help", "show this list"),
+    ("moves", "list the legal moves"),
+    (
+        "undo",
+        "take back the last move, or your last move against the computer",
+    ),
+    ("new", "start a new game"),
+    ("history", "show the moves played so far"),
+    ("flip", "turn the board around"),
+    ("fen", "show the position as FEN"),
+    ("pgn", "show the game as PGN"),
+    (
+        "save",
+        "<file> write the game to a file to continue with --resume",
+    ),
+    ("load", "<file> [number] continue a game from a PGN file"),
+    ("resign", "give up the game"),
+    ("hint", "suggest a move"),
+    ("analyze", "search the position until a key is pressed"),
+    ("explorer", "show or hide the opening explorer"),
+    (
+        "theme",
+        "[theme] [pieces] change the board colours or piece set",
+    ),
+    ("export", "svg|png|gif <file> write a diagram or animation"),
+    ("quit", "leave the game, which resigns it"),
+    ("q", "same as quit"),
+    ("exit", "same as quit"),
+    (
+        "<move>",
+        "such as e4, Nf3, e2 e4, or a square to select a piece",
+    ),
+];
+
+/// `~/.rust-chess/history`, or the working directory without a home
+pub fn default_path() -> PathBuf {
+    match env::var_os("HOME") {
+        Some(home) => Path::new(&home).join(".rust-chess").join("history"),
+        None => PathBuf::from("rust-chess.history"),
+    }
+}
+
+/// Completions for the position the prompt is shown in
+struct Completion {
+    files: FilenameCompleter,
+    /// Commands and legal moves in SAN
+    words: Vec<String>,
+    /// Squares of pieces that can move, each with its targets
+    squares: Vec<(String, Vec<String>)>,
+}
+
+impl Completion {
+    fn candidates(&self, previous: &[&str], word: &str) -> Vec<String> {
+        let mut words: Vec<String> = match previous {
+            [] => self
+                .words
+                .iter()
+                .cloned()
+                .chain(self.squares.iter().map(|(square, _)| square.clone()))
+                .collect(),
+            [first] => match self.squares.iter().find(|(square, _)| square == first) {
+                Some((_, targets)) => targets.clone(),
+                None if *first == "export" => vec!["svg", "png", "gif"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+                None if *first == "theme" => theme::PRESETS
+                    .iter()
+                    .chain(theme::GLYPH_SETS.iter())
+                    .map(|name| name.to_string())
+                    .collect(),
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        };
+
+        words.retain(|candidate| candidate.starts_with(word));
+        words.sort();
+        words.dedup();
+        words
+    }
+}
+
+impl Completer for Completion {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(' ').map_or(0, |idx| idx + 1);
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+
+        // File names for the commands that read or write files
+        if matches!(previous.as_slice(), ["save" | "load"] | ["export", _]) {
+            return self.files.complete(line, pos, ctx);
+        }
+
+        let pairs = self
+            .candidates(&previous, &line[start..pos])
+            .into_iter()
+            .map(|word| Pair {
+                display: word.clone(),
+                replacement: word,
+            })
+            .collect();
+
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+/// Line editing with tab completion of commands and legal moves, and a
+/// history kept between games
+pub struct Prompt {
+    editor: Option<Editor<Completion, DefaultHistory>>,
+    history: PathBuf,
+}
+
+impl Prompt {
+    pub fn new(history: PathBuf) -> Prompt {
+        let editor = Editor::new().ok().map(|mut editor| {
+            editor.set_helper(Some(Completion {
+                files: FilenameCompleter::new(),
+                words: Vec::new(),
+                squares: Vec::new(),
+            }));
+
+            // A missing history file is normal before the first game
+            let _ = editor.load_history(&history);
+            editor
+        });
+
+        Prompt { editor, history }
+    }
+
+    /// Reads a line completing moves legal on the board, or None at the end
+    /// of input or on interrupt
+    pub fn read(&mut self, prompt: &str, board: &Board) -> Option<String> {
+        let words = COMMANDS
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .filter(|name| !name.starts_with('<'))
+            .chain(legal_sans(board))
+            .collect();
+
+        self.read_completing(prompt, words, movable_squares(board))
+    }
+
+    /// Reads a line completing the given commands, for prompts outside games
+    pub fn read_commands(&mut self, prompt: &str, commands: &[&str]) -> Option<String> {
+        let words = commands.iter().map(|name| name.to_string()).collect();
+        self.read_completing(prompt, words, Vec::new())
+    }
+
+    fn read_completing(
+        &mut self,
+        prompt: &str,
+        words: Vec<String>,
+        squares: Vec<(String, Vec<String>)>,
+    ) -> Option<String> {
+        let editor = match self.editor.as_mut() {
+            Some(editor) => editor,
+            None => return read_plain(prompt),
+        };
+
+        if let Some(completion) = editor.helper_mut() {
+            completion.words = words;
+            completion.squares = squares;
+        }
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    let _ = editor.add_history_entry(line.trim());
+                    self.save_history();
+                }
+
+                Some(line)
+            }
+            Err(ReadlineError::Eof) | Err(ReadlineError::Interrupted) => None,
+            Err(err) => {
+                println!("Error reading line: {}", err);
+                None
+            }
+        }
+    }
+
+    fn save_history(&mut self) {
+        if let Some(editor) = self.editor.as_mut() {
+            if let Some(dir) = self.history.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+
+            let _ = editor.append_history(&self.history);
+        }
+    }
+}
+
+/// Standard input without editing, when the terminal cannot be set up
+fn read_plain(prompt: &str) -> Option<String> {
+    use std::io::{stdin, stdout, Write};
+
+    print!("{}", prompt);
+    stdout().flush().ok()?;
+
+    let mut line = String::new();
+
+    match stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line),
+    }
+}
+
+fn legal_sans(board: &Board) -> Vec<String> {
+    board
+        .get_legal_moves()
+        .iter()
+        .map(|mv| board.to_san(mv))
+        .collect()
+}
+
+fn movable_squares(board: &Board) -> Vec<(String, Vec<String>)> {
+    let mut squares: Vec<(String, Vec<String>)> = Vec::new();
+
+    for mv in board.get_legal_moves().iter().filter(|mv| !mv.is_drop()) {
+        let from = mv.from.to_string();
+        let to = mv.to.to_string();
+
+        match squares.iter_mut().find(|(square, _)| *square == from) {
+            Some((_, targets)) => {
+                if !targets.contains(&to) {
+                    targets.push(to);
+                }
+            }
+            None => squares.push((from, vec![to])),
+        }
+    }
+
+    squares
+}
+
+/// The commands on one line, for views without room for `help`
+pub fn command_names() -> String {
+    let names: Vec<&str> = COMMANDS
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| !name.starts_with('<'))
+        .collect();
+
+    format!("Commands: {}", names.join(", "))
+}
+
+/// The help text listing every command
+pub fn help() -> String {
+    let lines: Vec<String> = COMMANDS
+        .iter()
+        .map(|(name, text)| format!("  {:<10} {}", name, text))
+        .collect();
+
+    format!("Commands:\n{}", lines.join("\n"))
+}
@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::f64::consts::PI;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const INITIAL_RATING: f64 = 1500.0;
+const INITIAL_DEVIATION: f64 = 350.0;
+const INITIAL_VOLATILITY: f64 = 0.06;
+const ELO_K: f64 = 32.0;
+/// Constrains how fast the Glicko-2 volatility changes
+const GLICKO_TAU: f64 = 0.5;
+const GLICKO_SCALE: f64 = 173.7178;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum System {
+    Elo,
+    Glicko2,
+}
+
+impl System {
+    pub fn from(name: &str) -> Option<System> {
+        match name.to_lowercase().as_str() {
+            "elo" => Some(System::Elo),
+            "glicko" | "glicko2" | "glicko-2" => Some(System::Glicko2),
+            _ => None,
+        }
+    }
+}
+
+/// A player's Elo and Glicko-2 ratings, both kept up to date
+#[derive(Debug, Clone)]
+pub struct Rating {
+    pub elo: f64,
+    pub glicko: f64,
+    pub deviation: f64,
+    pub volatility: f64,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Rating {
+        Rating {
+            elo: INITIAL_RATING,
+            glicko: INITIAL_RATING,
+            deviation: INITIAL_DEVIATION,
+            volatility: INITIAL_VOLATILITY,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+}
+
+impl Rating {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn value(&self, system: System) -> i32 {
+        match system {
+            System::Elo => self.elo.round() as i32,
+            System::Glicko2 => self.glicko.round() as i32,
+        }
+    }
+
+    /// Ratings after scoring `points` against an opponent, using the
+    /// opponent's ratings from before the game
+    fn update(&self, opponent: &Rating, points: f64) -> Rating {
+        let mut rating = self.clone();
+
+        let expected = 1.0 / (1.0 + 10f64.powf((opponent.elo - self.elo) / 400.0));
+        rating.elo += ELO_K * (points - expected);
+
+        let (glicko, deviation, volatility) = glicko2(self, opponent, points);
+        rating.glicko = glicko;
+        rating.deviation = deviation;
+        rating.volatility = volatility;
+
+        match points {
+            p if p > 0.5 => rating.wins += 1,
+            p if p < 0.5 => rating.losses += 1,
+            _ => rating.draws += 1,
+        }
+
+        rating
+    }
+}
+
+/// One game as its own rating period, following Glickman's description of
+/// the Glicko-2 system
+fn glicko2(player: &Rating, opponent: &Rating, points: f64) -> (f64, f64, f64) {
+    let mu = (player.glicko - INITIAL_RATING) / GLICKO_SCALE;
+    let phi = player.deviation / GLICKO_SCALE;
+    let mu_j = (opponent.glicko - INITIAL_RATING) / GLICKO_SCALE;
+    let phi_j = opponent.deviation / GLICKO_SCALE;
+
+    let g = 1.0 / (1.0 + 3.0 * phi_j * phi_j / (PI * PI)).sqrt();
+    let expected = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+    let v = 1.0 / (g * g * expected * (1.0 - expected));
+    let delta = v * g * (points - expected);
+
+    // New volatility by the Illinois algorithm
+    let a = (player.volatility * player.volatility).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        let denominator = phi * phi + v + ex;
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * denominator * denominator)
+            - (x - a) / (GLICKO_TAU * GLICKO_TAU)
+    };
+
+    let mut low = a;
+    let mut high = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+
+        while f(a - k * GLICKO_TAU) < 0.0 {
+            k += 1.0;
+        }
+
+        a - k * GLICKO_TAU
+    };
+
+    let mut f_low = f(low);
+    let mut f_high = f(high);
+
+    while (high - low).abs() > 1e-6 {
+        let mid = low + (low - high) * f_low / (f_high - f_low);
+        let f_mid = f(mid);
+
+        if f_mid * f_high <= 0.0 {
+            low = high;
+            f_low = f_high;
+        } else {
+            f_low /= 2.0;
+        }
+
+        high = mid;
+        f_high = f_mid;
+    }
+
+    let volatility = (low / 2.0).exp();
+    let phi_star = (phi * phi + volatility * volatility).sqrt();
+    let new_phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+    let new_mu = mu + new_phi * new_phi * g * (points - expected);
+
+    (
+        new_mu * GLICKO_SCALE + INITIAL_RATING,
+        new_phi * GLICKO_SCALE,
+        volatility,
+    )
+}
+
+/// Ratings by player name, kept in a tab-separated text file
+pub struct RatingStore {
+    path: PathBuf,
+    players: BTreeMap<String, Rating>,
+}
+
+/// `~/.rust-chess/ratings.tsv`, or the working directory without a home
+pub fn default_path() -> PathBuf {
+    match env::var_os("HOME") {
+        Some(home) => Path::new(&home).join(".rust-chess").join("ratings.tsv"),
+        None => PathBuf::from("ratings.tsv"),
+    }
+}
+
+impl RatingStore {
+    /// Reads the store, starting empty when the file does not exist yet
+    pub fn open(path: &Path) -> io::Result<RatingStore> {
+        let mut store = RatingStore {
+            path: path.to_path_buf(),
+            players: BTreeMap::new(),
+        };
+
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(store),
+            Err(err) => return Err(err),
+        };
+
+        for line in text.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+
+            if fields.len() != 8 {
+                continue;
+            }
+
+            let number = |idx: usize| fields[idx].parse::<f64>().ok();
+            let count = |idx: usize| fields[idx].parse::<u32>().ok();
+
+            let rating = match (
+                number(1),
+                number(2),
+                number(3),
+                number(4),
+                count(5),
+                count(6),
+                count(7),
+            ) {
+                (
+                    Some(elo),
+                    Some(glicko),
+                    Some(deviation),
+                    Some(volatility),
+                    Some(wins),
+                    Some(losses),
+                    Some(draws),
+                ) => Rating {
+                    elo,
+                    glicko,
+                    deviation,
+                    volatility,
+                    wins,
+                    losses,
+                    draws,
+                },
+                _ => continue,
+            };
+
+            store.players.insert(unescape(fields[0]), rating);
+        }
+
+        Ok(store)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            if !dir.as_os_str().is_empty() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+
+        let mut file = fs::File::create(&self.path)?;
+        writeln!(
+            file,
+            "# name\telo\tglicko\tdeviation\tvolatility\twins\tlosses\tdraws"
+        )?;
+
+        for (name, r) in self.players.iter() {
+            writeln!(
+                file,
+                "{}\t{:.2}\t{:.2}\t{:.2}\t{:.6}\t{}\t{}\t{}",
+                escape(name),
+                r.elo,
+                r.glicko,
+                r.deviation,
+                r.volatility,
+                r.wins,
+                r.losses,
+                r.draws
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn default_rating(system: System) -> i32 {
+        Rating::default().value(system)
+    }
+
+    /// The player's rating, or the initial rating for a new player
+    pub fn get(&self, name: &str) -> Rating {
+        self.players.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Updates both players after a game, where `winner` is 1 for white,
+    /// 2 for black and 0 for a draw
+    pub fn record(&mut self, white: &str, black: &str, winner: i32) {
+        let white_points = match winner {
+            1 => 1.0,
+            2 => 0.0,
+            _ => 0.5,
+        };
+
+        let (white_rating, black_rating) = (self.get(white), self.get(black));
+
+        self.players.insert(
+            white.to_string(),
+            white_rating.update(&black_rating, white_points),
+        );
+        self.players.insert(
+            black.to_string(),
+            black_rating.update(&white_rating, 1.0 - white_points),
+        );
+    }
+
+    /// Players with ratings, best first
+    pub fn ranking(&self, system: System) -> Vec<(&String, &Rating)> {
+        let mut players: Vec<(&String, &Rating)> = self.players.iter().collect();
+        players.sort_by_key(|(_, rating)| -rating.value(system));
+        players
+    }
+}
+
+/// Names may hold anything, so tabs, line breaks and a leading `#`, which
+/// would break the file, are written as `\t`, `\n`, `\r` and `\#`, with
+/// backslashes doubled
+fn escape(name: &str) -> String {
+    let mut text = String::new();
+
+    for (idx, c) in name.chars().enumerate() {
+        match c {
+            '\\' => text.push_str("\\\\"),
+            '\t' => text.push_str("\\t"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '#' if idx == 0 => text.push_str("\\#"),
+            _ => text.push(c),
+        }
+    }
+
+    text
+}
+
+fn unescape(text: &str) -> String {
+    let mut name = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            name.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => name.push('\t'),
+            Some('n') => name.push('\n'),
+            Some('r') => name.push('\r'),
+            Some(other) => name.push(other),
+            None => name.push('\\'),
+        }
+    }
+
+    name
+}
+
+/// Records a finished game and saves the store, reporting problems
+/// without interrupting the caller
+pub fn record_game(path: &Path, white: &str, black: &str, winner: i32) {
+    let result = RatingStore::open(path).and_then(|mut store| {
+        store.record(white, black, winner);
+        store.save()?;
+
+        for name in [white, black].iter() {
+            let rating = store.get(name);
+            println!(
+                "{}: Elo {}, Glicko-2 {} (RD {:.0})",
+                name,
+                rating.value(System::Elo),
+                rating.value(System::Glicko2),
+                rating.deviation
+            );
+        }
+
+        Ok(())
+    });
+
+    if let Err(err) = result {
+        println!("Could not update ratings in '{}': {}", path.display(), err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn odd_names_survive_saving() {
+        let path = env::temp_dir().join(format!("rust-chess-ratings-{}.tsv", process::id()));
+        let names = ["#1", "tab\tname", "two\nlines", "back\\slash\\t", "plain"];
+
+        let mut store = RatingStore::open(&path).unwrap();
+
+        for pair in names.windows(2) {
+            store.record(pair[0], pair[1], 1);
+        }
+
+        store.save().unwrap();
+
+        let reopened = RatingStore::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.players.len(), names.len());
+
+        for name in names.iter() {
+            let (saved, read) = (store.get(name), reopened.get(name));
+            assert!(reopened.players.contains_key(*name), "{:?}", name);
+            assert_eq!(read.games(), saved.games(), "{:?}", name);
+            assert!((read.elo - saved.elo).abs() < 0.01, "{:?}", name);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+mod pieces;
+mod raster;
+mod svg;
+mod text;
+
+use crate::chess::{Board, Square, Variant};
+use crate::db::MoveStats;
+use crate::theme;
+use ansi_term::{ANSIString, Colour, Style};
+use std::env;
+use std::io::{stdout, IsTerminal};
+use std::sync::RwLock;
+
+pub use raster::{board_png, game_gif};
+pub use svg::{board_svg, Diagram};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Coloured squares and the glyphs of the current theme
+    Colour,
+    /// Letters and punctuation only, without escape codes
+    Ascii,
+    /// The position described in words, also without escape codes
+    Accessible,
+}
+
+static MODE: RwLock<Option<Mode>> = RwLock::new(None);
+
+impl Mode {
+    pub fn from(name: &str) -> Option<Mode> {
+        match name.to_lowercase().as_str() {
+            "colour" | "color" => Some(Mode::Colour),
+            "ascii" => Some(Mode::Ascii),
+            "accessible" | "words" => Some(Mode::Accessible),
+            _ => None,
+        }
+    }
+
+    /// Plain letters when `NO_COLOR` is set or the output is not a terminal,
+    /// as in CI logs and pipes
+    pub fn detect() -> Mode {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+
+        if no_color || !stdout().is_terminal() {
+            Mode::Ascii
+        } else {
+            Mode::Colour
+        }
+    }
+}
+
+/// The chosen mode, detected on first use
+pub fn mode() -> Mode {
+    if let Some(mode) = *MODE.read().unwrap() {
+        return mode;
+    }
+
+    *MODE.write().unwrap().get_or_insert_with(Mode::detect)
+}
+
+pub fn set_mode(mode: Mode) {
+    *MODE.write().unwrap() = Some(mode);
+}
+
+/// The text with its colours, or without them outside colour mode
+pub fn paint(text: &ANSIString) -> String {
+    if mode() == Mode::Colour {
+        text.to_string()
+    } else {
+        (**text).to_string()
+    }
+}
+
+pub fn draw_board(board: &Board, reverse: bool) {
+    match mode() {
+        Mode::Colour => draw_colour_board(board, reverse),
+        Mode::Ascii => text::draw_ascii_board(board, reverse),
+        Mode::Accessible => text::describe_board(board),
+    }
+}
+
+fn draw_colour_board(board: &Board, reverse: bool) {
+    let info_style = Colour::White;
+    let mut rows: Vec<String> = Vec::new();
+
+    for yi in 0..8 {
+        let y = if reverse { yi } else { 7 - yi };
+        let mut columns: Vec<ANSIString> = Vec::with_capacity(8);
+
+        for x in 0..8 {
+            columns.push(square_cell(board, x, y));
+        }
+
+        let cols_str: Vec<_> = columns.iter().map(ToString::to_string).collect();
+        let line = cols_str.join("");
+        let line_num = info_style.paint((y + 1).to_string());
+
+        rows.push(format!("{} {} {}", line_num, line, line_num));
+    }
+
+    if board.variant == Variant::Crazyhouse {
+        let (top, bottom) = if reverse { (1, 2) } else { (2, 1) };
+        rows[0] = format!("{}   {}", rows[0], pocket_string(board, top));
+        rows[7] = format!("{}   {}", rows[7], pocket_string(board, bottom));
+    }
+
+    println!("\n  {}", info_style.paint("a b c d e f g h"));
+    println!("{}", rows.join("\n"));
+    println!("  {}", info_style.paint("a b c d e f g h"));
+}
+
+/// Moves played from the position in the game database, numbered so they
+/// can be chosen by typing the number
+pub fn draw_explorer(board: &Board, moves: &[MoveStats]) {
+    let info_style = Colour::White;
+
+    println!();
+
+    if moves.is_empty() {
+        println!(
+            "{}",
+            paint(&info_style.paint("No games in the database reach this position"))
+        );
+        return;
+    }
+
+    println!(
+        "{}",
+        paint(&info_style.paint(format!(
+            "{:>3}  {:<8} {:>6}  {:>6} {:>6} {:>6}  {:>6}",
+            "#", "Move", "Games", "White", "Draw", "Black", "Rating"
+        )))
+    );
+
+    for (idx, stats) in moves.iter().enumerate() {
+        let (white, draw, black) = stats.percentages();
+
+        let rating = match stats.average_rating() {
+            Some(rating) => rating.to_string(),
+            None => String::from("-"),
+        };
+
+        println!(
+            "{:>3}  {:<8} {:>6}  {:>5.1}% {:>5.1}% {:>5.1}%  {:>6}",
+            idx + 1,
+            board.to_san(&stats.mv),
+            stats.games,
+            white,
+            draw,
+            black,
+            rating
+        );
+    }
+}
+
+/// A square as two coloured cells, highlighting the selected piece and its
+/// targets, or else the previous move
+pub fn square_cell<'a>(board: &Board, x: i32, y: i32) -> ANSIString<'a> {
+    let theme = theme::current();
+    let square = board.get(x, y);
+
+    if let Some(pos) = board.cur_pos {
+        let selected = x == pos.x && y == pos.y;
+        let target = board
+            .cur_moves
+            .iter()
+            .any(|mv| mv.to.x == x && mv.to.y == y);
+
+        if selected || target {
+            return square_string_style(square, &square_color(square).on(theme.selected));
+        }
+    } else if let Some(prev_move) = board.prev_move {
+        if (x == prev_move.from.x && y == prev_move.from.y)
+            || (x == prev_move.to.x && y == prev_move.to.y)
+        {
+            return square_string_style(square, &square_color(square).on(theme.last_move));
+        }
+    }
+
+    square_string_style(square, &square_color(square).on(square_backgroud(x, y)))
+}
+
+fn pocket_string(board: &Board, player: i32) -> String {
+    let pocket = board.get_pocket(player);
+    let light = theme::current().light;
+    let mut parts: Vec<String> = Vec::new();
+
+    for piece in pocket.pieces() {
+        let square = Square::from(piece, player);
+        let style = square_color(&square).on(light);
+        let count = Colour::White.paint(pocket.get(piece).to_string());
+        parts.push(format!("{}{}", square_string_style(&square, &style), count));
+    }
+
+    parts.join(" ")
+}
+
+fn square_string_style<'a>(square: &Square, style: &Style) -> ANSIString<'a> {
+    style.paint(format!("{} ", square_letter(square)))
+}
+
+pub fn square_letter(square: &Square) -> char {
+    theme::current().glyphs.glyph(square.piece, square.player)
+}
+
+fn square_color(square: &Square) -> Style {
+    let theme = theme::current();
+
+    if square.is_white() {
+        Style::new().fg(theme.white_pieces)
+    } else {
+        Style::new().fg(theme.black_pieces)
+    }
+}
+
+fn square_backgroud(x: i32, y: i32) -> Colour {
+    let theme = theme::current();
+    let odd = (y % 2) ^ (x % 2) == 0;
+
+    if odd {
+        theme.dark
+    } else {
+        theme.light
+    }
+}
@@ -0,0 +1,200 @@
+use crate::chess::Piece;
+
+/// Width and height of the box the piece shapes are drawn in
+pub const SIZE: f32 = 45.0;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Polygon(&'static [(f32, f32)]),
+    /// Centre and radius
+    Circle(f32, f32, f32),
+}
+
+/// One filled shape of a piece, in the piece colour or, for details such
+/// as eyes and slits, in the outline colour
+#[derive(Debug, Clone, Copy)]
+pub struct Part {
+    pub shape: Shape,
+    pub detail: bool,
+}
+
+const fn body(shape: Shape) -> Part {
+    Part {
+        shape,
+        detail: false,
+    }
+}
+
+const fn detail(shape: Shape) -> Part {
+    Part {
+        shape,
+        detail: true,
+    }
+}
+
+const PAWN: [Part; 2] = [
+    body(Shape::Polygon(&[
+        (18.0, 19.0),
+        (27.0, 19.0),
+        (30.0, 32.0),
+        (34.0, 35.0),
+        (34.0, 39.0),
+        (11.0, 39.0),
+        (11.0, 35.0),
+        (15.0, 32.0),
+    ])),
+    body(Shape::Circle(22.5, 14.0, 5.5)),
+];
+
+const ROOK: [Part; 2] = [
+    body(Shape::Polygon(&[
+        (9.0, 39.0),
+        (36.0, 39.0),
+        (36.0, 35.0),
+        (32.0, 35.0),
+        (31.0, 18.0),
+        (34.0, 15.0),
+        (34.0, 9.0),
+        (30.0, 9.0),
+        (30.0, 12.0),
+        (25.0, 12.0),
+        (25.0, 9.0),
+        (20.0, 9.0),
+        (20.0, 12.0),
+        (15.0, 12.0),
+        (15.0, 9.0),
+        (11.0, 9.0),
+        (11.0, 15.0),
+        (14.0, 18.0),
+        (13.0, 35.0),
+        (9.0, 35.0),
+    ])),
+    detail(Shape::Polygon(&[
+        (14.0, 17.0),
+        (31.0, 17.0),
+        (31.0, 18.5),
+        (14.0, 18.5),
+    ])),
+];
+
+const KNIGHT: [Part; 2] = [
+    body(Shape::Polygon(&[
+        (12.0, 39.0),
+        (36.0, 39.0),
+        (35.0, 30.0),
+        (34.0, 20.0),
+        (31.0, 13.0),
+        (25.0, 9.0),
+        (22.0, 5.0),
+        (20.0, 10.0),
+        (16.0, 12.0),
+        (10.0, 20.0),
+        (8.0, 26.0),
+        (11.0, 28.0),
+        (15.0, 25.0),
+        (20.0, 24.0),
+        (14.0, 33.0),
+    ])),
+    detail(Shape::Circle(18.0, 16.0, 1.5)),
+];
+
+const BISHOP: [Part; 3] = [
+    body(Shape::Polygon(&[
+        (10.0, 39.0),
+        (35.0, 39.0),
+        (35.0, 36.0),
+        (28.0, 34.0),
+        (30.0, 30.0),
+        (30.0, 24.0),
+        (26.0, 17.0),
+        (22.5, 12.0),
+        (19.0, 17.0),
+        (15.0, 24.0),
+        (15.0, 30.0),
+        (17.0, 34.0),
+        (10.0, 36.0),
+    ])),
+    body(Shape::Circle(22.5, 9.0, 2.5)),
+    detail(Shape::Polygon(&[
+        (24.5, 19.5),
+        (26.0, 21.0),
+        (21.0, 26.5),
+        (19.5, 25.0),
+    ])),
+];
+
+const QUEEN: [Part; 6] = [
+    body(Shape::Polygon(&[
+        (11.0, 39.0),
+        (34.0, 39.0),
+        (33.0, 34.0),
+        (31.0, 31.0),
+        (36.0, 14.0),
+        (30.0, 24.0),
+        (29.5, 11.0),
+        (25.5, 23.0),
+        (22.5, 10.0),
+        (19.5, 23.0),
+        (15.5, 11.0),
+        (15.0, 24.0),
+        (9.0, 14.0),
+        (14.0, 31.0),
+        (12.0, 34.0),
+    ])),
+    body(Shape::Circle(9.0, 13.0, 2.5)),
+    body(Shape::Circle(15.5, 10.0, 2.5)),
+    body(Shape::Circle(22.5, 9.0, 2.5)),
+    body(Shape::Circle(29.5, 10.0, 2.5)),
+    body(Shape::Circle(36.0, 13.0, 2.5)),
+];
+
+const KING: [Part; 3] = [
+    body(Shape::Polygon(&[
+        (11.0, 39.0),
+        (34.0, 39.0),
+        (33.0, 34.0),
+        (35.0, 26.0),
+        (37.0, 21.0),
+        (33.0, 17.0),
+        (27.0, 18.0),
+        (22.5, 22.0),
+        (18.0, 18.0),
+        (12.0, 17.0),
+        (8.0, 21.0),
+        (10.0, 26.0),
+        (12.0, 34.0),
+    ])),
+    body(Shape::Polygon(&[
+        (21.5, 5.0),
+        (23.5, 5.0),
+        (23.5, 8.0),
+        (26.5, 8.0),
+        (26.5, 10.0),
+        (23.5, 10.0),
+        (23.5, 19.0),
+        (21.5, 19.0),
+        (21.5, 10.0),
+        (18.5, 10.0),
+        (18.5, 8.0),
+        (21.5, 8.0),
+    ])),
+    detail(Shape::Polygon(&[
+        (12.0, 33.0),
+        (33.0, 33.0),
+        (33.0, 34.5),
+        (12.0, 34.5),
+    ])),
+];
+
+/// The shapes a piece is drawn with, from back to front
+pub fn parts(piece: Piece) -> &'static [Part] {
+    match piece {
+        Piece::None => &[],
+        Piece::Bishop => &BISHOP,
+        Piece::King => &KING,
+        Piece::Knight => &KNIGHT,
+        Piece::Pawn => &PAWN,
+        Piece::Queen => &QUEEN,
+        Piece::Rook => &ROOK,
+    }
+}
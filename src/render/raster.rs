@@ -0,0 +1,417 @@
+use super::pieces::{self, Shape};
+use super::Diagram;
+use crate::chess::{Board, Move, Piece, Position};
+use crate::theme;
+use std::io;
+
+/// Side of a square in board units, the same as in SVG diagrams
+const SQUARE: f32 = pieces::SIZE;
+const STROKE: f32 = 1.5;
+const ARROW_COLOR: Rgb = (21, 120, 27);
+const OUTLINE: Rgb = (0, 0, 0);
+/// Samples per pixel along each axis
+const SAMPLES: u32 = 3;
+
+type Rgb = (u8, u8, u8);
+
+/// Files and ranks in a 3 by 5 pixel font, one string per row
+const GLYPHS: [[&str; 5]; 16] = [
+    ["...", ".##", "#.#", "#.#", ".##"],
+    ["#..", "##.", "#.#", "#.#", "##."],
+    ["...", ".##", "#..", "#..", ".##"],
+    ["..#", ".##", "#.#", "#.#", ".##"],
+    ["...", ".#.", "###", "#..", ".##"],
+    [".##", "#..", "##.", "#..", "#.."],
+    [".##", "#.#", ".##", "..#", "##."],
+    ["#..", "##.", "#.#", "#.#", "#.#"],
+    [".#.", "##.", ".#.", ".#.", "###"],
+    ["##.", "..#", ".#.", "#..", "###"],
+    ["##.", "..#", ".#.", "..#", "##."],
+    ["#.#", "#.#", "###", "..#", "..#"],
+    ["###", "#..", "##.", "..#", "##."],
+    [".##", "#..", "###", "#.#", "###"],
+    ["###", "..#", ".#.", ".#.", ".#."],
+    ["###", "#.#", "###", "#.#", "###"],
+];
+const GLYPH_CELL: f32 = 1.6;
+
+/// An image as rows of red, green and blue bytes
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn to_png(&self) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(other_error)?;
+        writer.write_image_data(&self.pixels).map_err(other_error)?;
+        writer.finish().map_err(other_error)?;
+
+        Ok(data)
+    }
+}
+
+/// The board drawn like `board_svg` draws it, antialiased by averaging
+/// several samples per pixel
+pub fn board_image(board: &Board, diagram: &Diagram) -> Image {
+    let size = diagram.size.max(8);
+    let scale = SQUARE * 8.0 / size as f32;
+    let scene = Scene::new(board, diagram);
+    let mut pixels = Vec::with_capacity((size * size * 3) as usize);
+
+    for py in 0..size {
+        for px in 0..size {
+            let mut sum = [0u32; 3];
+
+            for sy in 0..SAMPLES {
+                for sx in 0..SAMPLES {
+                    let u = (px as f32 + (sx as f32 + 0.5) / SAMPLES as f32) * scale;
+                    let v = (py as f32 + (sy as f32 + 0.5) / SAMPLES as f32) * scale;
+                    let (r, g, b) = scene.colour_at(u, v);
+
+                    sum[0] += r as u32;
+                    sum[1] += g as u32;
+                    sum[2] += b as u32;
+                }
+            }
+
+            for channel in sum.iter() {
+                pixels.push((channel / (SAMPLES * SAMPLES)) as u8);
+            }
+        }
+    }
+
+    Image {
+        width: size,
+        height: size,
+        pixels,
+    }
+}
+
+pub fn board_png(board: &Board, diagram: &Diagram) -> io::Result<Vec<u8>> {
+    board_image(board, diagram).to_png()
+}
+
+/// The game as an animated GIF that shows the start position and then the
+/// position after each move, each for `delay` hundredths of a second and
+/// the final one three times as long
+pub fn game_gif(
+    start: &Board,
+    moves: &[Move],
+    diagram: &Diagram,
+    delay: u16,
+) -> io::Result<Vec<u8>> {
+    let size = diagram.size.max(8) as u16;
+    let mut data = Vec::new();
+
+    {
+        let mut encoder = gif::Encoder::new(&mut data, size, size, &[]).map_err(other_error)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(other_error)?;
+
+        let mut board = start.clone();
+        board.prev_move = None;
+
+        for idx in 0..=moves.len() {
+            if idx > 0 {
+                board.make_move(&moves[idx - 1]);
+            }
+
+            let image = board_image(&board, diagram);
+            let mut frame = gif::Frame::from_rgb_speed(size, size, &image.pixels, 10);
+
+            frame.delay = if idx == moves.len() {
+                delay.saturating_mul(3)
+            } else {
+                delay
+            };
+
+            encoder.write_frame(&frame).map_err(other_error)?;
+        }
+    }
+
+    Ok(data)
+}
+
+fn other_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> io::Error {
+    io::Error::other(err)
+}
+
+/// Everything drawn on the board, looked up by point in board units
+struct Scene<'a> {
+    board: &'a Board,
+    diagram: &'a Diagram,
+    theme: theme::Theme,
+    check: Option<Position>,
+}
+
+impl<'a> Scene<'a> {
+    fn new(board: &'a Board, diagram: &'a Diagram) -> Scene<'a> {
+        let check = if board.is_check() {
+            board.find_king(board.turn)
+        } else {
+            None
+        };
+
+        Scene {
+            board,
+            diagram,
+            theme: theme::current(),
+            check,
+        }
+    }
+
+    fn colour_at(&self, u: f32, v: f32) -> Rgb {
+        let col = ((u / SQUARE) as i32).clamp(0, 7);
+        let row = ((v / SQUARE) as i32).clamp(0, 7);
+
+        let pos = if self.diagram.flipped {
+            Position::new(7 - col, row)
+        } else {
+            Position::new(col, 7 - row)
+        };
+
+        let highlighted = match self.board.prev_move {
+            Some(mv) if self.diagram.last_move => mv.to == pos || (!mv.is_drop() && mv.from == pos),
+            _ => false,
+        };
+
+        let mut colour = if highlighted {
+            theme::rgb(self.theme.last_move)
+        } else if (pos.x + pos.y) % 2 == 0 {
+            theme::rgb(self.theme.dark)
+        } else {
+            theme::rgb(self.theme.light)
+        };
+
+        // Square left and top corner, and the point within the square
+        let (left, top) = (col as f32 * SQUARE, row as f32 * SQUARE);
+        let (x, y) = (u - left, v - top);
+
+        if self.diagram.coordinates {
+            if let Some(text) = self.coordinate_colour(col, row, x, y) {
+                colour = text;
+            }
+        }
+
+        if self.check == Some(pos) {
+            colour = check_gradient(colour, x, y);
+        }
+
+        let square = self.board.getp(&pos);
+
+        if !square.is_none() {
+            colour = piece_colour(colour, square.piece, square.is_white(), &self.theme, x, y);
+        }
+
+        for circle in self.diagram.circles.iter() {
+            let (cx, cy) = self.centre(circle);
+            let dist = ((u - cx).powi(2) + (v - cy).powi(2)).sqrt();
+
+            if (dist - (SQUARE / 2.0 - 2.5)).abs() <= 1.5 {
+                colour = blend(colour, ARROW_COLOR, 0.8);
+            }
+        }
+
+        for (from, to) in self.diagram.arrows.iter() {
+            if self.on_arrow(from, to, u, v) {
+                colour = blend(colour, ARROW_COLOR, 0.8);
+            }
+        }
+
+        colour
+    }
+
+    /// The text colour where a file letter or rank digit covers the point
+    fn coordinate_colour(&self, col: i32, row: i32, x: f32, y: f32) -> Option<Rgb> {
+        let width = GLYPH_CELL * 3.0;
+        let height = GLYPH_CELL * 5.0;
+
+        // Letters go in the bottom right of the bottom row and digits in the
+        // top left of the left column
+        let (glyph, gx, gy, on_light) = if row == 7
+            && x >= SQUARE - 2.0 - width
+            && x < SQUARE - 2.0
+            && y >= SQUARE - 2.5 - height
+            && y < SQUARE - 2.5
+        {
+            let file = if self.diagram.flipped { 7 - col } else { col };
+            (
+                file,
+                x - (SQUARE - 2.0 - width),
+                y - (SQUARE - 2.5 - height),
+                col % 2 == 1,
+            )
+        } else if col == 0 && (2.0..2.0 + width).contains(&x) && (2.5..2.5 + height).contains(&y) {
+            let rank = if self.diagram.flipped { row } else { 7 - row };
+            (8 + rank, x - 2.0, y - 2.5, row % 2 == 0)
+        } else {
+            return None;
+        };
+
+        let cell_x = (gx / GLYPH_CELL) as usize;
+        let cell_y = (gy / GLYPH_CELL) as usize;
+        let bit = GLYPHS[glyph as usize].get(cell_y)?.as_bytes().get(cell_x)?;
+
+        if *bit != b'#' {
+            return None;
+        }
+
+        // In the colour of the other kind of square
+        Some(if on_light {
+            theme::rgb(self.theme.dark)
+        } else {
+            theme::rgb(self.theme.light)
+        })
+    }
+
+    fn centre(&self, pos: &Position) -> (f32, f32) {
+        let (col, row) = if self.diagram.flipped {
+            (7 - pos.x, pos.y)
+        } else {
+            (pos.x, 7 - pos.y)
+        };
+
+        ((col as f32 + 0.5) * SQUARE, (row as f32 + 0.5) * SQUARE)
+    }
+
+    /// A shaft with a round end and a head whose tip is on the centre of the
+    /// target square
+    fn on_arrow(&self, from: &Position, to: &Position, u: f32, v: f32) -> bool {
+        let (x1, y1) = self.centre(from);
+        let (x2, y2) = self.centre(to);
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+
+        let head = SQUARE * 0.6;
+        let shaft_end = (x2 - dx * SQUARE * 0.3, y2 - dy * SQUARE * 0.3);
+
+        if segment_distance((u, v), (x1, y1), shaft_end) <= SQUARE * 0.1 {
+            return true;
+        }
+
+        // Distance along the arrow back from the tip, and to either side
+        let along = (x2 - u) * dx + (y2 - v) * dy;
+        let across = ((u - x2) * -dy + (v - y2) * dx).abs();
+
+        along >= 0.0 && along <= head && across <= along / 2.0
+    }
+}
+
+/// Red fading out from the centre of the square of a king in check
+fn check_gradient(colour: Rgb, x: f32, y: f32) -> Rgb {
+    let dist = ((x - SQUARE / 2.0).powi(2) + (y - SQUARE / 2.0).powi(2)).sqrt() / (SQUARE / 2.0);
+
+    let (red, alpha) = if dist <= 0.25 {
+        let t = dist / 0.25;
+        ((255.0 - t * 24.0) as u8, 1.0)
+    } else if dist <= 0.9 {
+        let t = (dist - 0.25) / 0.65;
+        ((231.0 - t * 73.0) as u8, 1.0 - t)
+    } else {
+        return colour;
+    };
+
+    blend(colour, (red, 0, 0), alpha)
+}
+
+/// The colour at a point of a square after drawing the piece's parts in
+/// order, each filled and then stroked as in SVG
+fn piece_colour(
+    background: Rgb,
+    piece: Piece,
+    white: bool,
+    theme: &theme::Theme,
+    x: f32,
+    y: f32,
+) -> Rgb {
+    let (fill, detail) = if white {
+        (theme::rgb(theme.white_pieces), (0, 0, 0))
+    } else {
+        (theme::rgb(theme.black_pieces), (255, 255, 255))
+    };
+
+    let mut colour = background;
+
+    for part in pieces::parts(piece) {
+        let (inside, edge) = match part.shape {
+            Shape::Polygon(points) => (in_polygon(points, x, y), polygon_distance(points, x, y)),
+            Shape::Circle(cx, cy, r) => {
+                let dist = ((x - cx).powi(2) + (y - cy).powi(2)).sqrt();
+                (dist <= r, (dist - r).abs())
+            }
+        };
+
+        if part.detail {
+            if inside {
+                colour = detail;
+            }
+        } else if edge <= STROKE / 2.0 {
+            colour = OUTLINE;
+        } else if inside {
+            colour = fill;
+        }
+    }
+
+    colour
+}
+
+/// Even-odd test, which for the simple piece outlines is the same as
+/// nonzero
+fn in_polygon(points: &[(f32, f32)], x: f32, y: f32) -> bool {
+    let mut inside = false;
+    let mut prev = points[points.len() - 1];
+
+    for point in points.iter() {
+        let ((x1, y1), (x2, y2)) = (prev, *point);
+
+        if (y1 > y) != (y2 > y) && x < (x2 - x1) * (y - y1) / (y2 - y1) + x1 {
+            inside = !inside;
+        }
+
+        prev = *point;
+    }
+
+    inside
+}
+
+fn polygon_distance(points: &[(f32, f32)], x: f32, y: f32) -> f32 {
+    let mut prev = points[points.len() - 1];
+    let mut dist = f32::MAX;
+
+    for point in points.iter() {
+        dist = dist.min(segment_distance((x, y), prev, *point));
+        prev = *point;
+    }
+
+    dist
+}
+
+fn segment_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dy * dy;
+
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length).clamp(0.0, 1.0)
+    };
+
+    ((p.0 - a.0 - t * dx).powi(2) + (p.1 - a.1 - t * dy).powi(2)).sqrt()
+}
+
+fn blend(base: Rgb, over: Rgb, alpha: f32) -> Rgb {
+    let mix = |a: u8, b: u8| (a as f32 * (1.0 - alpha) + b as f32 * alpha).round() as u8;
+    (
+        mix(base.0, over.0),
+        mix(base.1, over.1),
+        mix(base.2, over.2),
+    )
+}
@@ -0,0 +1,294 @@
+use super::pieces::{self, Shape};
+use crate::chess::{Board, Position};
+use crate::theme;
+use ansi_term::Colour;
+use std::fmt::Write;
+
+/// Side of a square in SVG units, so pieces are drawn at their own size
+const SQUARE: f32 = pieces::SIZE;
+const ARROW_COLOR: &str = "#15781b";
+const CIRCLE_COLOR: &str = "#15781b";
+
+/// What to draw besides the position
+#[derive(Debug, Clone)]
+pub struct Diagram {
+    /// Black at the bottom
+    pub flipped: bool,
+    /// Width and height in pixels
+    pub size: u32,
+    pub coordinates: bool,
+    /// Highlight the squares of the previous move
+    pub last_move: bool,
+    pub arrows: Vec<(Position, Position)>,
+    pub circles: Vec<Position>,
+}
+
+impl Default for Diagram {
+    fn default() -> Diagram {
+        Diagram {
+            flipped: false,
+            size: 360,
+            coordinates: true,
+            last_move: true,
+            arrows: Vec::new(),
+            circles: Vec::new(),
+        }
+    }
+}
+
+impl Diagram {
+    /// Adds an arrow written as two squares like `e2e4`, or a circle
+    /// around one square like `d5`
+    pub fn mark(&mut self, text: &str) -> bool {
+        let bytes = text.as_bytes();
+
+        match bytes.len() {
+            2 => match Position::from(bytes) {
+                Some(pos) => self.circles.push(pos),
+                None => return false,
+            },
+            4 => match (Position::from(&bytes[..2]), Position::from(&bytes[2..])) {
+                (Some(from), Some(to)) if from != to => self.arrows.push((from, to)),
+                _ => return false,
+            },
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Top left corner of a square in SVG units
+    fn corner(&self, pos: &Position) -> (f32, f32) {
+        let (col, row) = if self.flipped {
+            (7 - pos.x, pos.y)
+        } else {
+            (pos.x, 7 - pos.y)
+        };
+
+        (col as f32 * SQUARE, row as f32 * SQUARE)
+    }
+
+    fn centre(&self, pos: &Position) -> (f32, f32) {
+        let (x, y) = self.corner(pos);
+        (x + SQUARE / 2.0, y + SQUARE / 2.0)
+    }
+}
+
+/// The board as a standalone SVG document in the colours of the current
+/// theme, highlighting the previous move and a king in check
+pub fn board_svg(board: &Board, diagram: &Diagram) -> String {
+    let theme = theme::current();
+    let size = SQUARE * 8.0;
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" version=\"1.1\" \
+         width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        diagram.size, diagram.size, size, size
+    );
+
+    svg.push_str(
+        "<defs>\
+         <radialGradient id=\"check\">\
+         <stop offset=\"0%\" stop-color=\"#ff0000\"/>\
+         <stop offset=\"25%\" stop-color=\"#e70000\"/>\
+         <stop offset=\"90%\" stop-color=\"#9e0000\" stop-opacity=\"0\"/>\
+         </radialGradient>\
+         <marker id=\"arrowhead\" viewBox=\"0 0 10 10\" refX=\"5\" refY=\"5\" \
+         markerWidth=\"3\" markerHeight=\"3\" orient=\"auto\">\
+         <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"",
+    );
+    svg.push_str(ARROW_COLOR);
+    svg.push_str("\"/></marker></defs>\n");
+
+    let highlighted = |pos: &Position| match board.prev_move {
+        Some(mv) if diagram.last_move => mv.to == *pos || (!mv.is_drop() && mv.from == *pos),
+        _ => false,
+    };
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let pos = Position::new(x, y);
+            let (left, top) = diagram.corner(&pos);
+
+            let colour = if highlighted(&pos) {
+                theme.last_move
+            } else if (x + y) % 2 == 0 {
+                theme.dark
+            } else {
+                theme.light
+            };
+
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                left,
+                top,
+                SQUARE,
+                SQUARE,
+                hex(colour)
+            );
+        }
+    }
+
+    if diagram.coordinates {
+        write_coordinates(&mut svg, diagram);
+    }
+
+    if board.is_check() {
+        if let Some(king) = board.find_king(board.turn) {
+            let (left, top) = diagram.corner(&king);
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"url(#check)\"/>",
+                left, top, SQUARE, SQUARE
+            );
+        }
+    }
+
+    for y in 0..8 {
+        for x in 0..8 {
+            let square = board.get(x, y);
+
+            if square.is_none() {
+                continue;
+            }
+
+            let (left, top) = diagram.corner(&Position::new(x, y));
+            let (fill, detail) = if square.is_white() {
+                (theme.white_pieces, Colour::RGB(0, 0, 0))
+            } else {
+                (theme.black_pieces, Colour::RGB(255, 255, 255))
+            };
+
+            let _ = writeln!(
+                svg,
+                "<g transform=\"translate({} {})\" stroke=\"#000000\" stroke-width=\"1.5\" \
+                 stroke-linejoin=\"round\">",
+                left, top
+            );
+
+            for part in pieces::parts(square.piece) {
+                let (colour, stroke) = if part.detail {
+                    (detail, " stroke=\"none\"")
+                } else {
+                    (fill, "")
+                };
+
+                match part.shape {
+                    Shape::Polygon(points) => {
+                        let points: Vec<String> =
+                            points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+                        let _ = writeln!(
+                            svg,
+                            "<polygon points=\"{}\" fill=\"{}\"{}/>",
+                            points.join(" "),
+                            hex(colour),
+                            stroke
+                        );
+                    }
+                    Shape::Circle(cx, cy, r) => {
+                        let _ = writeln!(
+                            svg,
+                            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"{}/>",
+                            cx,
+                            cy,
+                            r,
+                            hex(colour),
+                            stroke
+                        );
+                    }
+                }
+            }
+
+            svg.push_str("</g>\n");
+        }
+    }
+
+    for pos in diagram.circles.iter() {
+        let (cx, cy) = diagram.centre(pos);
+        let _ = writeln!(
+            svg,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" \
+             stroke-width=\"3\" opacity=\"0.8\"/>",
+            cx,
+            cy,
+            SQUARE / 2.0 - 2.5,
+            CIRCLE_COLOR
+        );
+    }
+
+    for (from, to) in diagram.arrows.iter() {
+        let (x1, y1) = diagram.centre(from);
+        let (x2, y2) = diagram.centre(to);
+
+        // Stop short of the centre so the head ends there
+        let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        let shorten = SQUARE * 0.3 / length;
+        let (x2, y2) = (x2 - (x2 - x1) * shorten, y2 - (y2 - y1) * shorten);
+
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" \
+             stroke-width=\"{}\" stroke-linecap=\"round\" opacity=\"0.8\" \
+             marker-end=\"url(#arrowhead)\"/>",
+            x1,
+            y1,
+            x2,
+            y2,
+            ARROW_COLOR,
+            SQUARE * 0.2
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Files along the bottom edge and ranks along the left edge, in the colour
+/// of the other kind of square so they stay readable
+fn write_coordinates(svg: &mut String, diagram: &Diagram) {
+    let theme = theme::current();
+
+    for idx in 0..8 {
+        let file = if diagram.flipped { 7 - idx } else { idx };
+        let rank = if diagram.flipped { idx } else { 7 - idx };
+
+        // The bottom left square is dark, so squares alternate from there
+        let bottom = if idx % 2 == 0 {
+            theme.light
+        } else {
+            theme.dark
+        };
+        let left = if idx % 2 == 0 {
+            theme.dark
+        } else {
+            theme.light
+        };
+
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"8\" \
+             font-weight=\"bold\" text-anchor=\"end\" fill=\"{}\">{}</text>",
+            idx as f32 * SQUARE + SQUARE - 2.0,
+            8.0 * SQUARE - 2.5,
+            hex(bottom),
+            (b'a' + file as u8) as char
+        );
+
+        let _ = writeln!(
+            svg,
+            "<text x=\"2\" y=\"{}\" font-family=\"sans-serif\" font-size=\"8\" \
+             font-weight=\"bold\" fill=\"{}\">{}</text>",
+            idx as f32 * SQUARE + 8.5,
+            hex(left),
+            rank + 1
+        );
+    }
+}
+
+fn hex(colour: Colour) -> String {
+    let (r, g, b) = theme::rgb(colour);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
@@ -0,0 +1,277 @@
+use crate::chess::{Board, Move, Piece, Position, Variant};
+
+/// Pieces in the order they are listed when describing a side
+const PIECE_ORDER: [Piece; 6] = [
+    Piece::King,
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Pawn,
+];
+
+/// The board as plain letters, with `[N]` around the selected piece, `( )`
+/// around its targets and `< >` around the squares of the previous move
+pub fn draw_ascii_board(board: &Board, reverse: bool) {
+    let mut rows: Vec<String> = Vec::new();
+
+    for yi in 0..8 {
+        let y = if reverse { yi } else { 7 - yi };
+        let cells: String = (0..8).map(|x| ascii_cell(board, x, y)).collect();
+
+        rows.push(format!("{} {} {}", y + 1, cells, y + 1));
+    }
+
+    if board.variant == Variant::Crazyhouse {
+        let (top, bottom) = if reverse { (1, 2) } else { (2, 1) };
+        rows[0] = format!("{}   {}", rows[0], pocket_letters(board, top));
+        rows[7] = format!("{}   {}", rows[7], pocket_letters(board, bottom));
+    }
+
+    println!("\n   a  b  c  d  e  f  g  h");
+    println!("{}", rows.join("\n"));
+    println!("   a  b  c  d  e  f  g  h");
+
+    if board.cur_pos.is_some() {
+        println!("[ ] selected  ( ) can move to");
+    } else if let Some(mv) = board.prev_move {
+        println!("< > last move {}", mv);
+    }
+}
+
+fn ascii_cell(board: &Board, x: i32, y: i32) -> String {
+    let square = board.get(x, y);
+
+    let letter = match (square.piece, square.is_white()) {
+        (Piece::None, _) => '.',
+        (piece, true) => piece.letter(),
+        (piece, false) => piece.letter().to_ascii_lowercase(),
+    };
+
+    let (open, close) = if let Some(pos) = board.cur_pos {
+        let target = board
+            .cur_moves
+            .iter()
+            .any(|mv| mv.to.x == x && mv.to.y == y);
+
+        if pos.x == x && pos.y == y {
+            ('[', ']')
+        } else if target {
+            ('(', ')')
+        } else {
+            (' ', ' ')
+        }
+    } else {
+        match board.prev_move {
+            Some(mv) if (mv.from.x == x && mv.from.y == y) || (mv.to.x == x && mv.to.y == y) => {
+                ('<', '>')
+            }
+            _ => (' ', ' '),
+        }
+    };
+
+    format!("{}{}{}", open, letter, close)
+}
+
+fn pocket_letters(board: &Board, player: i32) -> String {
+    let pocket = board.get_pocket(player);
+    let mut parts: Vec<String> = Vec::new();
+
+    for piece in pocket.pieces() {
+        let letter = if player == 1 {
+            piece.letter()
+        } else {
+            piece.letter().to_ascii_lowercase()
+        };
+
+        parts.push(format!("{}{}", letter, pocket.get(piece)));
+    }
+
+    parts.join(" ")
+}
+
+/// The position in sentences for screen readers: the previous move, whose
+/// turn it is, where every piece stands and what the selected piece can do
+pub fn describe_board(board: &Board) {
+    println!();
+
+    if let Some(mv) = board.prev_move {
+        let mut before = board.clone_with_history();
+        before.unmake_move();
+        println!("Last move: {}.", describe_move(&before, &mv));
+    }
+
+    println!("{} to move.", side_name(board.turn));
+
+    for player in 1..=2 {
+        println!(
+            "{} pieces: {}.",
+            side_name(player),
+            list_pieces(board, player)
+        );
+
+        if board.variant == Variant::Crazyhouse {
+            let pocket = board.get_pocket(player);
+            let pieces: Vec<String> = pocket
+                .pieces()
+                .into_iter()
+                .map(|piece| count_name(piece, pocket.get(piece) as usize))
+                .collect();
+
+            if !pieces.is_empty() {
+                println!("{} can drop: {}.", side_name(player), join_words(&pieces));
+            }
+        }
+    }
+
+    if let Some(pos) = board.cur_pos {
+        let square = board.getp(&pos);
+        let mut targets: Vec<String> = board.cur_moves.iter().map(|mv| mv.to.to_string()).collect();
+        targets.dedup();
+
+        println!(
+            "Selected {} {} on {}, which can move to {}.",
+            color_word(square.player),
+            piece_word(square.piece),
+            pos,
+            join_words(&targets)
+        );
+    }
+}
+
+/// A move in words as in "White knight from g1 to f3, takes black pawn",
+/// given the board before it was made
+fn describe_move(board: &Board, mv: &Move) -> String {
+    let side = side_name(board.turn);
+
+    let mut text = if mv.is_drop() {
+        format!("{} drops a {} on {}", side, piece_word(mv.drop), mv.to)
+    } else {
+        let piece = board.getp(&mv.from).piece;
+
+        if piece == Piece::King && (mv.to.x - mv.from.x).abs() == 2 {
+            let wing = if mv.to.x > mv.from.x {
+                "kingside"
+            } else {
+                "queenside"
+            };
+            format!("{} castles {}", side, wing)
+        } else {
+            let mut text = format!(
+                "{} {} from {} to {}",
+                side,
+                piece_word(piece),
+                mv.from,
+                mv.to
+            );
+
+            if board.is_en_passant(mv) {
+                text += &format!(", takes {} pawn en passant", color_word(3 - board.turn));
+            } else if board.is_capture(mv) {
+                let captured = board.getp(&mv.to);
+                text += &format!(
+                    ", takes {} {}",
+                    color_word(captured.player),
+                    piece_word(captured.piece)
+                );
+            }
+
+            if mv.promotion != Piece::None {
+                text += &format!(", promotes to {}", piece_word(mv.promotion));
+            }
+
+            text
+        }
+    };
+
+    let mut after = board.clone();
+    after.make_move(mv);
+
+    if after.is_checkmate() {
+        text += ", checkmate";
+    } else if after.is_check() {
+        text += ", check";
+    }
+
+    text
+}
+
+/// Every piece of one side grouped by kind, as in "king on e1, rooks on a1
+/// and h1"
+fn list_pieces(board: &Board, player: i32) -> String {
+    let mut groups: Vec<String> = Vec::new();
+
+    for piece in PIECE_ORDER.iter() {
+        let mut squares: Vec<String> = Vec::new();
+
+        for x in 0..8 {
+            for y in 0..8 {
+                let square = board.get(x, y);
+
+                if square.piece == *piece && square.player == player {
+                    squares.push(Position::new(x, y).to_string());
+                }
+            }
+        }
+
+        if !squares.is_empty() {
+            let name = if squares.len() == 1 {
+                piece_word(*piece).to_string()
+            } else {
+                format!("{}s", piece_word(*piece))
+            };
+
+            groups.push(format!("{} on {}", name, join_words(&squares)));
+        }
+    }
+
+    if groups.is_empty() {
+        String::from("none")
+    } else {
+        groups.join(", ")
+    }
+}
+
+fn join_words(words: &[String]) -> String {
+    match words {
+        [] => String::from("nothing"),
+        [word] => word.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+fn count_name(piece: Piece, count: usize) -> String {
+    if count == 1 {
+        format!("1 {}", piece_word(piece))
+    } else {
+        format!("{} {}s", count, piece_word(piece))
+    }
+}
+
+fn side_name(player: i32) -> &'static str {
+    if player == 1 {
+        "White"
+    } else {
+        "Black"
+    }
+}
+
+fn color_word(player: i32) -> &'static str {
+    if player == 1 {
+        "white"
+    } else {
+        "black"
+    }
+}
+
+fn piece_word(piece: Piece) -> &'static str {
+    match piece {
+        Piece::None => "empty square",
+        Piece::Bishop => "bishop",
+        Piece::King => "king",
+        Piece::Knight => "knight",
+        Piece::Pawn => "pawn",
+        Piece::Queen => "queen",
+        Piece::Rook => "rook",
+    }
+}
@@ -0,0 +1,158 @@
+use crate::arena;
+use crate::chess::Board;
+use crate::engine::Clock;
+use crate::pgn;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// `~/.rust-chess/autosave.pgn`, or the working directory without a home
+pub fn default_path() -> PathBuf {
+    match env::var_os("HOME") {
+        Some(home) => Path::new(&home).join(".rust-chess").join("autosave.pgn"),
+        None => PathBuf::from("autosave.pgn"),
+    }
+}
+
+/// Who the player at this terminal is playing against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Opponent {
+    /// Both sides move at this terminal
+    Local,
+    Computer {
+        level: i32,
+    },
+    Network,
+}
+
+impl Opponent {
+    fn name(&self) -> &'static str {
+        match self {
+            Opponent::Local => "local",
+            Opponent::Computer { .. } => "computer",
+            Opponent::Network => "network",
+        }
+    }
+}
+
+/// A game in progress with everything needed to continue it, written as
+/// PGN with extra tags so other programs can still read the moves
+#[derive(Debug, Clone)]
+pub struct SavedGame {
+    pub game: pgn::Game,
+    /// Side played at this terminal, 1 for white and 2 for black
+    pub player: i32,
+    pub opponent: Opponent,
+    /// Time control the game started with
+    pub control: Option<Clock>,
+    /// Time left with a time control, otherwise time used, white first
+    pub times: [Duration; 2],
+}
+
+impl SavedGame {
+    pub fn to_pgn(&self) -> String {
+        let mut game = self.game.clone();
+        let side = if self.player == 1 { "white" } else { "black" };
+
+        game.set_tag("LocalSide", side);
+        game.set_tag("Opponent", self.opponent.name());
+
+        if let Opponent::Computer { level } = self.opponent {
+            game.set_tag("Level", &level.to_string());
+        }
+
+        if let Some(control) = &self.control {
+            game.set_tag("TimeControl", &arena::format_time_control(control));
+        }
+
+        game.set_tag("WhiteClock", &format!("{:.1}", self.times[0].as_secs_f64()));
+        game.set_tag("BlackClock", &format!("{:.1}", self.times[1].as_secs_f64()));
+        game.to_pgn()
+    }
+
+    /// Reads the state back from the tags written by `to_pgn`
+    pub fn from_game(game: pgn::Game) -> Result<SavedGame, String> {
+        let player = match game.tag("LocalSide") {
+            Some("white") => 1,
+            Some("black") => 2,
+            _ => return Err(String::from("missing or invalid LocalSide tag")),
+        };
+
+        let opponent = match game.tag("Opponent") {
+            Some("local") => Opponent::Local,
+            Some("network") => Opponent::Network,
+            Some("computer") => match game.tag("Level").map(|level| level.parse::<i32>()) {
+                Some(Ok(level)) => Opponent::Computer { level },
+                _ => return Err(String::from("missing or invalid Level tag")),
+            },
+            _ => return Err(String::from("missing or invalid Opponent tag")),
+        };
+
+        let control = match game.tag("TimeControl") {
+            Some(tc) => match arena::parse_time_control(tc) {
+                Some(control) => Some(control),
+                None => return Err(format!("invalid TimeControl '{}'", tc)),
+            },
+            None => None,
+        };
+
+        let mut times = [Duration::ZERO; 2];
+
+        for (idx, name) in ["WhiteClock", "BlackClock"].iter().enumerate() {
+            times[idx] = match game.tag(name).map(|time| time.parse::<f64>()) {
+                Some(Ok(seconds)) if seconds >= 0.0 => Duration::from_secs_f64(seconds),
+                _ => return Err(format!("missing or invalid {} tag", name)),
+            };
+        }
+
+        Ok(SavedGame {
+            game,
+            player,
+            opponent,
+            control,
+            times,
+        })
+    }
+
+    /// The position reached, keeping the moves so they can be taken back
+    pub fn board(&self) -> Option<Board> {
+        let mut board = self.game.start_board()?;
+
+        for mv in self.game.moves.iter() {
+            board.make_move(mv);
+        }
+
+        Some(board)
+    }
+
+    /// Whether the game still has to be played out
+    pub fn in_progress(&self) -> bool {
+        self.game.winner().is_none()
+    }
+}
+
+pub fn read(path: &Path) -> io::Result<SavedGame> {
+    let text = fs::read_to_string(path)?;
+    let game = match pgn::parse(&text).into_iter().next() {
+        Some(game) => game,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no game in the file",
+            ))
+        }
+    };
+
+    SavedGame::from_game(game).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Writes the game, creating the directory it goes in
+pub fn write(path: &Path, saved: &SavedGame) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, saved.to_pgn())
+}
@@ -0,0 +1,323 @@
+use crate::chess::{Board, Piece, Position, Square, Variant};
+use crate::prompt::Prompt;
+use crate::{export_diagram, player_color, render};
+use ansi_term::{ANSIString, Colour};
+
+/// Commands understood by the editor, with their help text
+const COMMANDS: [(&str, &str); 14] = [
+    ("help", "show this list"),
+    (
+        "<piece><square>",
+        "place pieces, such as Ke1 Qd1 ke8, lowercase for black",
+    ),
+    ("remove", "<square>... take pieces off, also x"),
+    ("clear", "empty the board"),
+    ("reset", "the starting position"),
+    ("turn", "white|black set the side to move"),
+    ("castling", "KQkq|- set the castling rights"),
+    ("ep", "<square>|- set the en passant square"),
+    (
+        "fen",
+        "[fen] show the position as FEN, or set it up from FEN",
+    ),
+    ("flip", "turn the board around"),
+    ("export", "svg|png <file> write a diagram"),
+    ("check", "show what keeps the position from being played"),
+    ("play", "check the position and start a game from it"),
+    ("quit", "leave without playing"),
+];
+
+/// A position being set up. Castling rights are kept apart from the board,
+/// where they depend on which kings and rooks have moved.
+struct Setup {
+    board: Board,
+    /// White king side, white queen side, black king side, black queen side
+    castling: [bool; 4],
+}
+
+impl Setup {
+    fn new(board: Board) -> Setup {
+        let castling = board.castling_rights();
+        Setup { board, castling }
+    }
+
+    fn fen(&self) -> String {
+        let fen = self.board.to_fen();
+        let mut fields: Vec<&str> = fen.split(' ').collect();
+
+        let castling: String = ['K', 'Q', 'k', 'q']
+            .iter()
+            .zip(self.castling.iter())
+            .filter(|(_, right)| **right)
+            .map(|(c, _)| *c)
+            .collect();
+
+        fields[2] = if castling.is_empty() { "-" } else { &castling };
+        fields.join(" ")
+    }
+
+    /// Everything that keeps the position from being played, if anything
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let board = &self.board;
+        let mut kings = [0; 2];
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let square = board.get(x, y);
+
+                if square.piece == Piece::King {
+                    kings[(square.player - 1) as usize] += 1;
+                } else if square.piece == Piece::Pawn && (y == 0 || y == 7) {
+                    problems.push(format!(
+                        "Pawn on {}, pawns cannot stand on the first or last rank",
+                        Position::new(x, y)
+                    ));
+                }
+            }
+        }
+
+        // Kings are ordinary pieces in antichess
+        let royal = board.variant != Variant::Antichess;
+
+        if royal {
+            for player in 1..=2 {
+                let count = kings[(player - 1) as usize];
+
+                if count != 1 {
+                    problems.push(format!(
+                        "{} needs one king, not {}",
+                        player_color(player),
+                        count
+                    ));
+                }
+            }
+        }
+
+        for (idx, right) in self.castling.iter().enumerate() {
+            let player = if idx < 2 { 1 } else { 2 };
+            let y = if player == 1 { 0 } else { 7 };
+            let x = if idx % 2 == 0 { 7 } else { 0 };
+            let king = board.get(4, y);
+            let rook = board.get(x, y);
+
+            let possible = king.piece == Piece::King
+                && king.player == player
+                && rook.piece == Piece::Rook
+                && rook.player == player;
+
+            if *right && !possible {
+                problems.push(format!(
+                    "Castling needs the {} king on {} and a rook on {}",
+                    player_color(player).to_lowercase(),
+                    Position::new(4, y),
+                    Position::new(x, y)
+                ));
+            }
+        }
+
+        if let Some(pos) = board.en_passant {
+            // The pawn that just moved two squares stands in front of the
+            // square and came from the one behind it
+            let (rank, forward) = if board.turn == 1 { (5, -1) } else { (2, 1) };
+            let possible = pos.y == rank && {
+                let pawn = board.get(pos.x, pos.y + forward);
+
+                pawn.piece == Piece::Pawn
+                    && pawn.player != board.turn
+                    && board.getp(&pos).is_none()
+                    && board.get(pos.x, pos.y - forward).is_none()
+            };
+
+            if !possible {
+                problems.push(format!(
+                    "No pawn can have just moved past {} for en passant",
+                    pos
+                ));
+            }
+        }
+
+        if royal && kings == [1, 1] {
+            let mut other = board.clone();
+            other.turn = 3 - board.turn;
+
+            if other.is_check() {
+                problems.push(format!(
+                    "{} is in check with {} to move",
+                    player_color(other.turn),
+                    player_color(board.turn).to_lowercase()
+                ));
+            }
+        }
+
+        problems
+    }
+
+    /// Runs an editing command, returning a message for the player
+    fn command(&mut self, args: &[&str]) -> Result<String, String> {
+        match args[0] {
+            "remove" | "x" => {
+                for arg in args[1..].iter() {
+                    let pos = square(arg)?;
+                    self.board.setp(&pos, &Square::from(Piece::None, 0));
+                }
+            }
+            "clear" => {
+                for y in 0..8 {
+                    for x in 0..8 {
+                        self.board.set(x, y, &Square::from(Piece::None, 0));
+                    }
+                }
+
+                self.board.en_passant = None;
+                self.castling = [false; 4];
+            }
+            "reset" => *self = Setup::new(Board::new_variant(self.board.variant)),
+            "turn" => {
+                self.board.turn = match args.get(1) {
+                    Some(&"white") | Some(&"w") => 1,
+                    Some(&"black") | Some(&"b") => 2,
+                    _ => return Err(String::from("Usage: turn white|black")),
+                };
+            }
+            "castling" => {
+                let rights = match args.get(1) {
+                    Some(rights) => rights,
+                    None => return Err(String::from("Usage: castling KQkq|-")),
+                };
+
+                let mut castling = [false; 4];
+
+                for c in rights.chars() {
+                    match "KQkq".find(c) {
+                        Some(idx) => castling[idx] = true,
+                        None if c == '-' => {}
+                        None => return Err(format!("Invalid castling rights '{}'", rights)),
+                    }
+                }
+
+                self.castling = castling;
+            }
+            "ep" => {
+                self.board.en_passant = match args.get(1) {
+                    Some(&"-") => None,
+                    Some(arg) => Some(square(arg)?),
+                    None => return Err(String::from("Usage: ep <square>|-")),
+                };
+            }
+            "fen" if args.len() == 1 => return Ok(self.fen()),
+            "fen" => match Board::from_fen(&args[1..].join(" "), self.board.variant) {
+                Some(board) => *self = Setup::new(board),
+                None => return Err(format!("Invalid FEN '{}'", args[1..].join(" "))),
+            },
+            _ => {
+                for arg in args.iter() {
+                    self.place(arg)?;
+                }
+            }
+        }
+
+        Ok(String::new())
+    }
+
+    /// Places a piece written as its letter and square, such as `Nf3`
+    fn place(&mut self, text: &str) -> Result<(), String> {
+        let mut chars = text.chars();
+        let letter = chars.next().unwrap_or(' ');
+        let piece = Piece::from_letter(letter);
+
+        if piece == Piece::None || !letter.is_ascii_alphabetic() {
+            return Err(format!(
+                "Unknown command or piece '{}', type help for the commands",
+                text
+            ));
+        }
+
+        let player = if letter.is_ascii_uppercase() { 1 } else { 2 };
+        let pos = square(chars.as_str())?;
+        self.board.setp(&pos, &Square::from(piece, player));
+        Ok(())
+    }
+}
+
+fn square(text: &str) -> Result<Position, String> {
+    match Position::from(text.as_bytes()) {
+        Some(pos) if text.len() == 2 => Ok(pos),
+        _ => Err(format!("Invalid square '{}'", text)),
+    }
+}
+
+fn help() -> String {
+    let lines: Vec<String> = COMMANDS
+        .iter()
+        .map(|(name, text)| format!("  {:<16} {}", name, text))
+        .collect();
+
+    format!("Commands:\n{}", lines.join("\n"))
+}
+
+/// Lets the player set up a position, returning it once it is valid and
+/// the player chooses to play it, or None if they leave the editor
+pub fn run(board: Board, prompt: &mut Prompt) -> Option<Board> {
+    let mut setup = Setup::new(board);
+    let mut flipped = false;
+    let mut message = ANSIString::from("Place pieces such as Ke1 or ke8, type help for more");
+
+    let commands: Vec<&str> = COMMANDS
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| !name.starts_with('<'))
+        .chain(["x", "q", "exit"])
+        .collect();
+
+    loop {
+        render::draw_board(&setup.board, flipped);
+        println!("\n{}", setup.fen());
+
+        if !message.is_empty() {
+            println!("{}", render::paint(&message));
+            message = ANSIString::from("");
+        }
+
+        let line = prompt.read_commands("setup: ", &commands)?;
+        let args: Vec<&str> = line.split_whitespace().collect();
+
+        if args.is_empty() {
+            continue;
+        }
+
+        message = match args[0] {
+            "q" | "quit" | "exit" => return None,
+            "help" => ANSIString::from(help()),
+            "flip" => {
+                flipped = !flipped;
+                ANSIString::from("")
+            }
+            "export" => match Board::from_fen(&setup.fen(), setup.board.variant) {
+                Some(board) => match export_diagram(&board, flipped, &args[1..]) {
+                    Ok(message) => Colour::Green.paint(message),
+                    Err(message) => Colour::Red.paint(message),
+                },
+                None => Colour::Red.paint("The position cannot be drawn"),
+            },
+            "check" | "play" => {
+                let problems = setup.problems();
+
+                if !problems.is_empty() {
+                    Colour::Red.paint(problems.join("\n"))
+                } else if args[0] == "check" {
+                    Colour::Green.paint("The position is valid")
+                } else {
+                    match Board::from_fen(&setup.fen(), setup.board.variant) {
+                        Some(board) => return Some(board),
+                        None => Colour::Red.paint("The position cannot be played"),
+                    }
+                }
+            }
+            _ => match setup.command(&args) {
+                Ok(message) => Colour::Green.paint(message),
+                Err(message) => Colour::Red.paint(message),
+            },
+        };
+    }
+}
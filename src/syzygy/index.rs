@@ -0,0 +1,163 @@
+/// Lookup tables used to turn piece placements into table indices
+pub struct Indices {
+    /// Ways to choose k of n squares
+    pub binomial: [[u64; 64]; 7],
+    /// Squares a2-h7 numbered so the leading pawn has the highest value
+    pub map_pawns: [usize; 64],
+    pub lead_pawn_idx: [[u64; 64]; 6],
+    pub lead_pawns_size: [[u64; 4]; 6],
+    /// Squares below the a1-h8 diagonal numbered 0..27
+    pub map_b1h1h7: [u64; 64],
+    /// The a1-d1-d4 triangle numbered 0..9, diagonal squares last
+    pub map_a1d1d4: [usize; 64],
+    /// The 462 placements of two kings with the first in the a1-d1-d4 triangle
+    pub map_kk: [[u64; 64]; 10],
+}
+
+pub static INDICES: Indices = Indices::generate();
+
+/// Rank minus file, zero on the a1-h8 diagonal and negative below it
+pub const fn off_a1h8(sq: usize) -> i32 {
+    (sq >> 3) as i32 - (sq & 7) as i32
+}
+
+impl Indices {
+    const fn generate() -> Indices {
+        let mut indices = Indices {
+            binomial: [[0; 64]; 7],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+        };
+
+        let mut code = 0;
+        let mut sq = 0;
+        while sq < 64 {
+            if off_a1h8(sq) < 0 {
+                indices.map_b1h1h7[sq] = code;
+                code += 1;
+            }
+            sq += 1;
+        }
+
+        let mut diagonal = [0; 4];
+        let mut diagonal_len = 0;
+        let mut code = 0;
+        let mut sq = 0;
+        while sq <= 27 {
+            if sq & 7 <= 3 {
+                if off_a1h8(sq) < 0 {
+                    indices.map_a1d1d4[sq] = code;
+                    code += 1;
+                } else if off_a1h8(sq) == 0 {
+                    diagonal[diagonal_len] = sq;
+                    diagonal_len += 1;
+                }
+            }
+            sq += 1;
+        }
+
+        let mut idx = 0;
+        while idx < diagonal_len {
+            indices.map_a1d1d4[diagonal[idx]] = code;
+            code += 1;
+            idx += 1;
+        }
+
+        // Placements with both kings on the diagonal are numbered last
+        let mut both_on_diagonal = [(0, 0); 32];
+        let mut both_len = 0;
+        let mut code = 0;
+        let mut idx = 0;
+        while idx < 10 {
+            let mut s1 = 0;
+            while s1 <= 27 {
+                // b1 is the only square that maps to 0 on purpose
+                if indices.map_a1d1d4[s1] == idx && (idx != 0 || s1 == 1) {
+                    let mut s2 = 0;
+                    while s2 < 64 {
+                        let dx = (s1 & 7) as i32 - (s2 & 7) as i32;
+                        let dy = (s1 >> 3) as i32 - (s2 >> 3) as i32;
+
+                        let touching = dx.abs() <= 1 && dy.abs() <= 1;
+                        let on_diagonal = off_a1h8(s1) == 0;
+
+                        // With the first king on the diagonal the second stays below it
+                        let above = on_diagonal && off_a1h8(s2) > 0;
+
+                        if !touching && !above {
+                            if on_diagonal && off_a1h8(s2) == 0 {
+                                both_on_diagonal[both_len] = (idx, s2);
+                                both_len += 1;
+                            } else {
+                                indices.map_kk[idx][s2] = code;
+                                code += 1;
+                            }
+                        }
+
+                        s2 += 1;
+                    }
+                }
+                s1 += 1;
+            }
+            idx += 1;
+        }
+
+        let mut idx = 0;
+        while idx < both_len {
+            let (first, s2) = both_on_diagonal[idx];
+            indices.map_kk[first][s2] = code;
+            code += 1;
+            idx += 1;
+        }
+
+        indices.binomial[0][0] = 1;
+        let mut n = 1;
+        while n < 64 {
+            let mut k = 0;
+            while k < 7 && k <= n {
+                let left = if k > 0 {
+                    indices.binomial[k - 1][n - 1]
+                } else {
+                    0
+                };
+                let right = if k < n { indices.binomial[k][n - 1] } else { 0 };
+                indices.binomial[k][n] = left + right;
+                k += 1;
+            }
+            n += 1;
+        }
+
+        let mut available: i32 = 47;
+        let mut lead = 1;
+        while lead <= 5 {
+            let mut file = 0;
+            while file < 4 {
+                let mut idx = 0;
+                let mut rank = 1;
+                while rank <= 6 {
+                    let sq = rank * 8 + file;
+
+                    if lead == 1 {
+                        indices.map_pawns[sq] = available as usize;
+                        indices.map_pawns[sq ^ 7] = (available - 1) as usize;
+                        available -= 2;
+                    }
+
+                    indices.lead_pawn_idx[lead][sq] = idx;
+                    idx += indices.binomial[lead - 1][indices.map_pawns[sq]];
+                    rank += 1;
+                }
+
+                indices.lead_pawns_size[lead][file] = idx;
+                file += 1;
+            }
+            lead += 1;
+        }
+
+        indices
+    }
+}
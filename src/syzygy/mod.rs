@@ -0,0 +1,480 @@
+mod index;
+mod table;
+
+use self::table::{Kind, Lookup, Material, Table};
+use crate::chess::{Board, Move, Piece, Variant};
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Result for the side to move, where cursed wins and blessed losses are
+/// drawn by the 50-move rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl fmt::Display for Wdl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Wdl::Loss => "loss",
+            Wdl::BlessedLoss => "blessed loss",
+            Wdl::Draw => "draw",
+            Wdl::CursedWin => "cursed win",
+            Wdl::Win => "win",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Wdl {
+        match value {
+            v if v <= -2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+}
+
+/// A table file, mapped into memory the first time it is needed
+struct TableFile {
+    path: PathBuf,
+    kind: Kind,
+    table: OnceLock<Option<Table>>,
+}
+
+impl TableFile {
+    fn new(path: PathBuf, kind: Kind) -> TableFile {
+        TableFile {
+            path,
+            kind,
+            table: OnceLock::new(),
+        }
+    }
+
+    fn get(&self, material: Material) -> Option<&Table> {
+        self.table
+            .get_or_init(|| Table::load(&self.path, self.kind, material))
+            .as_ref()
+    }
+}
+
+struct Entry {
+    material: Material,
+    wdl: TableFile,
+    dtz: TableFile,
+}
+
+/// Syzygy tables found in one or more local directories
+pub struct Tablebase {
+    entries: HashMap<String, Entry>,
+    pub max_pieces: usize,
+}
+
+impl Tablebase {
+    /// Scans directories, separated like PATH entries, for .rtbw files.
+    /// Only WDL files are required, DTZ files are used when present.
+    pub fn open(paths: &str) -> io::Result<Tablebase> {
+        let mut entries = HashMap::new();
+        let mut max_pieces = 0;
+
+        for dir in env::split_paths(paths) {
+            for file in fs::read_dir(&dir)? {
+                let path = file?.path();
+
+                if path.extension() != Some(OsStr::new("rtbw")) {
+                    continue;
+                }
+
+                let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                    Some(name) => name.to_string(),
+                    None => continue,
+                };
+
+                let material = match Material::from(&name) {
+                    Some(material) => material,
+                    None => continue,
+                };
+
+                max_pieces = max_pieces.max(material.piece_count);
+
+                let entry = Entry {
+                    material,
+                    wdl: TableFile::new(path.clone(), Kind::Wdl),
+                    dtz: TableFile::new(path.with_extension("rtbz"), Kind::Dtz),
+                };
+
+                entries.insert(name, entry);
+            }
+        }
+
+        Ok(Tablebase {
+            entries,
+            max_pieces,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Tables only cover standard chess without castling rights
+    pub fn can_probe(&self, board: &Board) -> bool {
+        if board.variant != Variant::Standard || board.castling_rights().iter().any(|r| *r) {
+            return false;
+        }
+
+        let mut count = 0;
+
+        for y in 0..8 {
+            for x in 0..8 {
+                if board.get(x, y).piece != Piece::None {
+                    count += 1;
+                }
+            }
+        }
+
+        count <= self.max_pieces
+    }
+
+    /// Win, draw or loss for the side to move
+    pub fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        if !self.can_probe(board) {
+            return None;
+        }
+
+        let mut board = board.clone();
+        let (wdl, _) = self.search(&mut board, false)?;
+        Some(Wdl::from_value(wdl))
+    }
+
+    /// Plies to the next capture or pawn move with best play, negative when
+    /// losing and zero for draws
+    #[allow(dead_code)]
+    pub fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        if !self.can_probe(board) {
+            return None;
+        }
+
+        self.dtz(&mut board.clone())
+    }
+
+    /// Best move by the tables, taking the 50-move rule into account, with
+    /// the resulting result and distance to zeroing
+    pub fn best_move(&self, board: &Board) -> Option<(Move, Wdl, i32)> {
+        if !self.can_probe(board) {
+            return None;
+        }
+
+        const MAX_DTZ: i32 = 1 << 18;
+
+        let mut board = board.clone();
+        let mut best: Option<(Move, i32, i32)> = None;
+
+        for mv in board.get_legal_moves() {
+            let zeroing = board.is_capture(&mv) || board.getp(&mv.from).piece == Piece::Pawn;
+            let clock = if zeroing { 0 } else { board.halfmove_clock };
+
+            board.make_move(&mv);
+
+            let dtz = if board.is_check() && !board.can_move_any() {
+                Some(1)
+            } else if zeroing {
+                self.search(&mut board, false)
+                    .map(|(wdl, _)| dtz_before_zeroing(-wdl))
+            } else {
+                self.dtz(&mut board).map(|dtz| -dtz + (-dtz).signum())
+            };
+
+            board.unmake_move();
+
+            let dtz = dtz?;
+
+            // Wins that cannot be converted before the 50-move rule rank lower
+            let rank = if dtz > 0 {
+                if dtz + clock <= 100 {
+                    MAX_DTZ
+                } else {
+                    MAX_DTZ - (dtz + clock)
+                }
+            } else if dtz < 0 {
+                if -dtz * 2 + clock < 100 {
+                    -MAX_DTZ
+                } else {
+                    -MAX_DTZ + (-dtz + clock)
+                }
+            } else {
+                0
+            };
+
+            // Prefer the quickest win and the slowest loss
+            let better = match best {
+                Some((_, best_rank, best_dtz)) => (rank, -dtz) > (best_rank, -best_dtz),
+                None => true,
+            };
+
+            if better {
+                best = Some((mv, rank, dtz));
+            }
+        }
+
+        let (mv, rank, dtz) = best?;
+
+        let wdl = match rank {
+            MAX_DTZ => Wdl::Win,
+            r if r > 0 => Wdl::CursedWin,
+            0 => Wdl::Draw,
+            r if r == -MAX_DTZ => Wdl::Loss,
+            _ => Wdl::BlessedLoss,
+        };
+
+        Some((mv, wdl, dtz))
+    }
+
+    /// Piece codes per square as used by the tables, 1-6 for white pawn to
+    /// king and 9-14 for black
+    fn piece_codes(board: &Board) -> [u8; 64] {
+        let mut codes = [0; 64];
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let square = board.get(x, y);
+
+                let code = match square.piece {
+                    Piece::Pawn => 1,
+                    Piece::Knight => 2,
+                    Piece::Bishop => 3,
+                    Piece::Rook => 4,
+                    Piece::Queen => 5,
+                    Piece::King => 6,
+                    Piece::None => continue,
+                };
+
+                codes[(y * 8 + x) as usize] = if square.player == 2 { code | 8 } else { code };
+            }
+        }
+
+        codes
+    }
+
+    fn probe_table(&self, board: &Board, kind: Kind, wdl: i32) -> Option<Lookup> {
+        let codes = Tablebase::piece_codes(board);
+
+        if codes.iter().filter(|code| **code != 0).count() == 2 {
+            return Some(Lookup::Found(0));
+        }
+
+        let white = side_name(&codes, 0);
+        let black = side_name(&codes, 8);
+
+        let (entry, black_stronger) = match self.entries.get(&format!("{}v{}", white, black)) {
+            Some(entry) => (entry, false),
+            None => (self.entries.get(&format!("{}v{}", black, white))?, true),
+        };
+
+        let table = match kind {
+            Kind::Wdl => entry.wdl.get(entry.material)?,
+            Kind::Dtz => entry.dtz.get(entry.material)?,
+        };
+
+        let turn = if board.turn == 1 { 0 } else { 1 };
+        Some(table.probe(&codes, turn, black_stronger, wdl))
+    }
+
+    /// Captures, and pawn moves with `zeroing`, are searched because tables
+    /// may store any value where such a move wins. Returns the result and
+    /// whether the best move resets the 50-move counter.
+    fn search(&self, board: &mut Board, zeroing: bool) -> Option<(i32, bool)> {
+        let moves = board.get_legal_moves();
+        let mut best = -2;
+        let mut count = 0;
+
+        for mv in moves.iter() {
+            if !board.is_capture(mv) && (!zeroing || board.getp(&mv.from).piece != Piece::Pawn) {
+                continue;
+            }
+
+            count += 1;
+
+            board.make_move(mv);
+            let result = self.search(board, false);
+            board.unmake_move();
+
+            let value = -result?.0;
+
+            if value > best {
+                best = value;
+
+                if value >= 2 {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        // Tables do not store en passant rights, but if every move was
+        // searched the result is exact anyway
+        let all_searched = count > 0 && count == moves.len();
+
+        let value = if all_searched {
+            best
+        } else {
+            match self.probe_table(board, Kind::Wdl, 0)? {
+                Lookup::Found(value) => value,
+                Lookup::OtherSide => return None,
+            }
+        };
+
+        if best >= value {
+            return Some((best, best > 0 || all_searched));
+        }
+
+        Some((value, false))
+    }
+
+    fn dtz(&self, board: &mut Board) -> Option<i32> {
+        let (wdl, zeroing) = self.search(board, true)?;
+
+        if wdl == 0 {
+            return Some(0);
+        }
+
+        if zeroing {
+            return Some(dtz_before_zeroing(wdl));
+        }
+
+        if let Lookup::Found(dtz) = self.probe_table(board, Kind::Dtz, wdl)? {
+            let cursed = if wdl.abs() == 1 { 100 } else { 0 };
+            return Some((dtz + cursed) * wdl.signum());
+        }
+
+        // The table stores the other side to move, so search one ply
+        let mut min = i32::MAX;
+
+        for mv in board.get_legal_moves() {
+            let zeroing = board.is_capture(&mv) || board.getp(&mv.from).piece == Piece::Pawn;
+
+            board.make_move(&mv);
+
+            let result = if zeroing {
+                self.search(board, false)
+                    .map(|(wdl, _)| -dtz_before_zeroing(wdl))
+            } else {
+                self.dtz(board).map(|dtz| -dtz)
+            };
+
+            let mates = result == Some(1) && board.is_check() && !board.can_move_any();
+            board.unmake_move();
+
+            let mut dtz = result?;
+
+            if mates {
+                min = 1;
+            }
+
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+
+            if dtz < min && dtz.signum() == wdl.signum() {
+                min = dtz;
+            }
+        }
+
+        // Without legal moves the side to move is mated
+        Some(if min == i32::MAX { -1 } else { min })
+    }
+}
+
+/// Table name of one side, such as "KRP"
+fn side_name(codes: &[u8; 64], color: u8) -> String {
+    let mut name = String::from("K");
+
+    for (code, letter) in [(5, 'Q'), (4, 'R'), (3, 'B'), (2, 'N'), (1, 'P')].iter() {
+        let count = codes.iter().filter(|c| **c == code | color).count();
+        name.extend(std::iter::repeat_n(*letter, count));
+    }
+
+    name
+}
+
+/// DTZ of the move before a capture or pawn move, which tables do not store
+fn dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        2 => 1,
+        1 => 101,
+        -1 => -101,
+        -2 => -1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn krvk() -> Tablebase {
+        Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy")).unwrap()
+    }
+
+    fn position(fen: &str) -> Board {
+        Board::from_fen(fen, Variant::Standard).unwrap()
+    }
+
+    #[test]
+    fn probes_krvk() {
+        let tablebase = krvk();
+
+        // Mate in one, also mirrored and with the colours swapped
+        for fen in [
+            "6k1/8/6K1/8/8/8/8/R7 w - - 0 1",
+            "1k6/8/1K6/8/8/8/8/7R w - - 0 1",
+            "r7/8/8/8/8/6k1/8/6K1 b - - 0 1",
+        ] {
+            let board = position(fen);
+            assert_eq!(tablebase.probe_wdl(&board), Some(Wdl::Win), "{}", fen);
+            assert_eq!(tablebase.probe_dtz(&board), Some(1), "{}", fen);
+        }
+
+        // Mated after one move, which the DTZ table does not store
+        let board = position("7k/8/6K1/8/8/8/8/R7 b - - 0 1");
+        assert_eq!(tablebase.probe_wdl(&board), Some(Wdl::Loss));
+        assert_eq!(tablebase.probe_dtz(&board), Some(-2));
+
+        // Mate in sixteen, the longest in KRvK
+        let board = position("8/8/8/8/3k4/8/1R6/K7 w - - 0 1");
+        assert_eq!(tablebase.probe_wdl(&board), Some(Wdl::Win));
+        assert_eq!(tablebase.probe_dtz(&board), Some(31));
+
+        // The rook hangs, and stalemate
+        for fen in [
+            "8/8/8/8/8/8/1k6/R6K b - - 0 1",
+            "k7/1RK5/8/8/8/8/8/8 b - - 0 1",
+        ] {
+            let board = position(fen);
+            assert_eq!(tablebase.probe_wdl(&board), Some(Wdl::Draw), "{}", fen);
+            assert_eq!(tablebase.probe_dtz(&board), Some(0), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn best_move_mates() {
+        let board = position("6k1/8/6K1/8/8/8/8/R7 w - - 0 1");
+        let (mv, wdl, dtz) = krvk().best_move(&board).unwrap();
+
+        assert_eq!(mv.to_uci(), "a1a8");
+        assert_eq!((wdl, dtz), (Wdl::Win, 1));
+    }
+}
@@ -0,0 +1,759 @@
+use crate::syzygy::index::{off_a1h8, INDICES};
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+const MAX_PIECES: usize = 7;
+
+// Flags stored per table
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Wdl,
+    Dtz,
+}
+
+/// Material of a table, with white as the side named first
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub piece_count: usize,
+    pub has_pawns: bool,
+    pub has_unique_pieces: bool,
+    /// Pawns of the leading colour and of the other colour
+    pub pawn_count: [usize; 2],
+    pub symmetric: bool,
+}
+
+impl Material {
+    /// Parses a table name such as "KRPvKR"
+    pub fn from(name: &str) -> Option<Material> {
+        let mut sides = name.split('v');
+        let white = sides.next()?;
+        let black = sides.next()?;
+
+        if sides.next().is_some() {
+            return None;
+        }
+
+        for side in [white, black].iter() {
+            if !side.starts_with('K')
+                || side.matches('K').count() != 1
+                || !side.chars().all(|c| "KQRBNP".contains(c))
+            {
+                return None;
+            }
+        }
+
+        let piece_count = white.len() + black.len();
+
+        if piece_count > MAX_PIECES {
+            return None;
+        }
+
+        let unique = |side: &str| "QRBNP".chars().any(|c| side.matches(c).count() == 1);
+        let white_pawns = white.matches('P').count();
+        let black_pawns = black.matches('P').count();
+
+        // The side with fewer pawns leads, as that compresses better
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+
+        let pawn_count = if white_leads {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        };
+
+        Some(Material {
+            piece_count,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces: unique(white) || unique(black),
+            pawn_count,
+            symmetric: white == black,
+        })
+    }
+}
+
+pub enum Lookup {
+    Found(i32),
+    /// A DTZ table only stores one side to move, and it is not this one
+    OtherSide,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    max_sym_len: u8,
+    min_sym_len: u8,
+    num_blocks: usize,
+    block_size: usize,
+    span: usize,
+    lowest_sym: usize,
+    btree: usize,
+    block_length: usize,
+    block_length_size: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    pieces: [u8; MAX_PIECES],
+    group_idx: [u64; MAX_PIECES + 1],
+    group_len: [usize; MAX_PIECES + 1],
+    map_idx: [u16; 4],
+}
+
+/// A decoded .rtbw or .rtbz file, with pieces coded 1-6 for white pawn to
+/// king and 9-14 for black
+pub struct Table {
+    kind: Kind,
+    material: Material,
+    /// The file, mapped rather than read so only probed pages are loaded
+    bytes: Mmap,
+    /// Pairs data by side to move and by file of the leading pawn
+    items: Vec<Vec<PairsData>>,
+    map: usize,
+}
+
+impl Table {
+    pub fn load(path: &Path, kind: Kind, material: Material) -> Option<Table> {
+        let file = File::open(path).ok()?;
+        // Tables are not written to while open
+        let bytes = unsafe { Mmap::map(&file) }.ok()?;
+
+        let magic = match kind {
+            Kind::Wdl => WDL_MAGIC,
+            Kind::Dtz => DTZ_MAGIC,
+        };
+
+        if bytes.len() < 5 || bytes[0..4] != magic {
+            return None;
+        }
+
+        let mut table = Table {
+            kind,
+            material,
+            bytes,
+            items: Vec::new(),
+            map: 0,
+        };
+
+        table.setup();
+        Some(table)
+    }
+
+    fn u8_at(&self, offset: usize) -> u8 {
+        *self.bytes.get(offset).unwrap_or(&0)
+    }
+
+    fn u16_le(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.u8_at(offset), self.u8_at(offset + 1)])
+    }
+
+    fn u32_le(&self, offset: usize) -> u32 {
+        u32::from_le_bytes([
+            self.u8_at(offset),
+            self.u8_at(offset + 1),
+            self.u8_at(offset + 2),
+            self.u8_at(offset + 3),
+        ])
+    }
+
+    fn u32_be(&self, offset: usize) -> u32 {
+        u32::from_be_bytes([
+            self.u8_at(offset),
+            self.u8_at(offset + 1),
+            self.u8_at(offset + 2),
+            self.u8_at(offset + 3),
+        ])
+    }
+
+    fn pairs(&self, stm: usize, file: usize) -> &PairsData {
+        let side = &self.items[stm % self.items.len()];
+        &side[if self.material.has_pawns { file } else { 0 }]
+    }
+
+    /// Reads the header: piece order, groups, Huffman code and data offsets
+    fn setup(&mut self) {
+        let material = self.material;
+        let sides = if self.kind == Kind::Wdl && !material.symmetric {
+            2
+        } else {
+            1
+        };
+        let files = if material.has_pawns { 4 } else { 1 };
+        let both_pawns = material.has_pawns && material.pawn_count[1] > 0;
+
+        let mut items = vec![vec![PairsData::default(); files]; sides];
+
+        // The first byte after the magic holds flags we already know
+        let mut offset = 5;
+
+        for file in 0..files {
+            let first = self.u8_at(offset);
+            let second = self.u8_at(offset + 1);
+
+            let order = [
+                [
+                    (first & 0xF) as usize,
+                    if both_pawns {
+                        (second & 0xF) as usize
+                    } else {
+                        0xF
+                    },
+                ],
+                [
+                    (first >> 4) as usize,
+                    if both_pawns {
+                        (second >> 4) as usize
+                    } else {
+                        0xF
+                    },
+                ],
+            ];
+
+            offset += if both_pawns { 2 } else { 1 };
+
+            for k in 0..material.piece_count {
+                let byte = self.u8_at(offset);
+
+                for (side, item) in items.iter_mut().enumerate() {
+                    item[file].pieces[k] = if side == 1 { byte >> 4 } else { byte & 0xF };
+                }
+
+                offset += 1;
+            }
+
+            for (side, item) in items.iter_mut().enumerate() {
+                set_groups(&material, &mut item[file], order[side], file);
+            }
+        }
+
+        // Word alignment
+        offset += offset & 1;
+
+        for file in 0..files {
+            for item in items.iter_mut() {
+                offset = self.set_sizes(&mut item[file], offset);
+            }
+        }
+
+        if self.kind == Kind::Dtz {
+            self.map = offset;
+
+            for item in items[0].iter_mut() {
+                if item.flags & MAPPED == 0 {
+                    continue;
+                }
+
+                if item.flags & WIDE != 0 {
+                    offset += offset & 1;
+
+                    for idx in 0..4 {
+                        item.map_idx[idx] = ((offset - self.map) / 2 + 1) as u16;
+                        offset += 2 * self.u16_le(offset) as usize + 2;
+                    }
+                } else {
+                    for idx in 0..4 {
+                        item.map_idx[idx] = (offset - self.map + 1) as u16;
+                        offset += self.u8_at(offset) as usize + 1;
+                    }
+                }
+            }
+
+            offset += offset & 1;
+        }
+
+        for file in 0..files {
+            for item in items.iter_mut() {
+                item[file].sparse_index = offset;
+                offset += item[file].sparse_index_size * 6;
+            }
+        }
+
+        for file in 0..files {
+            for item in items.iter_mut() {
+                item[file].block_length = offset;
+                offset += item[file].block_length_size * 2;
+            }
+        }
+
+        for file in 0..files {
+            for item in items.iter_mut() {
+                // Compressed data starts on a 64 byte boundary
+                offset = (offset + 0x3F) & !0x3F;
+                item[file].data = offset;
+                offset += item[file].num_blocks * item[file].block_size;
+            }
+        }
+
+        self.items = items;
+    }
+
+    fn set_sizes(&self, d: &mut PairsData, mut offset: usize) -> usize {
+        d.flags = self.u8_at(offset);
+        offset += 1;
+
+        // Every position has the same value, stored in place of the symbol length
+        if d.flags & SINGLE_VALUE != 0 {
+            d.min_sym_len = self.u8_at(offset);
+            return offset + 1;
+        }
+
+        let groups = d.group_len.iter().position(|len| *len == 0).unwrap_or(0);
+        let tb_size = d.group_idx[groups] as usize;
+
+        d.block_size = 1 << self.u8_at(offset).min(31);
+        d.span = (1 << self.u8_at(offset + 1).min(31)) as usize;
+        d.sparse_index_size = tb_size.div_ceil(d.span);
+        let padding = self.u8_at(offset + 2) as usize;
+        d.num_blocks = self.u32_le(offset + 3) as usize;
+        d.block_length_size = d.num_blocks + padding;
+        d.max_sym_len = self.u8_at(offset + 7);
+        d.min_sym_len = self.u8_at(offset + 8);
+        offset += 9;
+        d.lowest_sym = offset;
+
+        let lengths = (d.max_sym_len as usize + 1).saturating_sub(d.min_sym_len as usize);
+        d.base64 = vec![0; lengths];
+
+        // Canonical Huffman codes: longer symbols have lower values, so the
+        // padded lowest code of each length decreases with the length
+        for idx in (0..lengths.saturating_sub(1)).rev() {
+            let lowest = self.u16_le(d.lowest_sym + 2 * idx) as u64;
+            let next = self.u16_le(d.lowest_sym + 2 * (idx + 1)) as u64;
+            d.base64[idx] = d.base64[idx + 1].wrapping_add(lowest).wrapping_sub(next) / 2;
+        }
+
+        for (idx, base) in d.base64.iter_mut().enumerate() {
+            let shift = 64 - (idx + d.min_sym_len as usize).min(64);
+            *base = base.checked_shl(shift as u32).unwrap_or(0);
+        }
+
+        offset += lengths * 2;
+        let symbols = self.u16_le(offset) as usize;
+        offset += 2;
+        d.btree = offset;
+        d.symlen = vec![0; symbols];
+
+        let mut visited = vec![false; symbols];
+
+        for sym in 0..symbols {
+            if !visited[sym] {
+                d.symlen[sym] = self.set_symlen(d, sym, &mut visited);
+            }
+        }
+
+        offset + symbols * 3 + (symbols & 1)
+    }
+
+    /// Symbols represent a pair of smaller symbols, this counts the values a
+    /// symbol expands to, minus one
+    fn set_symlen(&self, d: &mut PairsData, sym: usize, visited: &mut [bool]) -> u8 {
+        visited[sym] = true;
+
+        let right = self.right_symbol(d, sym);
+
+        if right == 0xFFF {
+            return 0;
+        }
+
+        let left = self.left_symbol(d, sym);
+
+        if left >= visited.len() || right >= visited.len() {
+            return 0;
+        }
+
+        if !visited[left] {
+            d.symlen[left] = self.set_symlen(d, left, visited);
+        }
+
+        if !visited[right] {
+            d.symlen[right] = self.set_symlen(d, right, visited);
+        }
+
+        d.symlen[left].wrapping_add(d.symlen[right]).wrapping_add(1)
+    }
+
+    fn left_symbol(&self, d: &PairsData, sym: usize) -> usize {
+        let lr = d.btree + 3 * sym;
+        ((self.u8_at(lr + 1) as usize & 0xF) << 8) | self.u8_at(lr) as usize
+    }
+
+    fn right_symbol(&self, d: &PairsData, sym: usize) -> usize {
+        let lr = d.btree + 3 * sym;
+        ((self.u8_at(lr + 2) as usize) << 4) | (self.u8_at(lr + 1) as usize >> 4)
+    }
+
+    fn symlen(d: &PairsData, sym: usize) -> i64 {
+        *d.symlen.get(sym).unwrap_or(&0) as i64
+    }
+
+    /// Finds the value stored at an index by locating its block through the
+    /// sparse index and then decoding Huffman symbols within the block
+    fn decompress_pairs(&self, d: &PairsData, idx: u64) -> i32 {
+        if d.flags & SINGLE_VALUE != 0 {
+            return d.min_sym_len as i32;
+        }
+
+        let span = d.span as u64;
+        let k = (idx / span) as usize;
+
+        let mut block = self.u32_le(d.sparse_index + 6 * k) as usize;
+        let mut offset = self.u16_le(d.sparse_index + 6 * k + 4) as i64;
+        offset += (idx % span) as i64 - (span / 2) as i64;
+
+        let block_length = |block: usize| self.u16_le(d.block_length + 2 * block) as i64;
+
+        while offset < 0 && block > 0 {
+            block -= 1;
+            offset += block_length(block) + 1;
+        }
+
+        while offset > block_length(block) && block < d.block_length_size {
+            offset -= block_length(block) + 1;
+            block += 1;
+        }
+
+        let mut ptr = d.data + block * d.block_size;
+        let mut buf64 = ((self.u32_be(ptr) as u64) << 32) | self.u32_be(ptr + 4) as u64;
+        ptr += 8;
+        let mut buf64_size = 64;
+        let min_sym_len = d.min_sym_len as usize;
+        let mut sym;
+
+        loop {
+            let mut len = 0;
+
+            while len + 1 < d.base64.len() && buf64 < d.base64[len] {
+                len += 1;
+            }
+
+            let shift = 64 - (len + min_sym_len).min(64);
+            sym = buf64
+                .wrapping_sub(d.base64[len])
+                .checked_shr(shift as u32)
+                .unwrap_or(0) as usize;
+            sym = (sym + self.u16_le(d.lowest_sym + 2 * len) as usize) & 0xFFFF;
+
+            if offset < Table::symlen(d, sym) + 1 || sym >= d.symlen.len() {
+                break;
+            }
+
+            offset -= Table::symlen(d, sym) + 1;
+            len += min_sym_len;
+            buf64 = buf64.checked_shl(len as u32).unwrap_or(0);
+            buf64_size -= len as i32;
+
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= (self.u32_be(ptr) as u64) << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        // Expand the symbol into its pair until reaching a single value
+        while Table::symlen(d, sym) > 0 {
+            let left = self.left_symbol(d, sym);
+
+            if offset < Table::symlen(d, left) + 1 {
+                sym = left;
+            } else {
+                offset -= Table::symlen(d, left) + 1;
+                sym = self.right_symbol(d, sym);
+            }
+        }
+
+        self.left_symbol(d, sym) as i32
+    }
+
+    /// Looks up a position given as piece codes per square. `turn` is 0 for
+    /// white to move, `black_stronger` is set when black has the material
+    /// named first. `wdl` is the known result, used to decode DTZ values.
+    pub fn probe(&self, codes: &[u8; 64], turn: usize, black_stronger: bool, wdl: i32) -> Lookup {
+        let material = &self.material;
+
+        // Tables store the named side as white, and symmetric tables only
+        // store white to move, so the board is mirrored otherwise
+        let flip = black_stronger || (material.symmetric && turn == 1);
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let stm = flip as usize ^ turn;
+
+        let mut squares = [0usize; MAX_PIECES];
+        let mut pieces = [0u8; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0;
+        let mut lead_mask = 0u64;
+        let mut file = 0;
+
+        if material.has_pawns {
+            // Pawns of the leading colour come first in every table
+            let lead = self.pairs(0, 0).pieces[0] ^ flip_color;
+
+            for (sq, code) in codes.iter().enumerate() {
+                if *code == lead && size < MAX_PIECES {
+                    squares[size] = sq ^ flip_squares;
+                    lead_mask |= 1 << sq;
+                    size += 1;
+                }
+            }
+
+            lead_pawns = size;
+
+            let max = (0..lead_pawns)
+                .max_by_key(|&idx| (INDICES.map_pawns[squares[idx]], usize::MAX - idx))
+                .unwrap_or(0);
+
+            squares.swap(0, max);
+            file = squares[0] & 7;
+
+            if file > 3 {
+                file = 7 - file;
+            }
+        }
+
+        if self.kind == Kind::Dtz {
+            let flags = self.pairs(0, file).flags;
+
+            // Symmetric pawnless tables hold both sides
+            let both_sides = material.symmetric && !material.has_pawns;
+
+            if (flags & STM) as usize != stm && !both_sides {
+                return Lookup::OtherSide;
+            }
+        }
+
+        for (sq, code) in codes.iter().enumerate() {
+            if *code != 0 && lead_mask & (1 << sq) == 0 && size < MAX_PIECES {
+                squares[size] = sq ^ flip_squares;
+                pieces[size] = code ^ flip_color;
+                size += 1;
+            }
+        }
+
+        let d = self.pairs(stm, file);
+
+        // Reorder the pieces to the sequence used by the table
+        for i in lead_pawns..size.saturating_sub(1) {
+            for j in i + 1..size {
+                if d.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        // Mirror so the leading piece is on files a-d
+        if squares[0] & 7 > 3 {
+            for sq in squares[..size].iter_mut() {
+                *sq ^= 7;
+            }
+        }
+
+        let binomial = &INDICES.binomial;
+        let mut idx;
+
+        if material.has_pawns {
+            idx = INDICES.lead_pawn_idx[lead_pawns][squares[0]];
+
+            let others = 1..lead_pawns.max(1);
+            squares[others.clone()].sort_by_key(|sq| INDICES.map_pawns[*sq]);
+
+            for (i, sq) in squares[others].iter().enumerate() {
+                idx += binomial[i + 1][INDICES.map_pawns[*sq]];
+            }
+        } else {
+            // Mirror so the leading piece is on ranks 1-4
+            if squares[0] >> 3 > 3 {
+                for sq in squares[..size].iter_mut() {
+                    *sq ^= 56;
+                }
+            }
+
+            // The first leading piece off the a1-h8 diagonal must be below it
+            for i in 0..d.group_len[0] {
+                if off_a1h8(squares[i]) == 0 {
+                    continue;
+                }
+
+                if off_a1h8(squares[i]) > 0 {
+                    for sq in squares[i..size].iter_mut() {
+                        *sq = ((*sq >> 3) | (*sq << 3)) & 63;
+                    }
+                }
+
+                break;
+            }
+
+            idx = if material.has_unique_pieces {
+                encode_unique(&squares)
+            } else {
+                INDICES.map_kk[INDICES.map_a1d1d4[squares[0]]][squares[1]]
+            };
+        }
+
+        idx = idx.wrapping_mul(d.group_idx[0]);
+
+        // Remaining groups, each mapped to the squares left free by earlier groups
+        let mut start = d.group_len[0];
+        let mut remaining_pawns = material.has_pawns && material.pawn_count[1] > 0;
+        let mut next = 1;
+
+        while next <= MAX_PIECES && d.group_len[next] != 0 && start + d.group_len[next] <= size {
+            let len = d.group_len[next];
+            squares[start..start + len].sort_unstable();
+
+            let mut n = 0;
+
+            for i in 0..len {
+                let sq = squares[start + i];
+                let adjust = squares[..start].iter().filter(|other| sq > **other).count();
+                let pawn_adjust = if remaining_pawns { 8 } else { 0 };
+                let free = sq.saturating_sub(adjust + pawn_adjust).min(63);
+                n += binomial[(i + 1).min(6)][free];
+            }
+
+            remaining_pawns = false;
+            idx = idx.wrapping_add(n.wrapping_mul(d.group_idx[next]));
+            start += len;
+            next += 1;
+        }
+
+        let value = self.decompress_pairs(d, idx);
+
+        Lookup::Found(match self.kind {
+            Kind::Wdl => value - 2,
+            Kind::Dtz => self.map_score(file, value, wdl),
+        })
+    }
+
+    /// DTZ values are stored remapped by frequency and sometimes in full moves
+    fn map_score(&self, file: usize, mut value: i32, wdl: i32) -> i32 {
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+
+        let d = self.pairs(0, file);
+
+        if d.flags & MAPPED != 0 {
+            let idx = d.map_idx[WDL_MAP[(wdl + 2) as usize]] as usize + value as usize;
+
+            value = if d.flags & WIDE != 0 {
+                self.u16_le(self.map + 2 * idx) as i32
+            } else {
+                self.u8_at(self.map + idx) as i32
+            };
+        }
+
+        if (wdl == 2 && d.flags & WIN_PLIES == 0)
+            || (wdl == -2 && d.flags & LOSS_PLIES == 0)
+            || wdl == 1
+            || wdl == -1
+        {
+            value *= 2;
+        }
+
+        value + 1
+    }
+}
+
+/// Index of three unique leading pieces, the first in the a1-d1-d4 triangle
+fn encode_unique(squares: &[usize; MAX_PIECES]) -> u64 {
+    let [s0, s1, s2] = [squares[0], squares[1], squares[2]];
+    let adjust1 = (s1 > s0) as usize;
+    let adjust2 = (s2 > s0) as usize + (s2 > s1) as usize;
+    let rank = |sq: usize| (sq >> 3) as u64;
+
+    if off_a1h8(s0) != 0 {
+        ((INDICES.map_a1d1d4[s0] * 63 + (s1 - adjust1)) * 62 + s2 - adjust2) as u64
+    } else if off_a1h8(s1) != 0 {
+        (6 * 63 + rank(s0) * 28 + INDICES.map_b1h1h7[s1]) * 62 + (s2 - adjust2) as u64
+    } else if off_a1h8(s2) != 0 {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + rank(s0) * 7 * 28
+            + (rank(s1) - adjust1 as u64) * 28
+            + INDICES.map_b1h1h7[s2]
+    } else {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + 4 * 7 * 28
+            + rank(s0) * 7 * 6
+            + (rank(s1) - adjust1 as u64) * 6
+            + (rank(s2) - adjust2 as u64)
+    }
+}
+
+/// Groups pieces encoded together and computes each group's index factor.
+/// Pawnless tables lead with three unique pieces or the two kings.
+fn set_groups(material: &Material, d: &mut PairsData, order: [usize; 2], file: usize) {
+    let mut n = 0;
+    let mut first_len: i32 = if material.has_pawns {
+        0
+    } else if material.has_unique_pieces {
+        3
+    } else {
+        2
+    };
+
+    d.group_len[0] = 1;
+
+    for i in 1..material.piece_count {
+        first_len -= 1;
+
+        if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+            d.group_len[n] += 1;
+        } else {
+            n += 1;
+            d.group_len[n] = 1;
+        }
+    }
+
+    n += 1;
+    d.group_len[n] = 0;
+
+    // Groups are multiplied in a per-table order: the leading group is at
+    // order[0] and the remaining pawns, if any, at order[1]
+    let both_pawns = material.has_pawns && material.pawn_count[1] > 0;
+    let mut next = if both_pawns { 2 } else { 1 };
+    let mut free = 64 - d.group_len[0] - if both_pawns { d.group_len[1] } else { 0 };
+    let mut idx: u64 = 1;
+    let mut k = 0;
+
+    while next < n || k == order[0] || k == order[1] {
+        if k == order[0] {
+            d.group_idx[0] = idx;
+            idx *= if material.has_pawns {
+                INDICES.lead_pawns_size[d.group_len[0].min(5)][file]
+            } else if material.has_unique_pieces {
+                31332
+            } else {
+                462
+            };
+        } else if k == order[1] {
+            d.group_idx[1] = idx;
+            idx *= INDICES.binomial[d.group_len[1].min(6)][48 - d.group_len[0]];
+        } else {
+            d.group_idx[next] = idx;
+            idx *= INDICES.binomial[d.group_len[next].min(6)][free];
+            free -= d.group_len[next];
+            next += 1;
+        }
+
+        k += 1;
+
+        if k > MAX_PIECES + 1 {
+            break;
+        }
+    }
+
+    d.group_idx[n] = idx;
+}
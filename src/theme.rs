@@ -0,0 +1,292 @@
+use crate::chess::Piece;
+use crate::config::Config;
+use ansi_term::Colour;
+use std::sync::RwLock;
+
+pub const PRESETS: [&str; 5] = ["grey", "green", "blue", "high-contrast", "colour-blind"];
+pub const GLYPH_SETS: [&str; 3] = ["filled", "outlined", "letters"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Glyphs {
+    /// Solid symbols for both sides, told apart by colour
+    Filled,
+    /// Outlined symbols for white and solid ones for black
+    Outlined,
+    /// `KQRBNP` for white and `kqrbnp` for black
+    Letters,
+}
+
+impl Glyphs {
+    pub fn from(name: &str) -> Option<Glyphs> {
+        match name.to_lowercase().as_str() {
+            "filled" => Some(Glyphs::Filled),
+            "outlined" | "outline" => Some(Glyphs::Outlined),
+            "letters" | "letter" => Some(Glyphs::Letters),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Glyphs::Filled => "filled",
+            Glyphs::Outlined => "outlined",
+            Glyphs::Letters => "letters",
+        }
+    }
+
+    pub fn glyph(&self, piece: Piece, player: i32) -> char {
+        match self {
+            Glyphs::Letters if player == 2 => piece.letter().to_ascii_lowercase(),
+            Glyphs::Letters => piece.letter(),
+            Glyphs::Outlined if player == 1 => match piece {
+                Piece::None => ' ',
+                Piece::Bishop => '♗',
+                Piece::King => '♔',
+                Piece::Knight => '♘',
+                Piece::Pawn => '♙',
+                Piece::Queen => '♕',
+                Piece::Rook => '♖',
+            },
+            _ => match piece {
+                Piece::None => ' ',
+                Piece::Bishop => '♝',
+                Piece::King => '♚',
+                Piece::Knight => '♞',
+                Piece::Pawn => '♟',
+                Piece::Queen => '♛',
+                Piece::Rook => '♜',
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub light: Colour,
+    pub dark: Colour,
+    /// Selected piece and its targets
+    pub selected: Colour,
+    pub last_move: Colour,
+    pub white_pieces: Colour,
+    pub black_pieces: Colour,
+    pub glyphs: Glyphs,
+}
+
+const GREY: Theme = Theme {
+    name: "grey",
+    light: Colour::RGB(160, 160, 160),
+    dark: Colour::RGB(96, 96, 96),
+    selected: Colour::RGB(64, 128, 160),
+    last_move: Colour::RGB(96, 128, 96),
+    white_pieces: Colour::RGB(255, 255, 255),
+    black_pieces: Colour::RGB(0, 0, 0),
+    glyphs: Glyphs::Filled,
+};
+
+static CURRENT: RwLock<Theme> = RwLock::new(GREY);
+static OVERRIDES: RwLock<Overrides> = RwLock::new(Overrides::NONE);
+
+/// Colours chosen in the config, which stay whatever preset is picked
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Overrides {
+    pub light: Option<Colour>,
+    pub dark: Option<Colour>,
+    pub selected: Option<Colour>,
+    pub last_move: Option<Colour>,
+    pub white_pieces: Option<Colour>,
+    pub black_pieces: Option<Colour>,
+}
+
+impl Overrides {
+    const NONE: Overrides = Overrides {
+        light: None,
+        dark: None,
+        selected: None,
+        last_move: None,
+        white_pieces: None,
+        black_pieces: None,
+    };
+
+    /// The `light`, `dark`, `selected`, `last_move`, `white_pieces` and
+    /// `black_pieces` settings
+    pub fn from_config(config: &Config) -> Result<Overrides, String> {
+        let mut overrides = Overrides::NONE;
+
+        let colours = [
+            ("light", &mut overrides.light),
+            ("dark", &mut overrides.dark),
+            ("selected", &mut overrides.selected),
+            ("last_move", &mut overrides.last_move),
+            ("white_pieces", &mut overrides.white_pieces),
+            ("black_pieces", &mut overrides.black_pieces),
+        ];
+
+        for (key, colour) in colours {
+            if let Some(value) = config.get(key) {
+                let parsed = parse_colour(value);
+                *colour = Some(parsed.ok_or(format!("Invalid colour '{}' for {}", value, key))?);
+            }
+        }
+
+        Ok(overrides)
+    }
+
+    fn apply(&self, theme: Theme) -> Theme {
+        Theme {
+            light: self.light.unwrap_or(theme.light),
+            dark: self.dark.unwrap_or(theme.dark),
+            selected: self.selected.unwrap_or(theme.selected),
+            last_move: self.last_move.unwrap_or(theme.last_move),
+            white_pieces: self.white_pieces.unwrap_or(theme.white_pieces),
+            black_pieces: self.black_pieces.unwrap_or(theme.black_pieces),
+            ..theme
+        }
+    }
+}
+
+impl Theme {
+    /// One of `PRESETS`, with filled glyphs
+    pub fn preset(name: &str) -> Option<Theme> {
+        let theme = match name.to_lowercase().as_str() {
+            "grey" | "gray" => GREY,
+            "green" => Theme {
+                name: "green",
+                light: Colour::RGB(235, 235, 208),
+                dark: Colour::RGB(119, 148, 85),
+                selected: Colour::RGB(80, 140, 190),
+                last_move: Colour::RGB(186, 202, 68),
+                ..GREY
+            },
+            "blue" => Theme {
+                name: "blue",
+                light: Colour::RGB(200, 214, 226),
+                dark: Colour::RGB(110, 140, 170),
+                selected: Colour::RGB(220, 170, 60),
+                last_move: Colour::RGB(150, 190, 120),
+                ..GREY
+            },
+            // Pieces in colours that stand out on both black and white
+            "high-contrast" | "contrast" => Theme {
+                name: "high-contrast",
+                light: Colour::RGB(255, 255, 255),
+                dark: Colour::RGB(0, 0, 0),
+                selected: Colour::RGB(255, 0, 255),
+                last_move: Colour::RGB(0, 200, 200),
+                white_pieces: Colour::RGB(255, 140, 0),
+                black_pieces: Colour::RGB(0, 90, 255),
+                ..GREY
+            },
+            // Blue and orange highlights from the Okabe-Ito palette, which
+            // stay distinct with red-green colour blindness
+            "colour-blind" | "color-blind" | "colourblind" | "colorblind" => Theme {
+                name: "colour-blind",
+                light: Colour::RGB(190, 190, 190),
+                dark: Colour::RGB(120, 120, 120),
+                selected: Colour::RGB(0, 114, 178),
+                last_move: Colour::RGB(230, 159, 0),
+                ..GREY
+            },
+            _ => return None,
+        };
+
+        Some(theme)
+    }
+
+    /// The preset and glyphs named by the `theme` and `pieces` settings,
+    /// see `Overrides` for the colours
+    pub fn from_config(config: &Config) -> Result<Theme, String> {
+        let mut theme = match config.get("theme") {
+            Some(name) => Theme::preset(name).ok_or(format!("Unknown theme '{}'", name))?,
+            None => GREY,
+        };
+
+        if let Some(name) = config.get("pieces") {
+            theme.glyphs = Glyphs::from(name).ok_or(format!("Unknown piece set '{}'", name))?;
+        }
+
+        Ok(theme)
+    }
+}
+
+/// `#rrggbb` or `r,g,b`
+fn parse_colour(text: &str) -> Option<Colour> {
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+
+        let channel = |idx: usize| u8::from_str_radix(hex.get(idx..idx + 2)?, 16).ok();
+        return Some(Colour::RGB(channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    let channels: Vec<u8> = text
+        .split(',')
+        .map(|part| part.trim().parse::<u8>().ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    match channels.as_slice() {
+        [r, g, b] => Some(Colour::RGB(*r, *g, *b)),
+        _ => None,
+    }
+}
+
+pub fn current() -> Theme {
+    *CURRENT.read().unwrap()
+}
+
+/// Switches to a theme with the overrides applied over its colours
+pub fn set(theme: Theme) {
+    *CURRENT.write().unwrap() = OVERRIDES.read().unwrap().apply(theme);
+}
+
+/// Keeps colours over those of every theme switched to from now on
+pub fn set_overrides(overrides: Overrides) {
+    *OVERRIDES.write().unwrap() = overrides;
+    set(current());
+}
+
+/// Red, green and blue of a colour, approximating the named terminal colours
+pub fn rgb(colour: Colour) -> (u8, u8, u8) {
+    match colour {
+        Colour::RGB(r, g, b) => (r, g, b),
+        Colour::Black => (0, 0, 0),
+        Colour::Red => (205, 0, 0),
+        Colour::Green => (0, 205, 0),
+        Colour::Yellow => (205, 205, 0),
+        Colour::Blue => (0, 0, 238),
+        Colour::Purple => (205, 0, 205),
+        Colour::Cyan => (0, 205, 205),
+        Colour::White => (229, 229, 229),
+        Colour::Fixed(_) => (128, 128, 128),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_stay_after_switching_theme() {
+        let light = Colour::RGB(1, 2, 3);
+
+        set_overrides(Overrides {
+            light: Some(light),
+            ..Overrides::default()
+        });
+
+        for name in PRESETS.iter() {
+            let preset = Theme::preset(name).unwrap();
+            set(preset);
+
+            let theme = current();
+            assert_eq!(theme.name, preset.name);
+            assert_eq!(theme.light, light);
+            assert_eq!(theme.dark, preset.dark);
+        }
+
+        set_overrides(Overrides::default());
+        set(GREY);
+        assert_eq!(current(), GREY);
+    }
+}
@@ -0,0 +1,399 @@
+mod pairing;
+
+use self::pairing::{History, Pairing};
+use crate::arena::{play_game, Adjudication, EngineSpec};
+use crate::chess::{Board, Variant};
+use crate::engine::{Clock, Limits, MAX_LEVEL};
+use crate::net::{ConsoleInterface, Interface, TcpInterface};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    RoundRobin,
+    Swiss,
+}
+
+impl Format {
+    pub fn from(name: &str) -> Option<Format> {
+        match name.to_lowercase().as_str() {
+            "roundrobin" | "round-robin" | "rr" => Some(Format::RoundRobin),
+            "swiss" => Some(Format::Swiss),
+            _ => None,
+        }
+    }
+
+    /// Swiss byes score a win, round robin byes are just a rest
+    fn bye_points(&self) -> f64 {
+        match self {
+            Format::RoundRobin => 0.0,
+            Format::Swiss => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Kind {
+    Engine(EngineSpec),
+    /// Enters moves at this terminal
+    Human,
+    /// Connects to this port with `rust-chess join <host>:<port>` for each game
+    Remote(u16),
+}
+
+#[derive(Debug, Clone)]
+pub struct Participant {
+    pub name: String,
+    pub kind: Kind,
+}
+
+impl Participant {
+    /// `[name=]kind`, where kind is `human`, `tcp:<port>`, `internal`,
+    /// `internal:<level>` or an external UCI engine command
+    pub fn from(spec: &str) -> Option<Participant> {
+        let (name, kind) = match spec.split_once('=') {
+            Some((name, kind)) if !name.contains(|c: char| c.is_whitespace() || c == '/') => {
+                (Some(name.to_string()), kind)
+            }
+            _ => (None, spec),
+        };
+
+        let (kind, default_name) = if kind == "human" {
+            (Kind::Human, String::from("Human"))
+        } else if let Some(port) = kind.strip_prefix("tcp:") {
+            let port = port.parse::<u16>().ok().filter(|port| *port != 0)?;
+            (Kind::Remote(port), format!("Remote {}", port))
+        } else {
+            let engine = EngineSpec::from(kind)?;
+
+            let name = match &engine {
+                EngineSpec::Internal(MAX_LEVEL) => String::from("rust-chess"),
+                EngineSpec::Internal(level) => format!("rust-chess level {}", level),
+                EngineSpec::External(command) => {
+                    let program = command.split_whitespace().next().unwrap_or(command);
+                    let stem = Path::new(program).file_stem().and_then(|s| s.to_str());
+                    stem.unwrap_or(program).to_string()
+                }
+            };
+
+            (Kind::Engine(engine), name)
+        };
+
+        Some(Participant {
+            name: name.unwrap_or(default_name),
+            kind,
+        })
+    }
+}
+
+pub struct TournamentSettings {
+    pub participants: Vec<Participant>,
+    pub format: Format,
+    /// Swiss rounds, or round robin cycles with colours reversed each cycle
+    pub rounds: usize,
+    pub variant: Variant,
+    pub clock: Option<Clock>,
+    /// Engine limits per move when there is no clock
+    pub limits: Limits,
+    pub adjudication: Adjudication,
+    pub pgn_out: Option<String>,
+    pub threads: usize,
+}
+
+/// A finished game, or a bye when there is no black player
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    white: usize,
+    black: Option<usize>,
+    white_points: f64,
+}
+
+struct Standing {
+    player: usize,
+    points: f64,
+    games: usize,
+    buchholz: f64,
+    sonneborn_berger: f64,
+}
+
+/// Plays every round in order and prints the standings after each one
+pub fn run_tournament(settings: &mut TournamentSettings) {
+    make_names_unique(&mut settings.participants);
+
+    let count = settings.participants.len();
+    let mut records: Vec<Record> = Vec::new();
+
+    let mut pgn_file = match &settings.pgn_out {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                println!("Could not open '{}': {}", path, err);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let schedule = match settings.format {
+        Format::RoundRobin => {
+            let cycle = pairing::round_robin(count);
+            let mut rounds: Vec<Vec<Pairing>> = Vec::new();
+
+            // Later cycles repeat the first with colours reversed
+            for idx in 0..settings.rounds.max(1) {
+                for round in cycle.iter() {
+                    let round = round.iter().map(|pairing| match pairing.black {
+                        Some(black) if idx % 2 == 1 => Pairing {
+                            white: black,
+                            black: Some(pairing.white),
+                        },
+                        _ => *pairing,
+                    });
+
+                    rounds.push(round.collect());
+                }
+            }
+
+            Some(rounds)
+        }
+        Format::Swiss => None,
+    };
+
+    let rounds = match &schedule {
+        Some(rounds) => rounds.len(),
+        None => settings.rounds,
+    };
+
+    for round in 0..rounds {
+        let pairings = match &schedule {
+            Some(rounds) => rounds[round].clone(),
+            None => pairing::swiss(&history(count, &records)),
+        };
+
+        println!();
+        println!("Round {}", round + 1);
+
+        for (board, pairing) in pairings.iter().enumerate() {
+            let white = &settings.participants[pairing.white];
+
+            let black = match pairing.black {
+                Some(black) => &settings.participants[black],
+                None => {
+                    println!("{} has a bye", white.name);
+
+                    records.push(Record {
+                        white: pairing.white,
+                        black: None,
+                        white_points: settings.format.bye_points(),
+                    });
+
+                    continue;
+                }
+            };
+
+            println!("{} - {}", white.name, black.name);
+
+            let result = match play_pairing(settings, white, black) {
+                Ok(result) => result,
+                Err(err) => {
+                    println!("Could not start the game: {}", err);
+                    return;
+                }
+            };
+
+            let mut game = result.to_game();
+            game.set_tag("Event", "Tournament");
+            game.set_tag("Round", &format!("{}.{}", round + 1, board + 1));
+            game.set_tag("White", &white.name);
+            game.set_tag("Black", &black.name);
+
+            println!(
+                "{} - {} {} ({})",
+                white.name, black.name, game.result, result.reason
+            );
+
+            if let Some(file) = pgn_file.as_mut() {
+                if let Err(err) = writeln!(file, "{}", game.to_pgn()) {
+                    println!("Could not write PGN: {}", err);
+                }
+            }
+
+            records.push(Record {
+                white: pairing.white,
+                black: pairing.black,
+                white_points: match result.winner {
+                    1 => 1.0,
+                    2 => 0.0,
+                    _ => 0.5,
+                },
+            });
+        }
+
+        print_standings(&settings.participants, &records);
+    }
+}
+
+/// Starts both sides and plays one game
+fn play_pairing(
+    settings: &TournamentSettings,
+    white: &Participant,
+    black: &Participant,
+) -> io::Result<crate::arena::GameResult> {
+    let mut white_interface = create_interface(settings, white, 1)?;
+    let mut black_interface = create_interface(settings, black, 2)?;
+
+    Ok(play_game(
+        white_interface.as_mut(),
+        black_interface.as_mut(),
+        &Board::new_variant(settings.variant),
+        settings.clock,
+        &settings.adjudication,
+    ))
+}
+
+fn create_interface(
+    settings: &TournamentSettings,
+    participant: &Participant,
+    player: i32,
+) -> io::Result<Box<dyn Interface>> {
+    match &participant.kind {
+        Kind::Engine(engine) => {
+            let (interface, _) = engine.create(
+                settings.limits,
+                settings.threads,
+                &settings.adjudication.tablebase,
+            )?;
+            Ok(interface)
+        }
+        Kind::Human => Ok(Box::new(ConsoleInterface::new(&participant.name))),
+        Kind::Remote(port) => {
+            println!("Waiting for {} on port {}...", participant.name, port);
+
+            let mut server = TcpInterface::server(*port)?;
+            server.send_player(player);
+            server.send_variant(settings.variant);
+            Ok(Box::new(server))
+        }
+    }
+}
+
+/// Numbers repeated names so standings and PGN tags can tell them apart
+fn make_names_unique(participants: &mut [Participant]) {
+    for idx in 1..participants.len() {
+        let name = participants[idx].name.clone();
+        let earlier = participants[..idx]
+            .iter()
+            .filter(|other| other.name == name || other.name.starts_with(&format!("{} (", name)))
+            .count();
+
+        if earlier > 0 {
+            participants[idx].name = format!("{} ({})", name, earlier + 1);
+        }
+    }
+}
+
+fn history(count: usize, records: &[Record]) -> Vec<History> {
+    let mut history = vec![History::default(); count];
+
+    for record in records.iter() {
+        let black = match record.black {
+            Some(black) => black,
+            None => {
+                history[record.white].points += record.white_points;
+                history[record.white].had_bye = true;
+                continue;
+            }
+        };
+
+        let white = &mut history[record.white];
+        white.points += record.white_points;
+        white.opponents.push(black);
+        white.colour_balance += 1;
+        white.last_colour = Some(1);
+
+        let black = &mut history[black];
+        black.points += 1.0 - record.white_points;
+        black.opponents.push(record.white);
+        black.colour_balance -= 1;
+        black.last_colour = Some(2);
+    }
+
+    history
+}
+
+/// Points with Buchholz, the sum of the opponents' points, and
+/// Sonneborn-Berger, the points of beaten opponents plus half of those of
+/// drawn opponents, as tiebreaks
+fn standings(count: usize, records: &[Record]) -> Vec<Standing> {
+    let history = history(count, records);
+
+    let mut standings: Vec<Standing> = (0..count)
+        .map(|player| Standing {
+            player,
+            points: history[player].points,
+            games: history[player].opponents.len(),
+            buchholz: 0.0,
+            sonneborn_berger: 0.0,
+        })
+        .collect();
+
+    for record in records.iter() {
+        let black = match record.black {
+            Some(black) => black,
+            None => continue,
+        };
+
+        let sides = [
+            (record.white, black, record.white_points),
+            (black, record.white, 1.0 - record.white_points),
+        ];
+
+        for (player, opponent, points) in sides.iter() {
+            let opponent_points = history[*opponent].points;
+            standings[*player].buchholz += opponent_points;
+            standings[*player].sonneborn_berger += points * opponent_points;
+        }
+    }
+
+    standings.sort_by(|a, b| {
+        let key = |s: &Standing| (s.points, s.buchholz, s.sonneborn_berger);
+        key(b).partial_cmp(&key(a)).unwrap()
+    });
+
+    standings
+}
+
+fn print_standings(participants: &[Participant], records: &[Record]) {
+    let width = participants
+        .iter()
+        .map(|participant| participant.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(6);
+
+    println!();
+    println!(
+        "{:>3}  {:<width$}  {:>6}  {:>5}  {:>8}  {:>6}",
+        "#",
+        "Player",
+        "Points",
+        "Games",
+        "Buchholz",
+        "SB",
+        width = width
+    );
+
+    for (rank, standing) in standings(participants.len(), records).iter().enumerate() {
+        println!(
+            "{:>3}  {:<width$}  {:>6.1}  {:>5}  {:>8.1}  {:>6.2}",
+            rank + 1,
+            participants[standing.player].name,
+            standing.points,
+            standing.games,
+            standing.buchholz,
+            standing.sonneborn_berger,
+            width = width
+        );
+    }
+}
@@ -0,0 +1,159 @@
+/// A game to play in a round, where a missing black player is a bye
+#[derive(Debug, Clone, Copy)]
+pub struct Pairing {
+    pub white: usize,
+    pub black: Option<usize>,
+}
+
+/// What pairing a player needs to know about the rounds so far
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    pub points: f64,
+    pub opponents: Vec<usize>,
+    /// Games with white minus games with black
+    pub colour_balance: i32,
+    pub last_colour: Option<i32>,
+    pub had_bye: bool,
+}
+
+/// Every round of a round robin using the circle method, where one player
+/// stays in place and the others rotate around it
+pub fn round_robin(players: usize) -> Vec<Vec<Pairing>> {
+    let mut ids: Vec<Option<usize>> = (0..players).map(Some).collect();
+
+    if ids.len() % 2 == 1 {
+        ids.push(None);
+    }
+
+    let count = ids.len();
+    let mut rounds = Vec::new();
+
+    for round in 0..count.saturating_sub(1) {
+        let mut pairings = Vec::new();
+
+        for idx in 0..count / 2 {
+            let (a, b) = (ids[idx], ids[count - 1 - idx]);
+
+            // Alternate colours by round for the fixed player and by board
+            // for the rest so everyone ends up close to even
+            let swap = if idx == 0 {
+                round % 2 == 1
+            } else {
+                idx % 2 == 1
+            };
+
+            let (white, black) = if swap { (b, a) } else { (a, b) };
+
+            let pairing = match (white, black) {
+                (Some(white), black) => Pairing { white, black },
+                (None, Some(black)) => Pairing {
+                    white: black,
+                    black: None,
+                },
+                (None, None) => continue,
+            };
+
+            pairings.push(pairing);
+        }
+
+        rounds.push(pairings);
+        ids[1..].rotate_right(1);
+    }
+
+    rounds
+}
+
+/// Pairs players with equal or close scores who have not met yet, giving
+/// the bye to the lowest ranked player who has not had one
+pub fn swiss(history: &[History]) -> Vec<Pairing> {
+    let mut order: Vec<usize> = (0..history.len()).collect();
+    order.sort_by(|a, b| history[*b].points.partial_cmp(&history[*a].points).unwrap());
+
+    let mut bye = None;
+
+    if order.len() % 2 == 1 {
+        let idx = order
+            .iter()
+            .rposition(|player| !history[*player].had_bye)
+            .unwrap_or(order.len() - 1);
+
+        bye = Some(order.remove(idx));
+    }
+
+    // Rematches only when there is no other way to pair everyone
+    let pairs = pair_players(&order, history, false)
+        .or_else(|| pair_players(&order, history, true))
+        .unwrap_or_default();
+
+    let mut pairings: Vec<Pairing> = pairs
+        .into_iter()
+        .map(|(a, b)| {
+            let (white, black) = colours(a, b, history);
+
+            Pairing {
+                white,
+                black: Some(black),
+            }
+        })
+        .collect();
+
+    if let Some(player) = bye {
+        pairings.push(Pairing {
+            white: player,
+            black: None,
+        });
+    }
+
+    pairings
+}
+
+/// Pairs the highest ranked player with the next one possible, backtracking
+/// when the rest cannot be paired
+fn pair_players(
+    players: &[usize],
+    history: &[History],
+    rematches: bool,
+) -> Option<Vec<(usize, usize)>> {
+    let first = match players.first() {
+        Some(first) => *first,
+        None => return Some(Vec::new()),
+    };
+
+    for &other in players.iter().skip(1) {
+        if !rematches && history[first].opponents.contains(&other) {
+            continue;
+        }
+
+        let rest: Vec<usize> = players[1..]
+            .iter()
+            .copied()
+            .filter(|player| *player != other)
+            .collect();
+
+        if let Some(mut pairs) = pair_players(&rest, history, rematches) {
+            pairs.insert(0, (first, other));
+            return Some(pairs);
+        }
+    }
+
+    None
+}
+
+/// White goes to whoever has had black more often, then to whoever had
+/// black last, then to the higher ranked player `a`
+fn colours(a: usize, b: usize, history: &[History]) -> (usize, usize) {
+    let (first, second) = (&history[a], &history[b]);
+
+    if first.colour_balance != second.colour_balance {
+        return if first.colour_balance < second.colour_balance {
+            (a, b)
+        } else {
+            (b, a)
+        };
+    }
+
+    match (first.last_colour, second.last_colour) {
+        (Some(1), Some(2)) | (Some(1), None) => (b, a),
+        _ => (a, b),
+    }
+}
@@ -0,0 +1,584 @@
+use crate::chess::{Board, Piece, Position, Square};
+use crate::net::{DummyInterface, Interface, Interrupt};
+use crate::{
+    autosave, clock, do_move, eco, handle_command, player_color, promote, prompt, render, Context,
+};
+use ansi_term::{ANSIString, Colour};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEventKind,
+};
+use crossterm::terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue, style::Print};
+use std::io::{self, stdout, Stdout, Write};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const BOARD_LEFT: u16 = 2;
+const BOARD_TOP: u16 = 1;
+const PANEL_LEFT: u16 = 28;
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 22;
+/// Explorer moves listed under the board
+const EXPLORER_ROWS: usize = 5;
+/// How often the clocks are redrawn while waiting for input
+const TICK: Duration = Duration::from_millis(250);
+
+/// Raw mode on the alternate screen with mouse reporting, restored when
+/// dropped so a panic does not leave the terminal unusable
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            stdout(),
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            cursor::Hide
+        )?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(
+            stdout(),
+            cursor::Show,
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// The opponent's interface thinking or listening on another thread
+struct Waiting {
+    /// Hands the interface back with the board and whether a move was made
+    handle: JoinHandle<(Box<dyn Interface>, Board, bool)>,
+    interrupt: Option<Interrupt>,
+}
+
+struct Tui {
+    input: String,
+    /// First move list row shown, or None to follow the latest move
+    scroll: Option<usize>,
+    /// Set when the game ends without an outcome on the board
+    finished: bool,
+    waiting: Option<Waiting>,
+}
+
+impl Tui {
+    fn new() -> Tui {
+        Tui {
+            input: String::new(),
+            scroll: None,
+            finished: false,
+            waiting: None,
+        }
+    }
+}
+
+/// Plays the game in a full-screen view that is redrawn in place. Pieces
+/// are moved by clicking them and then a highlighted target, or by typing
+/// moves and commands as in the line mode.
+pub fn run(ctx: &mut Context) -> io::Result<()> {
+    let _screen = Screen::enter()?;
+    let mut out = stdout();
+    let mut tui = Tui::new();
+
+    loop {
+        ctx.clock.update(ctx.board.turn);
+        autosave(ctx);
+
+        if ctx.board.get_outcome().is_some() {
+            ctx.clock.stop();
+            tui.finished = true;
+        } else if !tui.finished {
+            if let Some(player) = ctx.clock.check_flag() {
+                let color = player_color(player);
+                ctx.message = Colour::Blue.paint(format!("{} lost on time", color));
+                tui.finished = true;
+            }
+        }
+
+        if tui
+            .waiting
+            .as_ref()
+            .is_some_and(|waiting| waiting.handle.is_finished())
+        {
+            finish_wait(ctx, &mut tui);
+            continue;
+        }
+
+        draw(&mut out, ctx, &tui)?;
+
+        let local = ctx.interface.is_local() || ctx.player == ctx.board.turn;
+
+        if !tui.finished && !local && tui.waiting.is_none() {
+            tui.waiting = Some(start_wait(ctx));
+        }
+
+        if !event::poll(TICK)? {
+            continue;
+        }
+
+        let event = event::read()?;
+
+        // Messages stay until the player presses a key or clicks
+        match &event {
+            Event::Key(_) => ctx.message = ANSIString::from(""),
+            Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Down(_)) => {
+                ctx.message = ANSIString::from("")
+            }
+            _ => {}
+        }
+
+        match event {
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                let playing = !tui.finished && handle_key(ctx, &mut tui, key);
+
+                if !playing {
+                    return Ok(());
+                }
+            }
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) if tui.waiting.is_none() => {
+                    handle_click(ctx, mouse.column, mouse.row)
+                }
+                MouseEventKind::ScrollUp => scroll(ctx, &mut tui, -1),
+                MouseEventKind::ScrollDown => scroll(ctx, &mut tui, 1),
+                _ => {}
+            },
+            // Everything is laid out from the size on every redraw
+            Event::Resize(_, _) => {}
+            _ => {}
+        }
+    }
+}
+
+/// Lets the opponent move on another thread, so the clocks keep ticking
+/// and the player can still quit while it thinks or the network is quiet
+fn start_wait(ctx: &mut Context) -> Waiting {
+    if let Some(clock) = ctx.clock.remaining() {
+        ctx.interface.set_clock(clock);
+    }
+
+    let mut interface = std::mem::replace(&mut ctx.interface, Box::new(DummyInterface {}));
+    let mut board = ctx.board.clone_with_history();
+    let interrupt = interface.interrupter();
+
+    let handle = thread::spawn(move || {
+        let moved = interface.wait(&mut board);
+        (interface, board, moved)
+    });
+
+    Waiting { handle, interrupt }
+}
+
+/// Takes back the interface and the board with the opponent's move
+fn finish_wait(ctx: &mut Context, tui: &mut Tui) {
+    let waiting = match tui.waiting.take() {
+        Some(waiting) => waiting,
+        None => return,
+    };
+
+    let moved = match waiting.handle.join() {
+        Ok((interface, board, moved)) => {
+            ctx.interface = interface;
+            ctx.board = board;
+            moved
+        }
+        Err(_) => false,
+    };
+
+    if !moved {
+        ctx.message = Colour::Red.paint("Player disconnected");
+        ctx.clock.stop();
+        tui.finished = true;
+    } else if ctx.interface.has_resigned() {
+        let color = player_color(ctx.board.turn);
+        ctx.message = Colour::Blue.paint(format!("{} resigns", color));
+        ctx.clock.stop();
+        tui.finished = true;
+    }
+}
+
+/// Gives up the game, first taking the opponent's interface back from a
+/// wait so it hears of it, returning false once the game is left
+fn quit(ctx: &mut Context, tui: &mut Tui, command: &str) -> bool {
+    if let Some(waiting) = tui.waiting.take() {
+        let interrupt = match &waiting.interrupt {
+            Some(interrupt) => interrupt,
+            None => {
+                let color = player_color(ctx.board.turn);
+                ctx.message = Colour::Red.paint(format!("Waiting for {} to move first", color));
+                tui.waiting = Some(waiting);
+                return true;
+            }
+        };
+
+        interrupt();
+
+        // The move it may still have made is dropped with its board
+        if let Ok((interface, _, _)) = waiting.handle.join() {
+            ctx.interface = interface;
+        }
+    }
+
+    handle_command(ctx, command)
+}
+
+/// Edits and submits the command line, returning false to quit
+fn handle_key(ctx: &mut Context, tui: &mut Tui, key: KeyEvent) -> bool {
+    if ctx.board.get_promoting().is_some() {
+        let piece = match key.code {
+            KeyCode::Char('q') => Piece::Queen,
+            KeyCode::Char('r') => Piece::Rook,
+            KeyCode::Char('b') => Piece::Bishop,
+            KeyCode::Char('n') | KeyCode::Char('k') => Piece::Knight,
+            KeyCode::Char('K') => Piece::King,
+            _ => return true,
+        };
+
+        if let Err(err) = promote(ctx, piece) {
+            ctx.message = Colour::Red.paint(err);
+        }
+
+        return true;
+    }
+
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            return quit(ctx, tui, "quit");
+        }
+        KeyCode::Char(c) => tui.input.push(c),
+        KeyCode::Backspace => {
+            tui.input.pop();
+        }
+        KeyCode::Esc => {
+            tui.input.clear();
+            ctx.board.select(None);
+        }
+        KeyCode::Up => scroll(ctx, tui, -1),
+        KeyCode::Down => scroll(ctx, tui, 1),
+        KeyCode::PageUp => scroll(ctx, tui, -10),
+        KeyCode::PageDown => scroll(ctx, tui, 10),
+        KeyCode::End => tui.scroll = None,
+        KeyCode::Enter => {
+            let line = std::mem::take(&mut tui.input);
+
+            // Analysis prints until a key is pressed, which needs line mode,
+            // and the message line has room for one line only
+            match line.trim() {
+                "q" | "quit" | "exit" | "resign" => return quit(ctx, tui, line.trim()),
+                "flip" => return handle_command(ctx, "flip"),
+                _ if tui.waiting.is_some() => {
+                    let color = player_color(ctx.board.turn);
+                    ctx.message =
+                        Colour::Red.paint(format!("Waiting for {}, only quit works now", color))
+                }
+                "analyze" => {
+                    ctx.message = Colour::Red.paint("Analysis is only available without --tui")
+                }
+                "help" => ctx.message = Colour::Green.paint(prompt::command_names()),
+                "pgn" => ctx.message = Colour::Red.paint("Use save <file> to see the PGN"),
+                command => return handle_command(ctx, command),
+            }
+        }
+        _ => {}
+    }
+
+    true
+}
+
+/// Selects the clicked piece, or moves the selected piece if a highlighted
+/// target was clicked
+fn handle_click(ctx: &mut Context, column: u16, row: u16) {
+    let pos = match square_at(column, row, ctx.reversed()) {
+        Some(pos) => pos,
+        None => return,
+    };
+
+    if ctx.board.cur_pos.is_some() {
+        if let Some(mv) = ctx.board.cur_moves.iter().find(|mv| mv.to == pos).copied() {
+            ctx.board.select(None);
+            do_move(ctx, Some(mv));
+            return;
+        }
+    }
+
+    let name = pos.to_string();
+
+    if !ctx.board.select(Some(&name.as_str())) {
+        ctx.board.select(None);
+    }
+}
+
+fn square_at(column: u16, row: u16, reverse: bool) -> Option<Position> {
+    let left = BOARD_LEFT + 2;
+    let top = BOARD_TOP + 1;
+
+    if column < left || column >= left + 16 || row < top || row >= top + 8 {
+        return None;
+    }
+
+    let x = ((column - left) / 2) as i32;
+    let yi = (row - top) as i32;
+    let y = if reverse { yi } else { 7 - yi };
+
+    Some(Position::new(x, y))
+}
+
+fn scroll(ctx: &Context, tui: &mut Tui, delta: i32) {
+    let rows = move_rows(&ctx.board).len();
+    let visible = move_list_height();
+    let last = rows.saturating_sub(visible);
+    let current = tui.scroll.unwrap_or(last) as i32;
+    let next = (current + delta).clamp(0, last as i32) as usize;
+
+    tui.scroll = if next >= last { None } else { Some(next) };
+}
+
+fn move_list_height() -> usize {
+    let (_, height) = terminal::size().unwrap_or((80, 24));
+    height.saturating_sub(15) as usize
+}
+
+/// Moves in SAN, one line per full move
+fn move_rows(board: &Board) -> Vec<String> {
+    let mut replay = board.start_board();
+    let mut rows: Vec<String> = Vec::new();
+
+    for mv in board.game_moves().iter() {
+        let san = replay.to_san(mv);
+
+        if replay.turn == 1 {
+            rows.push(format!("{:>3}. {:<8}", replay.fullmove_number, san));
+        } else if let Some(row) = rows.last_mut() {
+            row.push_str(&san);
+        } else {
+            rows.push(format!(
+                "{:>3}. {:<8}{}",
+                replay.fullmove_number, "...", san
+            ));
+        }
+
+        replay.make_move(mv);
+    }
+
+    rows
+}
+
+/// Pieces each side has taken, from comparing the board with the start
+fn captured(board: &Board) -> [String; 2] {
+    let start = board.start_board();
+    let pieces = [
+        Piece::Queen,
+        Piece::Rook,
+        Piece::Bishop,
+        Piece::Knight,
+        Piece::Pawn,
+    ];
+
+    let count = |board: &Board, piece: Piece, player: i32| {
+        board
+            .grid
+            .iter()
+            .flatten()
+            .filter(|square| square.piece == piece && square.player == player)
+            .count()
+    };
+
+    let mut taken = [String::new(), String::new()];
+
+    for (idx, player) in [2, 1].iter().enumerate() {
+        for piece in pieces.iter() {
+            let missing =
+                count(&start, *piece, *player).saturating_sub(count(board, *piece, *player));
+            let letter = render::square_letter(&Square::from(*piece, *player));
+            taken[idx].extend(std::iter::repeat_n(letter, missing));
+        }
+    }
+
+    taken
+}
+
+fn draw(out: &mut Stdout, ctx: &Context, tui: &Tui) -> io::Result<()> {
+    let (width, height) = terminal::size()?;
+
+    // Terminals that support it show the whole redraw at once
+    queue!(
+        out,
+        terminal::BeginSynchronizedUpdate,
+        terminal::Clear(ClearType::All)
+    )?;
+
+    if width < MIN_WIDTH || height < MIN_HEIGHT {
+        let text = format!("Enlarge the terminal to {}x{}", MIN_WIDTH, MIN_HEIGHT);
+        queue!(
+            out,
+            cursor::MoveTo(0, 0),
+            Print(text),
+            terminal::EndSynchronizedUpdate
+        )?;
+        return out.flush();
+    }
+
+    let board = &ctx.board;
+    let reverse = ctx.reversed();
+    let files = Colour::White.paint("a b c d e f g h").to_string();
+
+    queue!(
+        out,
+        cursor::MoveTo(BOARD_LEFT + 2, BOARD_TOP),
+        Print(&files)
+    )?;
+
+    for yi in 0..8 {
+        let y = if reverse { yi } else { 7 - yi };
+        let cells: String = (0..8)
+            .map(|x| render::square_cell(board, x, y).to_string())
+            .collect();
+        let rank = Colour::White.paint((y + 1).to_string());
+
+        queue!(
+            out,
+            cursor::MoveTo(BOARD_LEFT, BOARD_TOP + 1 + yi as u16),
+            Print(format!("{} {} {}", rank, cells, rank))
+        )?;
+    }
+
+    queue!(
+        out,
+        cursor::MoveTo(BOARD_LEFT + 2, BOARD_TOP + 9),
+        Print(&files)
+    )?;
+
+    // Players, clocks and captured pieces
+    let names = match &ctx.names {
+        Some(names) => names.clone(),
+        None => [player_color(1), player_color(2)],
+    };
+
+    let taken = captured(board);
+    let panel_width = (width - PANEL_LEFT) as usize;
+
+    for player in 1..=2 {
+        let marker = if player == board.turn && !tui.finished {
+            ">"
+        } else {
+            " "
+        };
+
+        let line = format!(
+            "{} {:<20} {}  {}",
+            marker,
+            truncate(&names[(player - 1) as usize], 20),
+            clock::format_time(ctx.clock.shown(player)),
+            taken[(player - 1) as usize]
+        );
+
+        queue!(
+            out,
+            cursor::MoveTo(PANEL_LEFT, BOARD_TOP + player as u16 - 1),
+            Print(line)
+        )?;
+    }
+
+    if let Some(opening) = eco::classify(board) {
+        let name = format!("{} {}", opening.eco, opening.full_name());
+        queue!(
+            out,
+            cursor::MoveTo(PANEL_LEFT, BOARD_TOP + 3),
+            Print(truncate(&name, panel_width))
+        )?;
+    }
+
+    // Move list, following the latest move unless scrolled back
+    let rows = move_rows(board);
+    let visible = move_list_height();
+    let first = tui
+        .scroll
+        .unwrap_or_else(|| rows.len().saturating_sub(visible));
+
+    queue!(
+        out,
+        cursor::MoveTo(PANEL_LEFT, BOARD_TOP + 5),
+        Print(Colour::White.bold().paint("Moves"))
+    )?;
+
+    for (idx, row) in rows.iter().skip(first).take(visible).enumerate() {
+        queue!(
+            out,
+            cursor::MoveTo(PANEL_LEFT, BOARD_TOP + 6 + idx as u16),
+            Print(truncate(row, panel_width))
+        )?;
+    }
+
+    // Explorer under the board
+    if let Some(database) = &ctx.explorer {
+        let moves = database.explore(board);
+
+        for (idx, stats) in moves.iter().take(EXPLORER_ROWS).enumerate() {
+            let (white, draw, black) = stats.percentages();
+            let line = format!(
+                "{} {:<6} {:>4} {:>3.0}/{:.0}/{:.0}",
+                idx + 1,
+                board.to_san(&stats.mv),
+                stats.games,
+                white,
+                draw,
+                black
+            );
+
+            queue!(
+                out,
+                cursor::MoveTo(BOARD_LEFT, BOARD_TOP + 11 + idx as u16),
+                Print(line)
+            )?;
+        }
+    }
+
+    // Status, message and command line at the bottom
+    let status: ANSIString = if let Some(outcome) = board.get_outcome() {
+        Colour::Blue.paint(outcome.to_string())
+    } else if board.get_promoting().is_some() {
+        Colour::Green.paint("Promote: q, r, b or n")
+    } else if tui.finished {
+        Colour::Blue.paint("Game over")
+    } else if tui.waiting.is_none() && (ctx.interface.is_local() || ctx.player == board.turn) {
+        let check = if board.is_check() { ", check!" } else { "" };
+        ANSIString::from(format!("{} to move{}", player_color(board.turn), check))
+    } else {
+        ANSIString::from(format!("Waiting for {}...", player_color(board.turn)))
+    };
+
+    queue!(
+        out,
+        cursor::MoveTo(0, height - 3),
+        Print(status),
+        cursor::MoveTo(0, height - 2),
+        Print(&ctx.message)
+    )?;
+
+    let prompt = if tui.finished {
+        String::from("Press any key to leave")
+    } else {
+        format!("> {}", tui.input)
+    };
+
+    queue!(
+        out,
+        cursor::MoveTo(0, height - 1),
+        Print(truncate(&prompt, width as usize)),
+        terminal::EndSynchronizedUpdate
+    )?;
+
+    out.flush()
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
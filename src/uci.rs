@@ -0,0 +1,371 @@
+use crate::chess::{Board, Move, Variant};
+use crate::engine::{
+    mate_distance, Engine, Limits, SearchResult, Skill, DEFAULT_ELO, DEFAULT_HASH_MB, MAX_ELO,
+    MAX_THREADS, MIN_ELO,
+};
+use crate::syzygy::Tablebase;
+use std::io::{stdin, stdout, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const VARIANTS: [&str; 4] = ["chess", "crazyhouse", "antichess", "racingkings"];
+
+/// How often a finished search is checked for while no command comes in
+const POLL: Duration = Duration::from_millis(10);
+
+struct Uci {
+    engine: Option<Engine>,
+    search: Option<JoinHandle<Engine>>,
+    stop: Arc<AtomicBool>,
+    /// Commands that change the position or settings, held back until the
+    /// running search is over
+    pending: Vec<String>,
+    board: Board,
+    variant: Variant,
+    limit_strength: bool,
+    elo: i32,
+}
+
+/// Speaks the UCI protocol on stdin/stdout until `quit`
+pub fn run() {
+    let engine = Engine::new(DEFAULT_HASH_MB);
+
+    let mut uci = Uci {
+        stop: engine.stop_handle(),
+        engine: Some(engine),
+        search: None,
+        pending: Vec::new(),
+        board: Board::new(),
+        variant: Variant::Standard,
+        limit_strength: false,
+        elo: DEFAULT_ELO,
+    };
+
+    // Commands are read on a thread of their own, so the commands held
+    // back during a search run as soon as it ends
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    loop {
+        match receiver.recv_timeout(POLL) {
+            Ok(line) => {
+                if !uci.command(&line) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => uci.run_pending(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        stdout().flush().unwrap();
+    }
+}
+
+impl Uci {
+    /// Runs a command, returning false on `quit`
+    fn command(&mut self, line: &str) -> bool {
+        self.run_pending();
+
+        let args: Vec<&str> = line.split_whitespace().collect();
+
+        let changes_state = matches!(
+            args.first(),
+            Some(&"setoption") | Some(&"ucinewgame") | Some(&"position") | Some(&"go")
+        );
+
+        if changes_state && self.searching() {
+            self.pending.push(line.to_string());
+            return true;
+        }
+
+        match args.first() {
+            Some(&"uci") => {
+                println!("id name rust-chess");
+                println!("id author Andreas Kleven");
+                println!(
+                    "option name Hash type spin default {} min 1 max 4096",
+                    DEFAULT_HASH_MB
+                );
+                println!(
+                    "option name Threads type spin default 1 min 1 max {}",
+                    MAX_THREADS
+                );
+                println!(
+                    "option name UCI_Variant type combo default chess var {}",
+                    VARIANTS.join(" var ")
+                );
+                println!("option name SyzygyPath type string default <empty>");
+                println!("option name UCI_LimitStrength type check default false");
+                println!(
+                    "option name UCI_Elo type spin default {} min {} max {}",
+                    DEFAULT_ELO, MIN_ELO, MAX_ELO
+                );
+                println!("uciok");
+            }
+            Some(&"isready") => println!("readyok"),
+            Some(&"setoption") => self.set_option(&args[1..]),
+            Some(&"ucinewgame") => {
+                self.engine().clear();
+                self.board = Board::new_variant(self.variant);
+            }
+            Some(&"position") => match parse_position(&args[1..], self.variant) {
+                Some(board) => self.board = board,
+                None => println!("info string Invalid position"),
+            },
+            Some(&"go") => self.go(&args[1..]),
+            // Pondering ends with the move found so far, as with stop
+            Some(&"stop") | Some(&"ponderhit") => {
+                self.stop.store(true, Ordering::Relaxed);
+                self.run_pending();
+            }
+            Some(&"quit") => {
+                self.stop.store(true, Ordering::Relaxed);
+                self.wait();
+                return false;
+            }
+            _ => (),
+        }
+
+        true
+    }
+
+    fn searching(&self) -> bool {
+        self.search
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Runs the commands held back once the search is over
+    fn run_pending(&mut self) {
+        if self.searching() {
+            return;
+        }
+
+        self.wait();
+
+        for line in std::mem::take(&mut self.pending) {
+            self.command(&line);
+        }
+    }
+
+    /// Waits for a running search and takes the engine back
+    fn wait(&mut self) {
+        if let Some(handle) = self.search.take() {
+            match handle.join() {
+                Ok(engine) => self.engine = Some(engine),
+                Err(_) => {
+                    // The engine went down with the thread, so start over
+                    println!("info string The search failed, the engine was reset");
+                    let engine = Engine::new(DEFAULT_HASH_MB);
+                    self.stop = engine.stop_handle();
+                    self.engine = Some(engine);
+                    self.update_skill();
+                }
+            }
+        }
+    }
+
+    fn engine(&mut self) -> &mut Engine {
+        self.wait();
+        self.engine.as_mut().unwrap()
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        let name_idx = args.iter().position(|arg| *arg == "name");
+        let value_idx = args.iter().position(|arg| *arg == "value");
+
+        let (name, value) = match (name_idx, value_idx) {
+            (Some(n), Some(v)) if n < v => (args[n + 1..v].join(" "), args[v + 1..].join(" ")),
+            _ => return,
+        };
+
+        match name.to_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(mb) => self.engine().set_hash(mb),
+                Err(_) => println!("info string Invalid hash size '{}'", value),
+            },
+            "threads" => match value.parse::<usize>() {
+                Ok(threads) => self.engine().set_threads(threads),
+                Err(_) => println!("info string Invalid thread count '{}'", value),
+            },
+            "uci_variant" => match Variant::from(&value) {
+                Some(variant) => {
+                    self.variant = variant;
+                    self.board = Board::new_variant(variant);
+                }
+                None => println!("info string Unknown variant '{}'", value),
+            },
+            "syzygypath" => {
+                if value.is_empty() || value == "<empty>" {
+                    self.engine().set_tablebase(None);
+                    return;
+                }
+
+                match Tablebase::open(&value) {
+                    Ok(tablebase) => {
+                        println!(
+                            "info string Found {} tablebases with up to {} pieces",
+                            tablebase.len(),
+                            tablebase.max_pieces
+                        );
+                        self.engine().set_tablebase(Some(Arc::new(tablebase)));
+                    }
+                    Err(err) => println!("info string Could not read '{}': {}", value, err),
+                }
+            }
+            "uci_limitstrength" => match value.parse::<bool>() {
+                Ok(limit) => {
+                    self.limit_strength = limit;
+                    self.update_skill();
+                }
+                Err(_) => println!("info string Invalid value '{}'", value),
+            },
+            "uci_elo" => match value.parse::<i32>() {
+                Ok(elo) => {
+                    self.elo = elo.clamp(MIN_ELO, MAX_ELO);
+                    self.update_skill();
+                }
+                Err(_) => println!("info string Invalid Elo '{}'", value),
+            },
+            _ => println!("info string Unknown option '{}'", name),
+        }
+    }
+
+    fn update_skill(&mut self) {
+        let skill = if self.limit_strength {
+            Some(Skill::from_elo(self.elo))
+        } else {
+            None
+        };
+
+        self.engine().set_skill(skill);
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        let limits = parse_go(args, self.board.turn);
+        let board = self.board.clone();
+        self.wait();
+        let mut engine = self.engine.take().unwrap();
+
+        // Cleared here rather than in the thread, so a stop sent right
+        // after go is not lost
+        let stop = engine.stop_handle();
+        stop.store(false, Ordering::Relaxed);
+
+        self.search = Some(thread::spawn(move || {
+            let result = engine.search(&board, &limits, &mut print_info);
+
+            // The move is kept back until stop, however early the search ends
+            while limits.infinite && !stop.load(Ordering::Relaxed) {
+                thread::sleep(POLL);
+            }
+
+            println!("info hashfull {}", engine.hashfull());
+
+            match result.best_move {
+                Some(mv) => println!("bestmove {}", mv.to_uci()),
+                None => println!("bestmove 0000"),
+            }
+
+            stdout().flush().unwrap();
+            engine
+        }));
+    }
+}
+
+fn print_info(result: &SearchResult) {
+    let score = match mate_distance(result.score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+
+    let millis = result.time.as_millis().max(1);
+    let nps = result.nodes as u128 * 1000 / millis;
+    let pv: Vec<String> = result.pv.iter().map(|mv| mv.to_uci()).collect();
+
+    println!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        nps,
+        result.time.as_millis(),
+        pv.join(" ")
+    );
+
+    stdout().flush().unwrap();
+}
+
+/// `startpos` or `fen <fen>`, optionally followed by `moves <move>...`
+fn parse_position(args: &[&str], variant: Variant) -> Option<Board> {
+    let moves_idx = args
+        .iter()
+        .position(|arg| *arg == "moves")
+        .unwrap_or(args.len());
+
+    let mut board = match args.first() {
+        Some(&"startpos") => Board::new_variant(variant),
+        Some(&"fen") => Board::from_fen(&args[1..moves_idx].join(" "), variant)?,
+        _ => return None,
+    };
+
+    for move_str in args.iter().skip(moves_idx + 1) {
+        let mv = Move::from_uci(move_str)?;
+
+        if !board.do_move(&mv) {
+            return None;
+        }
+    }
+
+    Some(board)
+}
+
+fn parse_go(args: &[&str], turn: i32) -> Limits {
+    let mut limits = Limits::default();
+
+    let value = |name: &str| -> Option<u64> {
+        let idx = args.iter().position(|arg| *arg == name)?;
+        args.get(idx + 1)?.parse().ok()
+    };
+
+    // Pondering goes on until ponderhit or stop, whatever the clock says
+    if args.contains(&"infinite") || args.contains(&"ponder") {
+        limits.infinite = true;
+        return limits;
+    }
+
+    limits.depth = value("depth").map(|depth| depth as i32);
+    limits.nodes = value("nodes");
+
+    if let Some(movetime) = value("movetime") {
+        limits.time = Some(Duration::from_millis(movetime));
+    }
+
+    let (time, inc) = if turn == 1 {
+        (value("wtime"), value("winc"))
+    } else {
+        (value("btime"), value("binc"))
+    };
+
+    if let Some(time) = time {
+        let moves_to_go = value("movestogo").map(|moves| moves as u32);
+        let clock = Limits::clock(
+            Duration::from_millis(time),
+            Duration::from_millis(inc.unwrap_or(0)),
+            moves_to_go,
+        );
+        limits.time = clock.time;
+    }
+
+    limits
+}
@@ -0,0 +1,330 @@
+#!/usr/bin/env python3
+"""Builds KRvK.rtbw and KRvK.rtbz in the Syzygy format for the tablebase tests.
+
+The values come from a retrograde analysis of KRvK. They are stored with a
+fixed-length code per value and no symbol pairs, which is a valid but simple
+case of the format's compression. Run from this directory.
+"""
+
+import struct
+
+WDL_MAGIC = bytes([0x71, 0xE8, 0x23, 0x5D])
+DTZ_MAGIC = bytes([0xD7, 0x66, 0x0C, 0xA5])
+
+# Piece codes in table order: white king, white rook, black king
+PIECES = [6, 4, 14]
+
+# Placements of three unique pieces with the first in the a1-d1-d4 triangle
+TB_SIZE = 31332
+
+BLOCK_BITS = 6
+SPAN_BITS = 10
+
+
+def rank(sq):
+    return sq >> 3
+
+
+def file(sq):
+    return sq & 7
+
+
+def off_diagonal(sq):
+    return rank(sq) - file(sq)
+
+
+def adjacent(a, b):
+    return a != b and abs(rank(a) - rank(b)) <= 1 and abs(file(a) - file(b)) <= 1
+
+
+def king_moves(sq):
+    return [
+        to for to in range(64) if adjacent(sq, to)
+    ]
+
+
+KING_MOVES = [king_moves(sq) for sq in range(64)]
+
+
+def rook_rays(sq, blockers):
+    """Squares a rook on `sq` reaches, stopping on the first blocker"""
+    squares = []
+
+    for dr, df in ((1, 0), (-1, 0), (0, 1), (0, -1)):
+        r, f = rank(sq) + dr, file(sq) + df
+
+        while 0 <= r < 8 and 0 <= f < 8:
+            to = r * 8 + f
+            squares.append(to)
+
+            if to in blockers:
+                break
+
+            r, f = r + dr, f + df
+
+    return squares
+
+
+def legal(wk, wr, bk, white_to_move):
+    if len({wk, wr, bk}) < 3 or adjacent(wk, bk):
+        return False
+
+    # The side not to move cannot be in check
+    return not white_to_move or bk not in rook_rays(wr, {wk, bk})
+
+
+def black_moves(wk, wr, bk):
+    """Black king moves as (square, captures rook)"""
+    moves = []
+    attacked = set(rook_rays(wr, {wk}))
+
+    for to in KING_MOVES[bk]:
+        if adjacent(to, wk) or to == wk:
+            continue
+
+        if to == wr:
+            moves.append((to, True))
+        elif to not in attacked:
+            moves.append((to, False))
+
+    return moves
+
+
+def white_moves(wk, wr, bk):
+    """Positions with black to move reached by a white move"""
+    positions = []
+
+    for to in KING_MOVES[wk]:
+        if to != wr and not adjacent(to, bk) and to != bk:
+            positions.append((to, wr, bk))
+
+    for to in rook_rays(wr, {wk, bk}):
+        if to not in (wk, bk):
+            positions.append((wk, to, bk))
+
+    return positions
+
+
+def analyse():
+    """Moves to mate for white to move and for black to move, where black
+    to move is mated after that many white moves"""
+    white_win = {}
+    black_loss = {}
+    black_positions = []
+
+    for wk in range(64):
+        for wr in range(64):
+            for bk in range(64):
+                if not legal(wk, wr, bk, False):
+                    continue
+
+                moves = black_moves(wk, wr, bk)
+                in_check = bk in rook_rays(wr, {wk, bk})
+
+                if not moves and in_check:
+                    black_loss[(wk, wr, bk)] = 0
+                elif moves and not any(capture for _, capture in moves):
+                    black_positions.append((wk, wr, bk, [to for to, _ in moves]))
+
+    white_positions = [
+        (wk, wr, bk)
+        for wk in range(64)
+        for wr in range(64)
+        for bk in range(64)
+        if legal(wk, wr, bk, True)
+    ]
+
+    successors = {pos: white_moves(*pos) for pos in white_positions}
+    n = 1
+
+    while True:
+        found = False
+
+        for pos in white_positions:
+            if pos in white_win:
+                continue
+
+            if any(black_loss.get(next) == n - 1 for next in successors[pos]):
+                white_win[pos] = n
+                found = True
+
+        for wk, wr, bk, moves in black_positions:
+            if (wk, wr, bk) in black_loss:
+                continue
+
+            wins = [white_win.get((wk, wr, to)) for to in moves]
+
+            if all(win is not None for win in wins):
+                black_loss[(wk, wr, bk)] = max(wins)
+                found = True
+
+        if not found:
+            break
+
+        n += 1
+
+    return white_positions, white_win, black_loss
+
+
+def encode(wk, wr, bk):
+    """Table index of a position, mirrored into the a1-d1-d4 triangle"""
+    squares = [wk, wr, bk]
+
+    if file(squares[0]) > 3:
+        squares = [sq ^ 7 for sq in squares]
+
+    if rank(squares[0]) > 3:
+        squares = [sq ^ 56 for sq in squares]
+
+    for sq in squares:
+        if off_diagonal(sq) != 0:
+            if off_diagonal(sq) > 0:
+                squares = [file(s) * 8 + rank(s) for s in squares]
+            break
+
+    s0, s1, s2 = squares
+    i = int(s1 > s0)
+    j = int(s2 > s0) + int(s2 > s1)
+
+    triangle = {1: 0, 2: 1, 3: 2, 10: 3, 11: 4, 19: 5}
+    lower = [sq for sq in range(64) if off_diagonal(sq) < 0]
+
+    if off_diagonal(s0) != 0:
+        return (triangle[s0] * 63 + s1 - i) * 62 + s2 - j
+
+    if off_diagonal(s1) != 0:
+        return 6 * 63 * 62 + (rank(s0) * 28 + lower.index(s1)) * 62 + s2 - j
+
+    if off_diagonal(s2) != 0:
+        return (
+            6 * 63 * 62
+            + 4 * 28 * 62
+            + rank(s0) * 7 * 28
+            + (rank(s1) - i) * 28
+            + lower.index(s2)
+        )
+
+    return (
+        6 * 63 * 62
+        + 4 * 28 * 62
+        + 4 * 7 * 28
+        + rank(s0) * 7 * 6
+        + (rank(s1) - i) * 6
+        + rank(s2)
+        - j
+    )
+
+
+def fill(values, default):
+    """Values by index, checking that mirrored positions agree"""
+    table = [None] * TB_SIZE
+
+    for pos, value in values.items():
+        idx = encode(*pos)
+        assert table[idx] in (None, value), pos
+        table[idx] = value
+
+    return [default if value is None else value for value in table]
+
+
+def pairs_data(values):
+    """Sizes, symbols, sparse index, block lengths and blocks of one table"""
+    symbols = sorted(set(values))
+    code = {value: sym for sym, value in enumerate(symbols)}
+    length = max(1, (len(symbols) - 1).bit_length())
+    per_block = (8 << BLOCK_BITS) // length
+    block_count = -(-len(values) // per_block)
+
+    sizes = bytes([0, BLOCK_BITS, SPAN_BITS, 0])
+    sizes += struct.pack("<I", block_count)
+    sizes += bytes([length, length])
+    sizes += struct.pack("<HH", 0, len(symbols))
+
+    # Every symbol is a single value, marked by 0xFFF as its right half
+    for value in symbols:
+        sizes += bytes([value & 0xFF, (value >> 8) | 0xF0, 0xFF])
+
+    sizes += bytes(len(symbols) & 1)
+
+    span = 1 << SPAN_BITS
+    sparse = b""
+
+    for k in range(-(-len(values) // span)):
+        middle = k * span + span // 2
+        block = min(middle // per_block, block_count - 1)
+        sparse += struct.pack("<IH", block, middle - block * per_block)
+
+    lengths = b""
+    blocks = b""
+
+    for block in range(block_count):
+        chunk = values[block * per_block:(block + 1) * per_block]
+        lengths += struct.pack("<H", len(chunk) - 1)
+
+        bits = 0
+        for value in chunk:
+            bits = (bits << length) | code[value]
+
+        total = 8 << BLOCK_BITS
+        bits <<= total - len(chunk) * length
+        blocks += bits.to_bytes(total // 8, "big")
+
+    return sizes, sparse, lengths, blocks
+
+
+def header(magic, flags):
+    data = magic + bytes([flags, 0x00])
+    data += bytes((piece << 4) | piece for piece in PIECES)
+    return data + bytes(len(data) & 1)
+
+
+def write(path, data, tables):
+    """Appends the sizes, sparse indices, block lengths and 64 byte aligned
+    blocks of each table, in the order the format lays them out"""
+    parts = [pairs_data(values) for values in tables]
+
+    for sizes, _, _, _ in parts:
+        data += sizes
+
+    data += bytes(len(data) & 1)
+
+    for _, sparse, _, _ in parts:
+        data += sparse
+
+    for _, _, lengths, _ in parts:
+        data += lengths
+
+    for _, _, _, blocks in parts:
+        data += bytes(-len(data) % 64)
+        data += blocks
+
+    with open(path, "wb") as f:
+        f.write(data)
+
+
+def main():
+    white_positions, white_win, black_loss = analyse()
+
+    assert all(pos in white_win for pos in white_positions)
+    assert max(white_win.values()) == 16
+
+    # WDL values are stored plus two: 0 for a loss, 2 draw and 4 win
+    black_wdl = {}
+
+    for wk in range(64):
+        for wr in range(64):
+            for bk in range(64):
+                if legal(wk, wr, bk, False):
+                    pos = (wk, wr, bk)
+                    black_wdl[pos] = 0 if pos in black_loss else 2
+
+    wdl = [fill({pos: 4 for pos in white_positions}, 4), fill(black_wdl, 0)]
+    write("KRvK.rtbw", header(WDL_MAGIC, 1), wdl)
+
+    # DTZ stores white to move only, in full moves minus one
+    dtz = [fill({pos: n - 1 for pos, n in white_win.items()}, 0)]
+    write("KRvK.rtbz", header(DTZ_MAGIC, 0), dtz)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,88 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Engine {
+    fn start() -> Engine {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rust-chess"))
+            .arg("uci")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let _ = sender.send(line);
+            }
+        });
+
+        Engine {
+            child,
+            stdin,
+            lines,
+        }
+    }
+
+    fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{}", command).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    /// Lines written within the given time
+    fn read_for(&self, time: Duration) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        while let Ok(line) = self.lines.recv_timeout(time) {
+            lines.push(line);
+        }
+
+        lines
+    }
+}
+
+fn bestmoves(lines: &[String]) -> usize {
+    lines
+        .iter()
+        .filter(|line| line.starts_with("bestmove"))
+        .count()
+}
+
+#[test]
+fn go_infinite_answers_only_after_stop() {
+    let mut engine = Engine::start();
+
+    // Mate in one, so the search itself is over almost at once
+    engine.send("position fen 6k1/5ppp/8/8/8/8/8/R6K w - - 0 1");
+    engine.send("go infinite");
+
+    let before = engine.read_for(Duration::from_millis(500));
+    assert_eq!(bestmoves(&before), 0, "answered before stop: {:?}", before);
+
+    engine.send("stop");
+    let after = engine.read_for(Duration::from_millis(500));
+    assert_eq!(bestmoves(&after), 1, "{:?}", after);
+    assert!(
+        after.contains(&String::from("bestmove a1a8")),
+        "{:?}",
+        after
+    );
+
+    engine.send("quit");
+    let rest = engine.read_for(Duration::from_millis(200));
+    assert_eq!(bestmoves(&rest), 0, "{:?}", rest);
+
+    engine.child.wait().unwrap();
+}